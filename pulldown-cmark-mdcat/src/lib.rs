@@ -49,7 +49,7 @@ use url::Url;
 pub use crate::resources::ResourceUrlHandler;
 pub use crate::terminal::capabilities::TerminalCapabilities;
 pub use crate::terminal::{TerminalProgram, TerminalSize};
-pub use crate::theme::Theme;
+pub use crate::theme::{RuleWidth, SyntaxTheme, SyntaxThemes, Theme, DEFAULT_RULE_GLYPH};
 
 mod references;
 pub mod resources;
@@ -59,16 +59,84 @@ mod theme;
 mod render;
 
 /// Settings for markdown rendering.
+///
+/// Create settings with [`Settings::new`], and change optional settings with the `with_`
+/// methods.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct Settings<'a> {
     /// Capabilities of the terminal mdcat writes to.
     pub terminal_capabilities: TerminalCapabilities,
     /// The size of the terminal mdcat writes to.
     pub terminal_size: TerminalSize,
+    /// The maximum width of paragraph text, in columns.
+    ///
+    /// Wrap paragraphs, headings and other inline text at this width, but still use the full
+    /// width of `terminal_size` for tables and rules.  If `None` wrap text at the terminal width.
+    pub prose_width: Option<u16>,
+    /// The number of columns to indent the text of list items by, per level of nesting.
+    ///
+    /// Never indent by less than the width of the list marker, e.g. `1. `.  If `None` indent by
+    /// the width of the list marker.
+    pub list_indent: Option<u16>,
     /// Syntax set for syntax highlighting of code blocks.
     pub syntax_set: &'a SyntaxSet,
     /// Colour theme for mdcat
     pub theme: Theme,
+    /// Syntax themes for highlighted code blocks.
+    pub syntax_themes: SyntaxThemes,
+}
+
+impl<'a> Settings<'a> {
+    /// Create settings to render on a terminal with the given capabilities and size.
+    ///
+    /// Highlight code with `syntax_set`, and style text with `theme`.  Wrap text at the full width
+    /// of the terminal, indent lists by the width of their markers, and use the default syntax
+    /// themes.
+    pub fn new(
+        terminal_capabilities: TerminalCapabilities,
+        terminal_size: TerminalSize,
+        syntax_set: &'a SyntaxSet,
+        theme: Theme,
+    ) -> Self {
+        Self {
+            terminal_capabilities,
+            terminal_size,
+            prose_width: None,
+            list_indent: None,
+            syntax_set,
+            theme,
+            syntax_themes: SyntaxThemes::default(),
+        }
+    }
+
+    /// Wrap paragraph text at `prose_width`, see [`Settings::prose_width`].
+    pub fn with_prose_width(mut self, prose_width: Option<u16>) -> Self {
+        self.prose_width = prose_width;
+        self
+    }
+
+    /// Indent list items by `list_indent`, see [`Settings::list_indent`].
+    pub fn with_list_indent(mut self, list_indent: Option<u16>) -> Self {
+        self.list_indent = list_indent;
+        self
+    }
+
+    /// Highlight code blocks with `syntax_themes`.
+    pub fn with_syntax_themes(mut self, syntax_themes: SyntaxThemes) -> Self {
+        self.syntax_themes = syntax_themes;
+        self
+    }
+
+    /// The number of columns to wrap paragraph text at.
+    ///
+    /// Use the prose width if any, but never exceed the number of columns of the terminal.
+    pub fn prose_columns(&self) -> u16 {
+        self.prose_width
+            .map_or(self.terminal_size.columns, |width| {
+                width.min(self.terminal_size.columns)
+            })
+    }
 }
 
 /// The environment to render markdown in.
@@ -175,12 +243,12 @@ mod tests {
     fn render_string_dumb(markup: &str) -> Result<String> {
         render_string(
             markup,
-            &Settings {
-                syntax_set: &SyntaxSet::default(),
-                terminal_capabilities: TerminalProgram::Dumb.capabilities(),
-                terminal_size: TerminalSize::default(),
-                theme: Theme::default(),
-            },
+            &Settings::new(
+                TerminalProgram::Dumb.capabilities(),
+                TerminalSize::default(),
+                &SyntaxSet::default(),
+                Theme::default(),
+            ),
         )
     }
 
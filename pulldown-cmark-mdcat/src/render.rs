@@ -37,7 +37,7 @@ use write::*;
 
 use crate::render::data::{CurrentLine, CurrentTable};
 use crate::render::state::MarginControl::NoMargin;
-use crate::terminal::capabilities::StyleCapability;
+use crate::terminal::capabilities::LinkCapability;
 use crate::terminal::osc::{clear_link, set_link_url};
 pub use data::StateData;
 pub use state::State;
@@ -164,6 +164,15 @@ pub fn write_event<'a, W: Write>(
                 .and_data(data)
                 .ok()
         }
+        (TopLevel(attrs), Start(DefinitionList)) => {
+            if attrs.margin_before != NoMargin {
+                writeln!(writer)?;
+            }
+            State::stack_onto(TopLevelAttrs::margin_before())
+                .current(Inline(DefinitionItem(StartItem), InlineAttrs::default()))
+                .and_data(data)
+                .ok()
+        }
         (TopLevel(attrs), Start(Table(alignments))) => {
             if attrs.margin_before != NoMargin {
                 writeln!(writer)?;
@@ -271,6 +280,17 @@ pub fn write_event<'a, W: Write>(
                 .and_data(data)
                 .ok()
         }
+        (Stacked(stack, StyledBlock(attrs)), Start(DefinitionList)) => {
+            if attrs.margin_before != NoMargin {
+                writeln!(writer)?;
+            }
+            let inline = InlineAttrs::from(&attrs);
+            stack
+                .push(attrs.with_margin_before().into())
+                .current(Inline(DefinitionItem(StartItem), inline))
+                .and_data(data)
+                .ok()
+        }
         (Stacked(stack, StyledBlock(attrs)), Start(CodeBlock(kind))) => {
             if attrs.margin_before != NoMargin {
                 writeln!(writer)?;
@@ -293,16 +313,13 @@ pub fn write_event<'a, W: Write>(
                 writeln!(writer)?;
             }
             write_indent(writer, indent)?;
-            let indent = match kind {
-                ListItemKind::Unordered => {
-                    write!(writer, "\u{2022} ")?;
-                    indent + 2
-                }
-                ListItemKind::Ordered(no) => {
-                    write!(writer, "{no:>2}. ")?;
-                    indent + 4
-                }
+            match kind {
+                ListItemKind::Unordered => write!(writer, "\u{2022} ")?,
+                ListItemKind::Ordered(no) => write!(writer, "{no:>2}. ")?,
             };
+            let item_indent = kind.indent(settings.list_indent);
+            write_indent(writer, item_indent - kind.marker_width())?;
+            let indent = indent + item_indent;
             stack
                 .current(Inline(
                     ListItem(kind, StartItem),
@@ -314,7 +331,35 @@ pub fn write_event<'a, W: Write>(
                 }))
                 .ok()
         }
-        (Stacked(stack, Inline(ListItem(kind, state), attrs)), Start(Paragraph)) => {
+        // Write the indent of a definition before anything but blocks on their own lines
+        (Stacked(stack, Inline(DefinitionStart, attrs)), event)
+            if !matches!(
+                event,
+                Start(List(_) | CodeBlock(_) | BlockQuote(_) | DefinitionList)
+                    | Rule
+                    | End(TagEnd::DefinitionListDefinition)
+            ) =>
+        {
+            write_indent(writer, attrs.indent)?;
+            let data = data.current_line(CurrentLine {
+                length: attrs.indent,
+                trailing_space: None,
+            });
+            let state = stack.current(Inline(DefinitionItem(StartItem), attrs));
+            write_event(
+                writer,
+                settings,
+                environment,
+                resource_handler,
+                state,
+                data,
+                event,
+            )
+        }
+        (
+            Stacked(stack, Inline(item @ (ListItem(_, state) | DefinitionItem(state)), attrs)),
+            Start(Paragraph),
+        ) => {
             if state != StartItem {
                 // Write margin, unless we're at the start of the list item in which case the first line of the
                 // paragraph should go right beside the item bullet.
@@ -322,12 +367,15 @@ pub fn write_event<'a, W: Write>(
                 write_indent(writer, attrs.indent)?;
             }
             stack
-                .push(Inline(ListItem(kind, ItemBlock), attrs.clone()))
+                .push(Inline(item.with_item_state(ItemBlock), attrs.clone()))
                 .current(Inline(InlineText, attrs))
                 .and_data(data)
                 .ok()
         }
-        (Stacked(stack, Inline(ListItem(kind, state), attrs)), Start(Tag::HtmlBlock)) => {
+        (
+            Stacked(stack, Inline(item @ (ListItem(_, state) | DefinitionItem(state)), attrs)),
+            Start(Tag::HtmlBlock),
+        ) => {
             let InlineAttrs { indent, style, .. } = attrs;
             let initial_indent = if state == StartItem {
                 0
@@ -336,7 +384,7 @@ pub fn write_event<'a, W: Write>(
                 indent
             };
             stack
-                .push(Inline(ListItem(kind, ItemBlock), attrs))
+                .push(Inline(item.with_item_state(ItemBlock), attrs))
                 .current(
                     HtmlBlockAttrs {
                         style: settings.theme.html_block_style.on_top_of(&style),
@@ -348,17 +396,33 @@ pub fn write_event<'a, W: Write>(
                 .and_data(data)
                 .ok()
         }
-        (Stacked(stack, Inline(ListItem(kind, _), attrs)), Start(CodeBlock(ck))) => {
-            writeln!(writer)?;
+        (
+            Stacked(
+                stack,
+                Inline(item @ (ListItem(..) | DefinitionItem(_) | DefinitionStart), attrs),
+            ),
+            Start(CodeBlock(ck)),
+        ) => {
+            if item != DefinitionStart {
+                writeln!(writer)?;
+            }
             let InlineAttrs { indent, style, .. } = attrs;
             stack
-                .push(Inline(ListItem(kind, ItemBlock), attrs))
+                .push(Inline(item.with_item_state(ItemBlock), attrs))
                 .current(write_start_code_block(writer, settings, indent, style, ck)?)
                 .and_data(data)
                 .ok()
         }
-        (Stacked(stack, Inline(ListItem(kind, _), attrs)), Rule) => {
-            writeln!(writer)?;
+        (
+            Stacked(
+                stack,
+                Inline(item @ (ListItem(..) | DefinitionItem(_) | DefinitionStart), attrs),
+            ),
+            Rule,
+        ) => {
+            if item != DefinitionStart {
+                writeln!(writer)?;
+            }
             write_indent(writer, attrs.indent)?;
             write_rule(
                 writer,
@@ -368,11 +432,14 @@ pub fn write_event<'a, W: Write>(
             )?;
             writeln!(writer)?;
             stack
-                .current(Inline(ListItem(kind, ItemBlock), attrs))
+                .current(Inline(item.with_item_state(ItemBlock), attrs))
                 .and_data(data)
                 .ok()
         }
-        (Stacked(stack, Inline(ListItem(kind, state), attrs)), Start(Heading { level, .. })) => {
+        (
+            Stacked(stack, Inline(item @ (ListItem(_, state) | DefinitionItem(state)), attrs)),
+            Start(Heading { level, .. }),
+        ) => {
             if state != StartItem {
                 writeln!(writer)?;
                 write_indent(writer, attrs.indent)?;
@@ -380,7 +447,7 @@ pub fn write_event<'a, W: Write>(
             // We deliberately don't mark headings which aren't top-level.
             let style = attrs.style;
             stack
-                .push(Inline(ListItem(kind, ItemBlock), attrs))
+                .push(Inline(item.with_item_state(ItemBlock), attrs))
                 .current(write_start_heading(
                     writer,
                     &settings.terminal_capabilities,
@@ -390,24 +457,56 @@ pub fn write_event<'a, W: Write>(
                 .and_data(data)
                 .ok()
         }
-        (Stacked(stack, Inline(ListItem(kind, _), attrs)), Start(List(start))) => {
-            writeln!(writer)?;
+        (
+            Stacked(
+                stack,
+                Inline(item @ (ListItem(..) | DefinitionItem(_) | DefinitionStart), attrs),
+            ),
+            Start(DefinitionList),
+        ) => {
+            if item != DefinitionStart {
+                writeln!(writer)?;
+            }
+            stack
+                .push(Inline(item.with_item_state(ItemBlock), attrs.clone()))
+                .current(Inline(DefinitionItem(StartItem), attrs))
+                .and_data(data)
+                .ok()
+        }
+        (
+            Stacked(
+                stack,
+                Inline(item @ (ListItem(..) | DefinitionItem(_) | DefinitionStart), attrs),
+            ),
+            Start(List(start)),
+        ) => {
+            if item != DefinitionStart {
+                writeln!(writer)?;
+            }
             let nested_kind = start.map_or(ListItemKind::Unordered, |start| {
                 ListItemKind::Ordered(start)
             });
             stack
-                .push(Inline(ListItem(kind, ItemBlock), attrs.clone()))
+                .push(Inline(item.with_item_state(ItemBlock), attrs.clone()))
                 .current(Inline(ListItem(nested_kind, StartItem), attrs))
                 .and_data(data)
                 .ok()
         }
-        (Stacked(stack, Inline(ListItem(kind, _), attrs)), Start(BlockQuote(_))) => {
-            writeln!(writer)?;
+        (
+            Stacked(
+                stack,
+                Inline(item @ (ListItem(..) | DefinitionItem(_) | DefinitionStart), attrs),
+            ),
+            Start(BlockQuote(_)),
+        ) => {
+            if item != DefinitionStart {
+                writeln!(writer)?;
+            }
             let block_quote = StyledBlockAttrs::from(&attrs)
                 .without_margin_before()
                 .block_quote();
             stack
-                .push(Inline(ListItem(kind, ItemBlock), attrs))
+                .push(Inline(item.with_item_state(ItemBlock), attrs))
                 .current(block_quote.into())
                 .and_data(data)
                 .ok()
@@ -422,9 +521,10 @@ pub fn write_event<'a, W: Write>(
                 data
             };
             // Decrease indent back to the level where we can write the next item bullet, and increment the list item number.
-            let (indent, kind) = match kind {
-                ListItemKind::Unordered => (indent - 2, ListItemKind::Unordered),
-                ListItemKind::Ordered(no) => (indent - 4, ListItemKind::Ordered(no + 1)),
+            let indent = indent - kind.indent(settings.list_indent);
+            let kind = match kind {
+                ListItemKind::Unordered => ListItemKind::Unordered,
+                ListItemKind::Ordered(no) => ListItemKind::Ordered(no + 1),
             };
             stack
                 .current(Inline(ListItem(kind, state), InlineAttrs { style, indent }))
@@ -432,6 +532,85 @@ pub fn write_event<'a, W: Write>(
                 .ok()
         }
 
+        // Definition lists: Terms in bold, and definitions indented below.  Like in lists, add
+        // margins between terms and definitions only if they contain paragraphs.
+        (Stacked(stack, Inline(DefinitionItem(state), attrs)), Start(DefinitionListTitle)) => {
+            if state == ItemBlock {
+                // Add margin
+                writeln!(writer)?;
+            }
+            write_indent(writer, attrs.indent)?;
+            let InlineAttrs { indent, style } = attrs;
+            stack
+                .push(Inline(DefinitionItem(StartItem), attrs))
+                .current(Inline(
+                    InlineText,
+                    InlineAttrs {
+                        style: style.bold(),
+                        indent,
+                    },
+                ))
+                .and_data(data.current_line(CurrentLine {
+                    length: indent,
+                    trailing_space: None,
+                }))
+                .ok()
+        }
+        (Stacked(stack, Inline(DefinitionItem(state), attrs)), Start(DefinitionListDefinition)) => {
+            if state == ItemBlock {
+                // Add margin
+                writeln!(writer)?;
+            }
+            let InlineAttrs { indent, style } = attrs;
+            stack
+                .current(Inline(
+                    DefinitionStart,
+                    InlineAttrs {
+                        style,
+                        indent: indent + 4,
+                    },
+                ))
+                .and_data(data.current_line(CurrentLine::empty()))
+                .ok()
+        }
+        // An empty definition
+        (Stacked(stack, Inline(DefinitionStart, attrs)), End(TagEnd::DefinitionListDefinition)) => {
+            let InlineAttrs { indent, style } = attrs;
+            stack
+                .current(Inline(
+                    DefinitionItem(StartItem),
+                    InlineAttrs {
+                        style,
+                        indent: indent - 4,
+                    },
+                ))
+                .and_data(data)
+                .ok()
+        }
+        (
+            Stacked(stack, Inline(DefinitionItem(state), attrs)),
+            End(TagEnd::DefinitionListDefinition),
+        ) => {
+            let InlineAttrs { indent, style } = attrs;
+            let data = if state != ItemBlock {
+                // End the inline text of this definition
+                writeln!(writer)?;
+                data.current_line(CurrentLine::empty())
+            } else {
+                data
+            };
+            stack
+                .current(Inline(
+                    DefinitionItem(state),
+                    InlineAttrs {
+                        style,
+                        indent: indent - 4,
+                    },
+                ))
+                .and_data(data)
+                .ok()
+        }
+
         // Literal blocks without highlighting
         (Stacked(stack, LiteralBlock(attrs)), Text(text)) => {
             let LiteralBlockAttrs { indent, style, .. } = attrs;
@@ -502,6 +681,7 @@ pub fn write_event<'a, W: Write>(
                     .expect("syntect parsing shouldn't fail in mdcat");
                 highlighting::write_as_ansi(
                     writer,
+                    attrs.syntax_theme,
                     HighlightIterator::new(&mut attrs.highlight_state, &ops, line, highlighter()),
                 )?;
                 if text.ends_with('\n') {
@@ -559,7 +739,7 @@ pub fn write_event<'a, W: Write>(
                 writer,
                 &settings.terminal_capabilities,
                 &settings.theme.code_style.on_top_of(&attrs.style),
-                settings.terminal_size.columns,
+                settings.prose_columns(),
                 attrs.indent,
                 data.current_line,
                 code,
@@ -571,12 +751,30 @@ pub fn write_event<'a, W: Write>(
             Ok(stack.current(Inline(state, attrs)).and_data(data))
         }
 
+        (Stacked(stack, Inline(state, attrs)), InlineMath(math) | DisplayMath(math)) => {
+            let current_line = write_styled_and_wrapped(
+                writer,
+                &settings.terminal_capabilities,
+                &settings.theme.math_style.on_top_of(&attrs.style),
+                settings.prose_columns(),
+                attrs.indent,
+                data.current_line,
+                // Display math often starts and ends with a newline
+                math.trim(),
+            )?;
+            let data = StateData {
+                current_line,
+                ..data
+            };
+            Ok(stack.current(Inline(state, attrs)).and_data(data))
+        }
+
         (Stacked(stack, Inline(state, attrs)), InlineHtml(html)) => {
             let current_line = write_styled_and_wrapped(
                 writer,
                 &settings.terminal_capabilities,
                 &settings.theme.inline_html_style.on_top_of(&attrs.style),
-                settings.terminal_size.columns,
+                settings.prose_columns(),
                 attrs.indent,
                 data.current_line,
                 html,
@@ -623,20 +821,26 @@ pub fn write_event<'a, W: Write>(
                 .and_data(data.current_line(CurrentLine::empty())))
         }
         // Inline text
-        (Stacked(stack, Inline(ListItem(kind, ItemBlock), attrs)), Text(text)) => {
+        (
+            Stacked(
+                stack,
+                Inline(item @ (ListItem(_, ItemBlock) | DefinitionItem(ItemBlock)), attrs),
+            ),
+            Text(text),
+        ) => {
             // Fresh text after a new block, so indent again.
             write_indent(writer, attrs.indent)?;
             let current_line = write_styled_and_wrapped(
                 writer,
                 &settings.terminal_capabilities,
                 &attrs.style,
-                settings.terminal_size.columns,
+                settings.prose_columns(),
                 attrs.indent,
                 data.current_line,
                 text,
             )?;
             Ok(stack
-                .current(Inline(ListItem(kind, ItemText), attrs))
+                .current(Inline(item.with_item_state(ItemText), attrs))
                 .and_data(StateData {
                     current_line,
                     ..data
@@ -652,7 +856,7 @@ pub fn write_event<'a, W: Write>(
                 writer,
                 &settings.terminal_capabilities,
                 &attrs.style,
-                settings.terminal_size.columns,
+                settings.prose_columns(),
                 attrs.indent,
                 data.current_line,
                 text,
@@ -669,6 +873,12 @@ pub fn write_event<'a, W: Write>(
                 .pop()
                 .and_data(data.current_line(CurrentLine::empty())))
         }
+        (Stacked(stack, Inline(_, _)), End(TagEnd::DefinitionListTitle)) => {
+            writeln!(writer)?;
+            Ok(stack
+                .pop()
+                .and_data(data.current_line(CurrentLine::empty())))
+        }
         (Stacked(stack, Inline(_, _)), End(TagEnd::Heading(_))) => {
             writeln!(writer)?;
             Ok(stack
@@ -689,18 +899,14 @@ pub fn write_event<'a, W: Write>(
                 ..
             }),
         ) => {
-            let maybe_link = settings
-                .terminal_capabilities
-                .style
-                .filter(|s| *s == StyleCapability::Ansi)
-                .and_then(|_| {
-                    if let LinkType::Email = link_type {
-                        // Turn email autolinks (i.e. <foo@example.com>) into mailto inline links
-                        Url::parse(&format!("mailto:{dest_url}")).ok()
-                    } else {
-                        environment.resolve_reference(&dest_url)
-                    }
-                });
+            let maybe_link = settings.terminal_capabilities.links.and_then(|_| {
+                if let LinkType::Email = link_type {
+                    // Turn email autolinks (i.e. <foo@example.com>) into mailto inline links
+                    Url::parse(&format!("mailto:{dest_url}")).ok()
+                } else {
+                    environment.resolve_reference(&dest_url)
+                }
+            });
 
             let (link_state, data) = match maybe_link {
                 None => (
@@ -791,8 +997,8 @@ pub fn write_event<'a, W: Write>(
                         None
                     } else {
                         event!(Level::INFO, url = %url, "Terminal does not support images, rendering image as link");
-                        match settings.terminal_capabilities.style {
-                            Some(StyleCapability::Ansi) => {
+                        match settings.terminal_capabilities.links {
+                            Some(LinkCapability::Osc8) => {
                                 set_link_url(writer, url, &environment.hostname)?;
                                 Some(Inline(
                                     InlineLink,
@@ -900,7 +1106,10 @@ pub fn write_event<'a, W: Write>(
             };
             Stacked(stack, TableBlock).and_data(data).ok()
         }
-        (Stacked(stack, TableBlock), Text(text)) | (Stacked(stack, TableBlock), Code(text)) => {
+        (Stacked(stack, TableBlock), Text(text))
+        | (Stacked(stack, TableBlock), Code(text))
+        | (Stacked(stack, TableBlock), InlineMath(text))
+        | (Stacked(stack, TableBlock), DisplayMath(text)) => {
             let current_table = data.current_table.push_fragment(text);
             let data = StateData {
                 current_table,
@@ -939,9 +1148,15 @@ pub fn write_event<'a, W: Write>(
         }
 
         // Unconditional returns to previous states
-        (Stacked(stack, _), End(TagEnd::BlockQuote(_) | TagEnd::List(_) | TagEnd::HtmlBlock)) => {
-            stack.pop().and_data(data).ok()
-        }
+        (
+            Stacked(stack, _),
+            End(
+                TagEnd::BlockQuote(_)
+                | TagEnd::List(_)
+                | TagEnd::DefinitionList
+                | TagEnd::HtmlBlock,
+            ),
+        ) => stack.pop().and_data(data).ok(),
 
         // Impossible events
         (s, e) => panic!("Event {e:?} impossible in state {s:?}"),
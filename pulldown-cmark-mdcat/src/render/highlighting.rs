@@ -13,6 +13,8 @@ use std::{
 };
 use syntect::highlighting::{FontStyle, Highlighter, Style, Theme};
 
+use crate::theme::SyntaxTheme;
+
 static SOLARIZED_DARK_DUMP: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/theme.dump"));
 static THEME: OnceLock<Theme> = OnceLock::new();
 static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
@@ -40,8 +42,11 @@ pub fn highlighter() -> &'static Highlighter<'static> {
 ///
 /// Furthermore we completely ignore any background colour settings, to avoid
 /// conflicts with the terminal colour themes.
+///
+/// The syntax `theme` finally picks the colour for each ANSI colour.
 pub fn write_as_ansi<'a, W: Write, I: Iterator<Item = (Style, &'a str)>>(
     writer: &mut W,
+    theme: SyntaxTheme,
     regions: I,
 ) -> Result<()> {
     for (style, text) in regions {
@@ -59,14 +64,14 @@ pub fn write_as_ansi<'a, W: Write, I: Iterator<Item = (Style, &'a str)>>(
             | (0x93, 0xa1, 0xa1)
             | (0xee, 0xe8, 0xd5)
             | (0xfd, 0xf6, 0xe3) => None,
-            (0xb5, 0x89, 0x00) => Some(AnsiColor::Yellow.into()),
-            (0xcb, 0x4b, 0x16) => Some(AnsiColor::BrightRed.into()),
-            (0xdc, 0x32, 0x2f) => Some(AnsiColor::Red.into()),
-            (0xd3, 0x36, 0x82) => Some(AnsiColor::Magenta.into()),
-            (0x6c, 0x71, 0xc4) => Some(AnsiColor::BrightMagenta.into()),
-            (0x26, 0x8b, 0xd2) => Some(AnsiColor::Blue.into()),
-            (0x2a, 0xa1, 0x98) => Some(AnsiColor::Cyan.into()),
-            (0x85, 0x99, 0x00) => Some(AnsiColor::Green.into()),
+            (0xb5, 0x89, 0x00) => theme.colour(AnsiColor::Yellow),
+            (0xcb, 0x4b, 0x16) => theme.colour(AnsiColor::BrightRed),
+            (0xdc, 0x32, 0x2f) => theme.colour(AnsiColor::Red),
+            (0xd3, 0x36, 0x82) => theme.colour(AnsiColor::Magenta),
+            (0x6c, 0x71, 0xc4) => theme.colour(AnsiColor::BrightMagenta),
+            (0x26, 0x8b, 0xd2) => theme.colour(AnsiColor::Blue),
+            (0x2a, 0xa1, 0x98) => theme.colour(AnsiColor::Cyan),
+            (0x85, 0x99, 0x00) => theme.colour(AnsiColor::Green),
             (r, g, b) => panic!("Unexpected RGB colour: #{r:2>0x}{g:2>0x}{b:2>0x}"),
         };
         let font = style.font_style;
@@ -9,6 +9,8 @@ use std::borrow::Borrow;
 use syntect::highlighting::HighlightState;
 use syntect::parsing::ParseState;
 
+use crate::theme::SyntaxTheme;
+
 /// Whether to add a margin.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub(super) enum MarginControl {
@@ -54,6 +56,25 @@ pub enum ListItemKind {
     Ordered(u64),
 }
 
+impl ListItemKind {
+    /// The width of the marker of this kind of list item, i.e. `• ` or ` 1. `.
+    pub fn marker_width(self) -> u16 {
+        match self {
+            ListItemKind::Unordered => 2,
+            ListItemKind::Ordered(_) => 4,
+        }
+    }
+
+    /// The number of columns to indent the text of this kind of list item by.
+    ///
+    /// Indent by `list_indent`, but never less than the width of the marker.
+    pub fn indent(self, list_indent: Option<u16>) -> u16 {
+        list_indent.map_or(self.marker_width(), |indent| {
+            indent.max(self.marker_width())
+        })
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ListItemState {
     /// The first line after the list bullet/
@@ -89,6 +110,31 @@ pub enum InlineState {
     /// List item text carries the type of the current item as well as a "state" which we use for
     /// newline control when ending and starting list items.
     ListItem(ListItemKind, ListItemState),
+    /// Terms and definitions of a definition list.
+    ///
+    /// Like a list item, but without a marker; definitions are indented instead.
+    DefinitionItem(ListItemState),
+    /// The start of a definition, before its indent.
+    ///
+    /// Definitions have no marker, so nested blocks right at the start of a definition go on the
+    /// line below the term.  Defer the indent until we know that the definition starts with
+    /// inline text, to avoid a line with only whitespace.
+    DefinitionStart,
+}
+
+impl InlineState {
+    /// Change the state of a list item or definition to `state`.
+    ///
+    /// Leave all other states alone.
+    pub fn with_item_state(self, state: ListItemState) -> Self {
+        match self {
+            InlineState::ListItem(kind, _) => InlineState::ListItem(kind, state),
+            InlineState::DefinitionItem(_) | InlineState::DefinitionStart => {
+                InlineState::DefinitionItem(state)
+            }
+            other => other,
+        }
+    }
 }
 
 /// State attributes for styled blocks.
@@ -157,6 +203,8 @@ where
 pub struct HighlightBlockAttrs {
     pub(super) parse_state: ParseState,
     pub(super) highlight_state: HighlightState,
+    /// The syntax theme for the language of this code block.
+    pub(super) syntax_theme: SyntaxTheme,
     /// The indentation to apply to this code block.
     ///
     /// Code blocks in nested blocks such as quotes, lists, etc. gain an additional indent to align
@@ -19,10 +19,12 @@ use crate::references::*;
 use crate::render::data::{CurrentLine, CurrentTable, LinkReferenceDefinition, TableCell};
 use crate::render::highlighting::highlighter;
 use crate::render::state::*;
-use crate::terminal::capabilities::{MarkCapability, StyleCapability, TerminalCapabilities};
+use crate::terminal::capabilities::{
+    LinkCapability, MarkCapability, StyleCapability, TerminalCapabilities,
+};
 use crate::terminal::osc::{clear_link, set_link_url};
 use crate::terminal::TerminalSize;
-use crate::theme::CombineStyle;
+use crate::theme::{CombineStyle, RuleWidth};
 use crate::Theme;
 use crate::{Environment, Settings};
 
@@ -227,7 +229,13 @@ pub fn write_rule<W: Write>(
     theme: &Theme,
     length: u16,
 ) -> std::io::Result<()> {
-    let rule = "\u{2550}".repeat(length as usize);
+    let columns = match theme.rule_width {
+        RuleWidth::Full => length,
+        RuleWidth::Half => length / 2,
+    };
+    // Wide glyphs take more than one column
+    let glyph = theme.rule_glyph.to_string();
+    let rule = glyph.repeat(usize::from(columns) / display_width(&glyph).max(1));
     write_styled(
         writer,
         capabilities,
@@ -272,8 +280,8 @@ pub fn write_link_refs<W: Write>(
             // clickable.  This mostly helps images inside inline links which we had to write as
             // reference links because we can't nest inline links.
             if let Some(url) = environment.resolve_reference(&link.target) {
-                match &capabilities.style {
-                    Some(StyleCapability::Ansi) => {
+                match &capabilities.links {
+                    Some(LinkCapability::Osc8) => {
                         set_link_url(writer, url, &environment.hostname)?;
                         write_styled(writer, capabilities, &link.style, link.target)?;
                         clear_link(writer)?;
@@ -326,10 +334,14 @@ pub fn write_start_code_block<W: Write>(
                 Some(syntax) => {
                     let parse_state = ParseState::new(syntax);
                     let highlight_state = HighlightState::new(highlighter(), ScopeStack::new());
+                    let syntax_theme = settings
+                        .syntax_themes
+                        .for_language([name.as_ref(), syntax.name.as_str()]);
                     Ok(HighlightBlockAttrs {
                         indent,
                         highlight_state,
                         parse_state,
+                        syntax_theme,
                     }
                     .into())
                 }
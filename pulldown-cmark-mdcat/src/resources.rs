@@ -12,12 +12,14 @@ use std::io::{Error, ErrorKind, Result};
 use mime::Mime;
 use url::Url;
 
+mod data;
 mod file;
 pub(crate) mod image;
 
 pub(crate) mod svg;
 
 pub(crate) use self::image::InlineImageProtocol;
+pub use data::DataUriResourceHandler;
 pub use file::FileResourceHandler;
 
 /// Data of a resource with associated mime type.
@@ -56,12 +58,42 @@ pub trait ResourceUrlHandler {
     /// given `url` is not supported by this resource handler.  In this case a higher level
     /// resource handler may try a different handler.
     fn read_resource(&self, url: &Url) -> Result<MimeData>;
+
+    /// Check whether a resource exists.
+    ///
+    /// Return `Ok(())` if the given `url` exists, or any IO error which occurred while accessing
+    /// the resource, with the same meaning as for [`ResourceUrlHandler::read_resource`].
+    /// Implementations should avoid reading the contents of the resource if possible.
+    ///
+    /// The default implementation reads the entire resource.
+    fn check_resource(&self, url: &Url) -> Result<()> {
+        self.read_resource(url).map(|_| ())
+    }
+
+    /// Prefetch resources.
+    ///
+    /// Read the given `urls` ahead of time, e.g. concurrently, to make subsequent calls to
+    /// [`ResourceUrlHandler::read_resource`] faster.  Ignore all errors; `read_resource` reports
+    /// them later.
+    ///
+    /// The default implementation does nothing.
+    fn prefetch(&self, urls: &[Url]) {
+        let _ = urls;
+    }
 }
 
 impl<R: ResourceUrlHandler + ?Sized> ResourceUrlHandler for &'_ R {
     fn read_resource(&self, url: &Url) -> Result<MimeData> {
         (*self).read_resource(url)
     }
+
+    fn check_resource(&self, url: &Url) -> Result<()> {
+        (*self).check_resource(url)
+    }
+
+    fn prefetch(&self, urls: &[Url]) {
+        (*self).prefetch(urls)
+    }
 }
 
 /// Filter by URL scheme.
@@ -112,6 +144,29 @@ impl ResourceUrlHandler for DispatchingResourceHandler {
             format!("No handler supported reading from {url}"),
         ))
     }
+
+    /// Check the given resource `url`.
+    ///
+    /// Dispatch among inner handlers like [`DispatchingResourceHandler::read_resource`].
+    fn check_resource(&self, url: &Url) -> Result<()> {
+        for handler in &self.handlers {
+            match handler.check_resource(url) {
+                Err(error) if error.kind() == ErrorKind::Unsupported => continue,
+                result => return result,
+            }
+        }
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("No handler supported reading from {url}"),
+        ))
+    }
+
+    /// Prefetch `urls` with every inner handler.
+    fn prefetch(&self, urls: &[Url]) {
+        for handler in &self.handlers {
+            handler.prefetch(urls);
+        }
+    }
 }
 
 /// A resource handler which doesn't read anything.
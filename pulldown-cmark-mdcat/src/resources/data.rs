@@ -0,0 +1,104 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Data URL resources.
+
+use std::io::{Error, ErrorKind, Result};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use mime::Mime;
+use tracing::{event, instrument, Level};
+use url::Url;
+
+use super::{filter_schemes, MimeData, ResourceUrlHandler};
+
+/// A resource handler for base64-encoded `data:` URLs.
+///
+/// See [RFC 2397](https://www.rfc-editor.org/rfc/rfc2397) for the format of `data:` URLs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataUriResourceHandler;
+
+impl ResourceUrlHandler for DataUriResourceHandler {
+    /// Decode the contents of a `data:` URL.
+    ///
+    /// Return the declared mime type, if any, and the decoded data.  Return an
+    /// [`ErrorKind::Unsupported`] error for `data:` URLs which are not base64-encoded, and an
+    /// [`ErrorKind::InvalidData`] error for malformed URLs.
+    #[instrument(level = "debug", skip(self))]
+    fn read_resource(&self, url: &Url) -> Result<MimeData> {
+        filter_schemes(&["data"], url).and_then(|url| {
+            let (media_type, data) = url
+                .path()
+                .split_once(',')
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing data in data URL"))?;
+            let media_type = media_type.strip_suffix(";base64").ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Unsupported,
+                    "Only base64-encoded data URLs are supported",
+                )
+            })?;
+            // Tools which wrap long data URLs may leave whitespace in the data.
+            let data = data
+                .chars()
+                .filter(|c| !c.is_ascii_whitespace())
+                .collect::<String>();
+            let data = STANDARD.decode(data).map_err(|error| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid base64 data in data URL: {error}"),
+                )
+            })?;
+            let mime_type = media_type.parse::<Mime>().ok();
+            event!(
+                Level::DEBUG,
+                "Decoded {} bytes of type {:?} from data URL",
+                data.len(),
+                mime_type
+            );
+            Ok(MimeData { mime_type, data })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::*;
+    use similar_asserts::assert_eq;
+    use std::io::ErrorKind;
+    use url::Url;
+
+    #[test]
+    fn read_png_data_url() {
+        let url = Url::parse("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==").unwrap();
+        let resource = DataUriResourceHandler.read_resource(&url).unwrap();
+        assert_eq!(resource.mime_type, Some(mime::IMAGE_PNG));
+        assert_eq!(resource.data.len(), 70);
+        assert!(resource.data.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn read_malformed_data_url() {
+        let url = Url::parse("data:image/png;base64,not*base64!").unwrap();
+        let error = DataUriResourceHandler.read_resource(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let url = Url::parse("data:image/png;base64").unwrap();
+        let error = DataUriResourceHandler.read_resource(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn read_resource_ignores_other_urls() {
+        let url = Url::parse("data:text/plain,Hello").unwrap();
+        let error = DataUriResourceHandler.read_resource(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let url = Url::parse("https://example.com").unwrap();
+        let error = DataUriResourceHandler.read_resource(&url).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
@@ -21,6 +21,7 @@ use super::{filter_schemes, MimeData, ResourceUrlHandler};
 #[derive(Debug, Clone)]
 pub struct FileResourceHandler {
     read_limit: u64,
+    follow_symlinks: bool,
 }
 
 impl FileResourceHandler {
@@ -28,7 +29,22 @@ impl FileResourceHandler {
     ///
     /// The resource handler does not read beyond `read_limit`.
     pub fn new(read_limit: u64) -> Self {
-        Self { read_limit }
+        Self {
+            read_limit,
+            follow_symlinks: true,
+        }
+    }
+
+    /// Whether to read resources which are symbolic links.
+    ///
+    /// If `false` refuse to read a resource whose path is a symbolic link, to keep references from
+    /// escaping a directory through links.  Still resolve links in the parent directories of a
+    /// resource, though.  Follow symbolic links by default.
+    pub fn with_follow_symlinks(self, follow_symlinks: bool) -> Self {
+        Self {
+            follow_symlinks,
+            ..self
+        }
     }
 }
 
@@ -59,6 +75,13 @@ impl ResourceUrlHandler for FileResourceHandler {
         filter_schemes(&["file"], url).and_then(|url| {
             match url.to_file_path() {
                 Ok(path) => {
+                    if !self.follow_symlinks && path.symlink_metadata()?.is_symlink() {
+                        event!(Level::DEBUG, "Not following symlink {}", path.display());
+                        return Err(Error::new(
+                            ErrorKind::PermissionDenied,
+                            format!("Refusing to read symbolic link {}", path.display()),
+                        ));
+                    }
                     event!(
                         Level::DEBUG,
                         "Reading from resource file {}",
@@ -122,18 +145,38 @@ mod tests {
     #[test]
     fn read_resource_obeys_size_limit() {
         let cwd = Url::from_directory_path(std::env::current_dir().unwrap()).unwrap();
-        let client = FileResourceHandler { read_limit: 10 };
+        let client = FileResourceHandler::new(10);
 
         let resource = cwd.join("../sample/rust-logo.svg").unwrap();
         let error = client.read_resource(&resource).unwrap_err().to_string();
         assert_eq!(error, format!("Contents of {resource} exceeded 10 bytes"));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn read_resource_with_symlinks() {
+        let cwd = std::env::current_dir().unwrap();
+        let directory = std::env::temp_dir().join(format!("mdcat-symlinks-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let link = directory.join("logo.png");
+        std::os::unix::fs::symlink(cwd.join("../sample/rust-logo-128x128.png"), &link).unwrap();
+        let url = Url::from_file_path(&link).unwrap();
+
+        let following = FileResourceHandler::new(5_000_000);
+        let resource = following.read_resource(&url);
+        let not_following = FileResourceHandler::new(5_000_000).with_follow_symlinks(false);
+        let error = not_following.read_resource(&url).unwrap_err();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(resource.unwrap().mime_type, Some(mime::IMAGE_PNG));
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+
     #[test]
     fn read_resource_ignores_http() {
         let url = Url::parse("https://example.com").unwrap();
 
-        let client = FileResourceHandler { read_limit: 10 };
+        let client = FileResourceHandler::new(10);
         let error = client.read_resource(&url).unwrap_err().to_string();
         assert_eq!(
             error,
@@ -15,10 +15,17 @@ pub mod terminology;
 /// The capability of basic styling.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum StyleCapability {
-    /// The terminal supports ANSI styles including OSC 8.
+    /// The terminal supports ANSI styles.
     Ansi,
 }
 
+/// The capability of the terminal to show hyperlinks.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LinkCapability {
+    /// The terminal supports OSC 8 hyperlinks.
+    Osc8,
+}
+
 /// The capability of the terminal to set marks.
 #[derive(Debug, Copy, Clone)]
 pub enum MarkCapability {
@@ -54,9 +61,15 @@ impl ImageCapability {
 /// to detect the terminal and then [`crate::TerminalProgram::capabilities`] to get its
 /// capabilities.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct TerminalCapabilities {
     /// Whether the terminal supports basic ANSI styling.
     pub style: Option<StyleCapability>,
+    /// How the terminal supports hyperlinks.
+    ///
+    /// Without this capability mdcat writes all links as references, with the URLs after the
+    /// paragraph.
+    pub links: Option<LinkCapability>,
     /// How the terminal supports images.
     pub image: Option<ImageCapability>,
     /// How the terminal supports marks.
@@ -68,6 +81,7 @@ impl Default for TerminalCapabilities {
     fn default() -> Self {
         TerminalCapabilities {
             style: None,
+            links: None,
             image: None,
             marks: None,
         }
@@ -15,7 +15,7 @@
 
 //! Kitty terminal extensions.
 use std::fmt::Display;
-use std::io::{Error, ErrorKind, Write};
+use std::io::{Error, Write};
 use std::str;
 
 use base64::engine::general_purpose::STANDARD;
@@ -60,7 +60,7 @@ impl std::error::Error for KittyImageError {
 
 impl From<KittyImageError> for std::io::Error {
     fn from(value: KittyImageError) -> Self {
-        std::io::Error::new(ErrorKind::Other, value)
+        std::io::Error::other(value)
     }
 }
 
@@ -265,7 +265,7 @@ impl KittyGraphicsProtocol {
                     mime_data.mime_type
                 );
                 Err(std::io::Error::new(
-                    ErrorKind::Unsupported,
+                    std::io::ErrorKind::Unsupported,
                     format!(
                         "Image data with mime type {:?} not supported",
                         mime_data.mime_type
@@ -97,7 +97,7 @@ impl TerminalProgram {
             Some("ghostty") => Some(Self::Ghostty),
             Some("vscode")
                 if get_term_program_major_minor_version()
-                    .map_or(false, |version| (1, 80) <= version) =>
+                    .is_some_and(|version| (1, 80) <= version) =>
             {
                 Some(Self::VSCode)
             }
@@ -146,6 +146,7 @@ impl TerminalProgram {
     pub fn capabilities(self) -> TerminalCapabilities {
         let ansi = TerminalCapabilities {
             style: Some(StyleCapability::Ansi),
+            links: Some(LinkCapability::Osc8),
             image: None,
             marks: None,
         };
@@ -20,6 +20,9 @@ pub fn write_osc<W: Write + ?Sized>(writer: &mut W, command: &str) -> Result<()>
     Ok(())
 }
 
+/// OSC 8 links.
+#[deprecated(note = "unused; hyperlinks are written with set_link_url and clear_link")]
+#[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct Osc8Links;
 
@@ -6,11 +6,28 @@
 
 //! Provide a colour theme for mdcat.
 
-use anstyle::{AnsiColor, Color, Style};
+use std::collections::HashMap;
+
+use anstyle::{AnsiColor, Color, RgbColor, Style};
+
+/// The default glyph for rulers.
+pub const DEFAULT_RULE_GLYPH: char = '\u{2550}';
+
+/// How much of the available width a ruler spans.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum RuleWidth {
+    /// Span all available columns.
+    #[default]
+    Full,
+    /// Span half of the available columns.
+    Half,
+}
 
 /// A colour theme for mdcat.
 ///
-/// Currently you cannot create custom styles, but only use the default theme via [`Theme::default`].
+/// Currently you cannot create custom styles, but only use the default theme via [`Theme::default`],
+/// or one of the predefined themes [`Theme::light`] and [`Theme::dracula`], and change how
+/// rulers look with [`Theme::with_rule`].
 #[derive(Debug, Clone)]
 pub struct Theme {
     /// Style for HTML blocks.
@@ -25,10 +42,16 @@ pub struct Theme {
     pub(crate) image_link_style: Style,
     /// Color for rulers.
     pub(crate) rule_color: Color,
+    /// Glyph for rulers.
+    pub(crate) rule_glyph: char,
+    /// Width of rulers.
+    pub(crate) rule_width: RuleWidth,
     /// Color for borders around code blocks.
     pub(crate) code_block_border_color: Color,
     /// Color for headings
     pub(crate) heading_style: Style,
+    /// Style for inline and display math.
+    pub(crate) math_style: Style,
 }
 
 impl Default for Theme {
@@ -41,12 +64,135 @@ impl Default for Theme {
             link_style: Style::new().fg_color(Some(AnsiColor::Blue.into())),
             image_link_style: Style::new().fg_color(Some(AnsiColor::Magenta.into())),
             rule_color: AnsiColor::Green.into(),
+            rule_glyph: DEFAULT_RULE_GLYPH,
+            rule_width: RuleWidth::Full,
             code_block_border_color: AnsiColor::Green.into(),
             heading_style: Style::new().fg_color(Some(AnsiColor::Blue.into())).bold(),
+            math_style: Style::new().fg_color(Some(AnsiColor::Cyan.into())).italic(),
+        }
+    }
+}
+
+impl Theme {
+    /// A theme for terminals with a light background.
+    ///
+    /// Like the default theme, but avoid colours which are hard to read on a light background.
+    pub fn light() -> Self {
+        Self {
+            code_style: Style::new().fg_color(Some(AnsiColor::Red.into())),
+            rule_color: AnsiColor::BrightBlack.into(),
+            code_block_border_color: AnsiColor::BrightBlack.into(),
+            ..Self::default()
+        }
+    }
+
+    /// A theme with the colours of the [Dracula](https://draculatheme.com) palette.
+    ///
+    /// Use 24-bit colours, regardless of the terminal's palette.
+    pub fn dracula() -> Self {
+        let green = RgbColor(0x50, 0xfa, 0x7b);
+        let comment = RgbColor(0x62, 0x72, 0xa4);
+        Self {
+            html_block_style: Style::new().fg_color(Some(RgbColor(0xff, 0xb8, 0x6c).into())),
+            inline_html_style: Style::new().fg_color(Some(RgbColor(0xff, 0xb8, 0x6c).into())),
+            code_style: Style::new().fg_color(Some(green.into())),
+            link_style: Style::new().fg_color(Some(RgbColor(0x8b, 0xe9, 0xfd).into())),
+            image_link_style: Style::new().fg_color(Some(RgbColor(0xbd, 0x93, 0xf9).into())),
+            rule_color: comment.into(),
+            rule_glyph: DEFAULT_RULE_GLYPH,
+            rule_width: RuleWidth::Full,
+            code_block_border_color: comment.into(),
+            heading_style: Style::new()
+                .fg_color(Some(RgbColor(0xff, 0x79, 0xc6).into()))
+                .bold(),
+            math_style: Style::new()
+                .fg_color(Some(RgbColor(0xf1, 0xfa, 0x8c).into()))
+                .italic(),
+        }
+    }
+
+    /// Draw rulers with `glyph` across `width` of the available columns.
+    pub fn with_rule(self, glyph: char, width: RuleWidth) -> Self {
+        Self {
+            rule_glyph: glyph,
+            rule_width: width,
+            ..self
         }
     }
 }
 
+/// A palette for syntax highlighting in code blocks.
+///
+/// mdcat highlights code with the Solarized colour scheme, and maps its accent colours to the
+/// ANSI colours of the terminal.  A syntax theme chooses the ANSI colour for every accent colour.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SyntaxTheme {
+    /// Map Solarized accent colours to their ANSI equivalents.
+    #[default]
+    Solarized,
+    /// Like [`SyntaxTheme::Solarized`], but with bright ANSI colours, for dark terminals.
+    Bright,
+    /// No colours, only bold, italic and underlined text.
+    Monochrome,
+}
+
+impl SyntaxTheme {
+    /// All syntax themes.
+    pub const ALL: [SyntaxTheme; 3] = [
+        SyntaxTheme::Solarized,
+        SyntaxTheme::Bright,
+        SyntaxTheme::Monochrome,
+    ];
+
+    /// The name of this syntax theme.
+    pub fn name(self) -> &'static str {
+        match self {
+            SyntaxTheme::Solarized => "solarized",
+            SyntaxTheme::Bright => "bright",
+            SyntaxTheme::Monochrome => "monochrome",
+        }
+    }
+
+    /// Get the syntax theme with the given `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|theme| theme.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Get the colour to use for the ANSI equivalent of a Solarized accent colour.
+    pub(crate) fn colour(self, accent: AnsiColor) -> Option<Color> {
+        match self {
+            SyntaxTheme::Solarized => Some(accent.into()),
+            SyntaxTheme::Bright => Some(accent.bright(true).into()),
+            SyntaxTheme::Monochrome => None,
+        }
+    }
+}
+
+/// Syntax themes for code blocks, with overrides for specific languages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyntaxThemes {
+    /// The syntax theme for all languages without an override.
+    pub default: SyntaxTheme,
+    /// Syntax themes for specific languages, by lowercase language name.
+    pub languages: HashMap<String, SyntaxTheme>,
+}
+
+impl SyntaxThemes {
+    /// Get the syntax theme for any of the given language `names`.
+    ///
+    /// Use the override for the first name which has one, ignoring case, and the default theme
+    /// otherwise.
+    pub fn for_language<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> SyntaxTheme {
+        names
+            .into_iter()
+            .find_map(|name| self.languages.get(&name.to_lowercase()))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
 /// Combine styles.
 pub trait CombineStyle {
     /// Put this style on top of the other style.
@@ -65,24 +65,24 @@ fn test_render() {
     root_url.set_host(Some("HOSTNAME")).unwrap();
     cwd_url.set_host(Some("HOSTNAME")).unwrap();
 
-    let dumb_settings = Settings {
-        terminal_capabilities: TerminalProgram::Dumb.capabilities(),
-        terminal_size: TerminalSize::default(),
-        theme: Theme::default(),
-        syntax_set: syntax_set(),
-    };
-    let ansi_settings = Settings {
-        terminal_capabilities: TerminalProgram::Ansi.capabilities(),
-        terminal_size: TerminalSize::default(),
-        theme: Theme::default(),
-        syntax_set: syntax_set(),
-    };
-    let iterm2_settings = Settings {
-        terminal_capabilities: TerminalProgram::ITerm2.capabilities(),
-        terminal_size: TerminalSize::default(),
-        theme: Theme::default(),
-        syntax_set: syntax_set(),
-    };
+    let dumb_settings = Settings::new(
+        TerminalProgram::Dumb.capabilities(),
+        TerminalSize::default(),
+        syntax_set(),
+        Theme::default(),
+    );
+    let ansi_settings = Settings::new(
+        TerminalProgram::Ansi.capabilities(),
+        TerminalSize::default(),
+        syntax_set(),
+        Theme::default(),
+    );
+    let iterm2_settings = Settings::new(
+        TerminalProgram::ITerm2.capabilities(),
+        TerminalSize::default(),
+        syntax_set(),
+        Theme::default(),
+    );
 
     glob!("markdown/**/*.md", |markdown_file| {
         let mut settings = insta::Settings::clone_current();
@@ -112,3 +112,39 @@ fn test_render() {
         drop(_guard);
     });
 }
+
+#[test]
+fn test_render_definition_lists() {
+    let markdown = "Term\n: - a\n  - b\n\nCode\n: ```\n  code\n  ```\n\nPlain\n: A *definition*.\n";
+    let parser = Parser::new_ext(markdown, Options::ENABLE_DEFINITION_LIST);
+    let settings = Settings::new(
+        TerminalProgram::Dumb.capabilities(),
+        TerminalSize::default(),
+        syntax_set(),
+        Theme::default(),
+    );
+    let env = Environment::for_local_directory(&std::env::current_dir().unwrap()).unwrap();
+    let mut sink = Vec::new();
+    pulldown_cmark_mdcat::push_tty(&settings, &env, &NoopResourceHandler, &mut sink, parser)
+        .unwrap();
+    let rendered = String::from_utf8(sink).unwrap();
+    assert!(
+        rendered
+            .lines()
+            .all(|line| line.is_empty() || !line.trim().is_empty()),
+        "Rendered: {rendered:?}"
+    );
+    assert_snapshot!(rendered, @r"
+    Term
+        • a
+        • b
+
+    Code
+        ────────────────────
+        code
+        ────────────────────
+
+    Plain
+        A definition.
+    ");
+}
@@ -35,12 +35,13 @@ fn render_to_string<S: AsRef<str>>(markdown: S, settings: &Settings) -> String {
 fn lines_are_below_column_width_of_terminal() {
     glob!("markdown/wrapping/*.md", |markdown_file| {
         let markdown = std::fs::read_to_string(markdown_file).unwrap();
-        let settings = Settings {
-            terminal_capabilities: TerminalProgram::Ansi.capabilities(),
-            terminal_size: TerminalSize::default(),
-            theme: Theme::default(),
-            syntax_set: &SyntaxSet::load_defaults_newlines(),
-        };
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let settings = Settings::new(
+            TerminalProgram::Ansi.capabilities(),
+            TerminalSize::default(),
+            &syntax_set,
+            Theme::default(),
+        );
         let rendered = render_to_string(markdown, &settings);
         for line in rendered.lines() {
             let width = textwrap::core::display_width(line);
@@ -0,0 +1,354 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use mime::Mime;
+use pulldown_cmark_mdcat::resources::{filter_schemes, MimeData, ResourceUrlHandler};
+use tracing::{event, instrument, Level};
+use url::Url;
+use zip::ZipArchive;
+
+/// The URL scheme for members of an archive.
+pub const ARCHIVE_SCHEME: &str = "archive";
+
+/// Get the URL of the archive member `name`.
+///
+/// Use the `archive:` scheme and the name of the member as absolute path, so that relative
+/// references in a member resolve to other members of the archive.
+pub fn member_url(name: &str) -> Url {
+    let mut url = Url::parse("archive:///").expect("Archive root URL must be valid");
+    url.set_path(name.trim_start_matches("./"));
+    url
+}
+
+/// The format of an archive.
+#[derive(Debug)]
+enum Format {
+    Zip(Mutex<ZipArchive<File>>),
+    Tar,
+    GzipTar,
+}
+
+/// An archive of files.
+///
+/// Supports zip archives with stored or deflated members, and tar archives, optionally
+/// compressed with gzip.  Read the list of members up front, but read the contents of members
+/// only on demand.
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    format: Format,
+    /// Names of all files in the archive, by the path of their member URL.
+    members: HashMap<String, String>,
+}
+
+impl Archive {
+    /// Open the archive at `path`.
+    ///
+    /// Tell zip, gzip, and tar archives apart by their magic bytes.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::read_members(path)
+            .with_context(|| format!("Failed to read archive {}", path.display()))
+    }
+
+    fn read_members(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut magic = Vec::with_capacity(262);
+        file.by_ref().take(262).read_to_end(&mut magic)?;
+        file.rewind()?;
+        let (format, names) =
+            if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+                let zip = ZipArchive::new(file).context("Invalid zip archive")?;
+                let names = zip
+                    .file_names()
+                    .filter(|name| !name.ends_with('/'))
+                    .map(ToString::to_string)
+                    .collect();
+                (Format::Zip(Mutex::new(zip)), names)
+            } else if magic.starts_with(&[0x1f, 0x8b]) {
+                (Format::GzipTar, tar_file_names(GzDecoder::new(file))?)
+            } else if magic.get(257..262) == Some(b"ustar") {
+                (Format::Tar, tar_file_names(file)?)
+            } else {
+                bail!("Unsupported archive format, expected zip or tar");
+            };
+        let members = names
+            .into_iter()
+            .map(|name: String| (member_url(&name).path().to_string(), name))
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            members,
+        })
+    }
+
+    /// Read the contents of the member at `url`, but not more than `limit` bytes.
+    ///
+    /// Fail with [`ErrorKind::NotFound`] if there is no such member, and with
+    /// [`ErrorKind::FileTooLarge`] if the member has more than `limit` bytes.
+    pub fn read_member(&self, url: &Url, limit: u64) -> std::io::Result<Vec<u8>> {
+        let name = self.members.get(url.path()).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("No member {} in archive", url.path()),
+            )
+        })?;
+        // Read a byte more than the limit to differentiate the end of the member from the limit
+        let mut contents = Vec::new();
+        match &self.format {
+            Format::Zip(zip) => {
+                let mut zip = zip.lock().unwrap_or_else(|error| error.into_inner());
+                let member = zip.by_name(name).map_err(std::io::Error::from)?;
+                member.take(limit + 1).read_to_end(&mut contents)?;
+            }
+            Format::Tar => read_tar_member(File::open(&self.path)?, name, limit, &mut contents)?,
+            Format::GzipTar => read_tar_member(
+                GzDecoder::new(File::open(&self.path)?),
+                name,
+                limit,
+                &mut contents,
+            )?,
+        }
+        if limit < contents.len() as u64 {
+            Err(Error::new(
+                ErrorKind::FileTooLarge,
+                format!("Contents of {url} exceeded {limit} bytes"),
+            ))
+        } else {
+            Ok(contents)
+        }
+    }
+}
+
+/// Get the names of all regular files in the tar archive read from `reader`.
+fn tar_file_names<R: Read>(reader: R) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in tar::Archive::new(reader).entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            names.push(entry.path()?.to_string_lossy().into_owned());
+        }
+    }
+    Ok(names)
+}
+
+/// Read up to `limit + 1` bytes of the member `name` of the tar archive in `reader` into `buffer`.
+///
+/// Decompress and skip over all preceding members, but do not read any further.
+fn read_tar_member<R: Read>(
+    reader: R,
+    name: &str,
+    limit: u64,
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    for entry in tar::Archive::new(reader).entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() && entry.path()?.to_string_lossy() == name {
+            entry.take(limit + 1).read_to_end(buffer)?;
+            return Ok(());
+        }
+    }
+    Err(Error::new(
+        ErrorKind::NotFound,
+        format!("No member {name} in archive"),
+    ))
+}
+
+/// Guess the mime type of the archive member at `url`, like for files.
+fn guess_mimetype(url: &Url) -> Option<Mime> {
+    let extension = url.path().rsplit_once('.')?.1.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some(mime::IMAGE_PNG),
+        "svg" => Some(mime::IMAGE_SVG),
+        _ => None,
+    }
+}
+
+/// A resource handler for members of an archive, with `archive:` URLs.
+#[derive(Debug)]
+pub struct ArchiveResourceHandler {
+    archive: Archive,
+    read_limit: u64,
+}
+
+impl ArchiveResourceHandler {
+    /// Create a resource handler for members of `archive`.
+    ///
+    /// The resource handler does not read beyond `read_limit`.
+    pub fn new(archive: Archive, read_limit: u64) -> Self {
+        Self {
+            archive,
+            read_limit,
+        }
+    }
+}
+
+impl ResourceUrlHandler for ArchiveResourceHandler {
+    #[instrument(level = "debug", skip(self))]
+    fn read_resource(&self, url: &Url) -> std::io::Result<MimeData> {
+        filter_schemes(&[ARCHIVE_SCHEME], url).and_then(|url| {
+            let data = self.archive.read_member(url, self.read_limit)?;
+            event!(Level::DEBUG, "Read {} bytes from archive", data.len());
+            Ok(MimeData {
+                mime_type: guess_mimetype(url),
+                data,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::CompressionMethod;
+
+    /// Write `data` to a temporary archive file named `name`.
+    fn archive_file(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("mdcat-{}-{name}", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Build a zip archive of `files` in memory, with members compressed by `method`.
+    fn zip(files: &[(&str, &[u8])], method: CompressionMethod) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(method);
+        for (name, contents) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Build a tar archive of `files` in memory.
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, contents) in files {
+            let mut header = tar::Header::new_ustar();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn read_zip_members() {
+        let data = zip(
+            &[
+                ("docs/README.md", b"# Docs\n\n![Logo](images/logo.png)\n"),
+                ("docs/images/logo.png", b"\x89PNG\r\n\x1a\n"),
+            ],
+            CompressionMethod::Deflated,
+        );
+        let path = archive_file("members.zip", &data);
+        let archive = Archive::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        let handler = ArchiveResourceHandler::new(archive.unwrap(), 1024);
+
+        let readme = member_url("./docs/README.md");
+        assert_eq!(readme.as_str(), "archive:///docs/README.md");
+        let contents = handler.read_resource(&readme).unwrap();
+        assert_eq!(contents.data, b"# Docs\n\n![Logo](images/logo.png)\n");
+        assert_eq!(contents.mime_type, None);
+
+        // Relative references resolve to other members of the archive
+        let logo = handler
+            .read_resource(&readme.join("images/logo.png").unwrap())
+            .unwrap();
+        assert_eq!(logo.data, b"\x89PNG\r\n\x1a\n");
+        assert_eq!(logo.mime_type, Some(mime::IMAGE_PNG));
+
+        let error = handler
+            .read_resource(&member_url("missing.md"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        let error = handler
+            .read_resource(&Url::parse("file:///docs/README.md").unwrap())
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn limit_member_size() {
+        let contents = "Lorem ipsum dolor sit amet. ".repeat(100);
+        let data = zip(
+            &[("README.md", contents.as_bytes())],
+            CompressionMethod::Deflated,
+        );
+        let path = archive_file("limit.zip", &data);
+        let archive = Archive::open(&path).unwrap();
+        let url = member_url("README.md");
+        let error = archive.read_member(&url, 100).unwrap_err();
+        let member = archive.read_member(&url, contents.len() as u64);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), ErrorKind::FileTooLarge);
+        assert_eq!(member.unwrap(), contents.as_bytes());
+    }
+
+    #[test]
+    fn reject_corrupted_zip_members() {
+        let mut data = zip(&[("README.md", b"# Stored\n")], CompressionMethod::Stored);
+        let offset = data.windows(8).position(|w| w == b"# Stored").unwrap();
+        data[offset + 2] = b's';
+        let path = archive_file("corrupted.zip", &data);
+        let archive = Archive::open(&path).unwrap();
+        let result = archive.read_member(&member_url("README.md"), 1024);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err(), "{result:?}");
+    }
+
+    #[test]
+    fn read_tar_members() {
+        let data = tar(&[
+            ("docs/README.md", b"# Tar\n"),
+            ("docs/other.md", b"Other\n"),
+        ]);
+        let path = archive_file("members.tar", &data);
+        let archive = Archive::open(&path).unwrap();
+        let readme = archive.read_member(&member_url("docs/README.md"), 1024);
+        let other = archive.read_member(&member_url("docs/other.md"), 3);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(readme.unwrap(), b"# Tar\n");
+        assert_eq!(other.unwrap_err().kind(), ErrorKind::FileTooLarge);
+    }
+
+    #[test]
+    fn read_gzip_tar_members() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&tar(&[("README.md", b"# Gzip\n")]))
+            .unwrap();
+        let path = archive_file("members.tar.gz", &encoder.finish().unwrap());
+        let archive = Archive::open(&path).unwrap();
+        let readme = archive.read_member(&member_url("README.md"), 1024);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(readme.unwrap(), b"# Gzip\n");
+    }
+
+    #[test]
+    fn reject_other_formats() {
+        let path = archive_file("other.md", b"# Not an archive\n");
+        let error = Archive::open(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            error.root_cause().to_string(),
+            "Unsupported archive format, expected zip or tar"
+        );
+    }
+}
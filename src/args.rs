@@ -4,8 +4,27 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anstyle::AnsiColor;
 use clap::ValueHint;
 use clap_complete::Shell;
+use pulldown_cmark_mdcat::{
+    RuleWidth, SyntaxTheme, SyntaxThemes, TerminalProgram, Theme, DEFAULT_RULE_GLYPH,
+};
+use tracing::level_filters::LevelFilter;
+
+use crate::highlighter::ExternalHighlighter;
+use crate::resources::{default_cache_directory, CurlOptions};
+use crate::stats::{
+    LineNumberOptions, StatsOptions, Stopwords, DEFAULT_LINE_NUMBER_SEPARATOR,
+    DEFAULT_WORDS_PER_MINUTE,
+};
+use crate::{LineRange, MarkdownExtensions, ProcessOptions, ResourceOptions};
+
+pub use crate::stats::StatsFormat;
 
 fn after_help() -> &'static str {
     "See 'man 1 mdcat' for more information.
@@ -37,18 +56,19 @@ pub struct Args {
 
 #[derive(Debug, clap::Subcommand)]
 pub enum Command {
-    #[command(version, about, after_help = after_help(), long_version = long_version())]
+    #[command(version, about, after_help = after_help(), long_version = long_version(), args_override_self = true)]
     Mdcat {
         #[command(flatten)]
         args: CommonArgs,
         /// Paginate the output of mdcat with a pager like less (default for mdless).
         #[arg(short, long, overrides_with = "no_pager")]
         paginate: bool,
-        /// Do not paginate output (default). Overrides an earlier --paginate.
+        /// Do not paginate output, even if it does not fit on the screen. Overrides an earlier
+        /// --paginate.
         #[arg(short = 'P', long)]
         no_pager: bool,
     },
-    #[command(version, about, after_help = after_help(), long_version = long_version())]
+    #[command(version, about, after_help = after_help(), long_version = long_version(), args_override_self = true)]
     Mdless {
         #[command(flatten)]
         args: CommonArgs,
@@ -62,7 +82,16 @@ pub enum Command {
 }
 
 impl Command {
+    /// Whether output can go to a pager at all.
+    fn may_paginate(&self) -> bool {
+        // Plain text, files, dry runs, and HTML never go to a pager
+        !(self.plain || self.writes_to_file() || self.dry_run || self.to == OutputFormat::Html)
+    }
+
     pub fn paginate(&self) -> bool {
+        if !self.may_paginate() {
+            return false;
+        }
         match *self {
             // In both cases look at the option indicating the non-default
             // behaviour; the overrides above are configured accordingly.
@@ -70,6 +99,18 @@ impl Command {
             Command::Mdless { no_pager, .. } => !no_pager,
         }
     }
+
+    /// Whether to paginate automatically if the output does not fit on the screen.
+    ///
+    /// This is the default for mdcat, unless `--paginate` or `--no-pager` decide explicitly.
+    pub fn auto_paginate(&self) -> bool {
+        match *self {
+            Command::Mdcat {
+                paginate, no_pager, ..
+            } => self.may_paginate() && !paginate && !no_pager,
+            Command::Mdless { .. } => false,
+        }
+    }
 }
 
 impl std::ops::Deref for Command {
@@ -85,42 +126,766 @@ impl std::ops::Deref for Command {
 
 #[derive(Debug, clap::Args)]
 // #[command(author, version, about, after_help = after_help(), long_version = long_version())]
+#[command(group(clap::ArgGroup::new("statistics").multiple(true)))]
 pub struct CommonArgs {
     /// Files to read.  If - read from standard input instead.
     #[arg(default_value="-", value_hint = ValueHint::FilePath)]
     pub filenames: Vec<String>,
-    /// Disable all colours and other styles.
+    /// Read markdown from the clipboard instead of files.
+    #[cfg(feature = "clipboard")]
+    #[arg(long, conflicts_with_all = ["filenames", "merge"])]
+    pub clipboard: bool,
+    /// Read files and relative resources from this zip or tar archive, instead of the file system.
+    #[cfg(feature = "archive")]
+    #[arg(long, value_name = "ARCHIVE", value_hint = ValueHint::FilePath)]
+    pub archive: Option<PathBuf>,
+    /// Join all files and render them as a single document, instead of one after another.
+    #[arg(long)]
+    pub merge: bool,
+    /// The pager command to paginate with, instead of $MDCAT_PAGER or $PAGER.
+    #[arg(long, value_name = "CMD", value_hint = ValueHint::CommandString)]
+    pub pager: Option<String>,
+    /// When paginating, print directly instead if the output fits on the screen.
+    #[arg(long)]
+    pub no_pager_if_fits: bool,
+    /// Do not expand glob patterns in filenames.
+    #[arg(long)]
+    pub no_glob: bool,
+    /// Disable all colours and other styles; same as --color=never.
     #[arg(short = 'c', long, aliases=["nocolour", "no-color", "nocolor"])]
     pub no_colour: bool,
-    /// Maximum number of columns to use for output.
-    #[arg(long)]
-    pub columns: Option<u16>,
+    /// When to use colours and other styles.
+    #[arg(long = "color", alias = "colour", value_name = "WHEN", value_enum, default_value_t = ColourMode::Auto, conflicts_with = "no_colour")]
+    pub colour: ColourMode,
+    /// Render documents to this format; HTML disables pagination and terminal detection.
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = OutputFormat::Terminal)]
+    pub to: OutputFormat,
+    /// Write output to this file instead of standard output, without pagination.
+    #[arg(short = 'o', long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+    /// Write each input to a file in this directory, named after the input with the extension of
+    /// the output format.
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath, conflicts_with_all = ["output", "merge"])]
+    pub output_dir: Option<PathBuf>,
+    /// Number of columns to use for output, a percentage of the terminal width like 60%, or
+    /// "auto" to use the width of the terminal.
+    #[arg(long, value_name = "COLUMNS", default_value = "auto", value_parser = parse_columns)]
+    pub columns: Columns,
+    /// Wrap paragraph text at N columns, but use the full width for tables and rules.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub prose_width: Option<u16>,
+    /// Indent the text of list items by N columns per level of nesting, from 1 to 8, but never
+    /// less than the width of the list marker.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..=8))]
+    pub list_indent: Option<u16>,
     /// Do not load remote resources like images.
     #[arg(short, long = "local")]
     pub local_only: bool,
+    /// Do not load any local or remote resources, for rendering untrusted documents.
+    #[arg(long)]
+    pub sandbox: bool,
+    /// Markdown to render between two files; {file} is replaced with the name of the next file.
+    /// Pass an empty string to disable.
+    #[arg(long, value_name = "MARKDOWN", default_value = DEFAULT_FILE_SEPARATOR)]
+    pub file_separator: String,
+    /// Timeout in seconds for fetching a remote resource.
+    #[arg(long, value_name = "SECONDS", default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub resource_timeout: u64,
+    /// Abort rendering a file after this many seconds, e.g. for pathological input in batch jobs.
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    pub render_timeout: Option<Duration>,
+    /// Fail if the output is a broken pipe, e.g. if a pager exits early, instead of ignoring it.
+    #[arg(long)]
+    pub strict_pipe: bool,
+    /// Retry fetching a remote resource up to N times after a timeout or a server error.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub resource_retries: u32,
+    /// Fetch remote resources through this proxy, instead of the proxy from the environment.
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+    /// Skip local and remote resources larger than this size, e.g. 500K or 10M.
+    #[arg(long, value_name = "BYTES", default_value = "100M", value_parser = parse_size)]
+    pub max_resource_size: u64,
+    /// Fail on input documents larger than this size, e.g. 500K or 10M, instead of reading them
+    /// into memory.
+    #[arg(long, value_name = "BYTES", value_parser = parse_size)]
+    pub max_input_size: Option<u64>,
+    /// Do not read local resources which are symbolic links.
+    #[arg(long, overrides_with = "follow_symlinks")]
+    pub no_follow_symlinks: bool,
+    /// Read local resources which are symbolic links (default). Overrides an earlier --no-follow-symlinks.
+    #[arg(long, overrides_with = "no_follow_symlinks")]
+    pub follow_symlinks: bool,
+    /// Add a header to all requests for remote resources; may be given multiple times.
+    #[arg(long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    pub headers: Vec<String>,
+    /// Connect to ADDR for requests to HOST on PORT, like curl's --resolve; may be given multiple
+    /// times.
+    #[arg(long, value_name = "HOST:PORT:ADDR", value_parser = parse_resolve)]
+    pub resolve: Vec<String>,
+    /// Send this user agent with all requests for remote resources.
+    #[arg(long, value_name = "STRING", value_parser = parse_user_agent)]
+    pub user_agent: Option<String>,
+    /// Cache remote images in the default cache directory.
+    #[arg(long, overrides_with = "no_cache")]
+    pub cache: bool,
+    /// Cache remote images in this directory.
+    #[arg(long, value_name = "DIRECTORY", overrides_with = "no_cache")]
+    pub cache_dir: Option<PathBuf>,
+    /// Do not cache remote images. Overrides an earlier --cache or --cache-dir.
+    #[arg(long, overrides_with_all = ["cache", "cache_dir"])]
+    pub no_cache: bool,
     /// Exit immediately if any error occurs processing an input file.
     #[arg(long = "fail")]
     pub fail_fast: bool,
+    /// Only print errors, and no warnings.
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Print debugging information on standard error; repeat for more details.
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Report progress for every input file on standard error.
+    #[arg(long, value_name = "FORMAT", value_enum)]
+    pub progress: Option<ProgressFormat>,
     /// Print detected terminal name and exit.
     #[arg(long = "detect-terminal")]
     pub detect_and_exit: bool,
+    /// The format to print the detected terminal in.
+    #[arg(long = "format", value_name = "FORMAT", value_enum, default_value_t = DetectFormat::Text, requires = "detect_and_exit")]
+    pub detect_format: DetectFormat,
+    /// Use this terminal instead of detecting it, e.g. kitty or iterm2; overrides $MDCAT_TERMINAL.
+    #[arg(long, value_name = "NAME", value_parser = parse_terminal, conflicts_with = "ansi_only")]
+    pub terminal: Option<TerminalProgram>,
+    /// The colour theme to use.
+    #[arg(long, value_enum, default_value_t = ThemeName::Dark)]
+    pub theme: ThemeName,
+    /// List all available colour themes and exit.
+    #[arg(long)]
+    pub list_themes: bool,
+    /// The glyph to draw horizontal rules with.
+    #[arg(long, value_name = "CHAR", default_value_t = DEFAULT_RULE_GLYPH)]
+    pub hr_char: char,
+    /// How wide to draw horizontal rules.
+    #[arg(long, value_name = "WIDTH", value_enum, default_value_t = HrWidth::Full)]
+    pub hr_width: HrWidth,
+    /// The syntax theme for highlighted code blocks: solarized, bright, or monochrome.
+    #[arg(long, value_name = "NAME", default_value = "solarized", value_parser = parse_syntax_theme)]
+    pub syntax_theme: SyntaxTheme,
+    /// Use the syntax theme NAME for code blocks in the language LANG; repeat for more languages.
+    #[arg(long, value_name = "LANG=NAME", value_parser = parse_language_syntax_theme)]
+    pub syntax_theme_for: Vec<(String, SyntaxTheme)>,
+    /// List all languages for syntax highlighting and exit.
+    ///
+    /// Print the name of each language followed by its aliases; use any of them as the language
+    /// of a fenced code block.
+    #[arg(long)]
+    pub list_languages: bool,
     /// Skip terminal detection and only use ANSI formatting.
-    #[arg(long = "ansi", conflicts_with = "no_colour")]
+    #[arg(long = "ansi", conflicts_with_all = ["no_colour", "colour"])]
     pub ansi_only: bool,
+    /// Do not write links as terminal hyperlinks, but as references with the URLs after each
+    /// paragraph.
+    #[arg(long)]
+    pub no_hyperlinks: bool,
+    /// Render plain text without any escape sequences or terminal features, and never paginate.
+    #[arg(long, conflicts_with = "ansi_only")]
+    pub plain: bool,
     /// Show line numbers in the output.
     #[arg(long = "line-numbers")]
     pub line_numbers: bool,
+    /// The separator between line numbers and lines.
+    #[arg(long = "line-number-sep", value_name = "STR", default_value = DEFAULT_LINE_NUMBER_SEPARATOR)]
+    pub line_number_separator: String,
+    /// The colour of line numbers.
+    #[arg(
+        long = "line-number-color",
+        alias = "line-number-colour",
+        value_name = "COLOR",
+        value_enum
+    )]
+    pub line_number_colour: Option<LineNumberColour>,
+    /// The number of the first line.
+    #[arg(long = "line-number-start", value_name = "N", default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub line_number_start: usize,
     /// Display statistics about the document (word count, character count, etc.).
-    #[arg(long = "stats")]
+    #[arg(long = "stats", group = "statistics")]
     pub stats: bool,
+    /// Format of document statistics.
+    #[arg(long = "stats-format", value_enum, default_value_t = StatsFormat::Text)]
+    pub stats_format: StatsFormat,
+    /// Display statistics about the document as a single line, e.g. for scripts or shell prompts.
+    #[arg(long = "stats-summary", group = "statistics", conflicts_with_all = ["stats_format", "stats_sections", "stats_top_words"])]
+    pub stats_summary: bool,
+    /// Only count characters, words and lines of the markdown source, without parsing it; faster
+    /// than --stats for many files.
+    #[arg(long, group = "statistics", conflicts_with_all = ["stats_summary", "stats_sections", "stats_top_words", "stats_exclude_code"])]
+    pub count_only: bool,
+    /// Only count lines added since the git REF, e.g. to see how much prose a branch adds;
+    /// implies --stats.
+    #[arg(long, value_name = "REF", group = "statistics")]
+    pub since_git: Option<String>,
+    /// Reading speed in words per minute for the estimated reading time.
+    #[arg(long = "reading-speed", value_name = "WPM", default_value_t = DEFAULT_WORDS_PER_MINUTE, value_parser = parse_reading_speed)]
+    pub reading_speed: NonZeroUsize,
+    /// Exclude code blocks from word and character counts in statistics.
+    #[arg(long = "stats-exclude-code")]
+    pub stats_exclude_code: bool,
+    /// Also count graphemes, i.e. user-perceived characters, in statistics.
+    #[arg(long = "stats-graphemes")]
+    pub stats_graphemes: bool,
+    /// Include word counts per section in statistics.
+    #[arg(long = "stats-sections")]
+    pub stats_sections: bool,
+    /// Include the N most frequent words in statistics.
+    #[arg(long = "stats-top-words", value_name = "N")]
+    pub stats_top_words: Option<usize>,
+    /// Write statistics to PATH, or to standard error if PATH is -, and render documents as
+    /// usual.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, requires = "statistics")]
+    pub stats_output: Option<PathBuf>,
+    /// Read words to omit from the most frequent words from FILE, instead of common English words.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath, value_parser = parse_stopwords)]
+    pub stopwords: Option<Stopwords>,
+    /// Show a table of contents before each document.
+    #[arg(long)]
+    pub toc: bool,
+    /// Only include headings up to this level in the table of contents.
+    #[arg(long, value_name = "N", default_value_t = 6, requires = "toc", value_parser = clap::value_parser!(u8).range(1..=6))]
+    pub toc_max_depth: u8,
+    /// Print the markdown source verbatim instead of rendering it; with --line-numbers number
+    /// every line of the source.
+    #[arg(
+        long,
+        alias = "raw",
+        conflicts_with_all = [
+            "statistics",
+            "since_git",
+            "toc",
+            "list_anchors",
+            "print_resources",
+            "check_links",
+            "check_headings",
+            "strict_markdown",
+            "section",
+            "dry_run",
+            "max_heading_level",
+            "dump_events",
+        ]
+    )]
+    pub literal: bool,
+    /// Print the raw markdown events instead of rendering, for debugging.
+    #[arg(long, hide = true)]
+    pub dump_events: bool,
+    /// List the anchor of every heading instead of rendering.
+    #[arg(long)]
+    pub list_anchors: bool,
+    /// List all images and links with their resolved path or URL instead of rendering, and mark
+    /// those which --local or --sandbox would block.
+    #[arg(long)]
+    pub print_resources: bool,
+    /// Report links to missing files instead of rendering; with remote access also report links
+    /// to remote URLs which fail to load.
+    #[arg(long)]
+    pub check_links: bool,
+    /// Report headings which skip levels and multiple level 1 headings instead of rendering.
+    #[arg(long)]
+    pub check_headings: bool,
+    /// Report tabs in list indentation, lists without a blank line before, and bare URLs instead
+    /// of rendering.
+    #[arg(long, alias = "strict")]
+    pub strict_markdown: bool,
+    /// Render documents without output to check that they render and all images load.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Read default options from this configuration file.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub config: Option<PathBuf>,
+    /// Do not read default options from a configuration file.
+    #[arg(long, conflicts_with = "config")]
+    pub no_config: bool,
+    /// Omit sections with headings deeper than this level.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=6))]
+    pub max_heading_level: Option<u8>,
+    /// Only render the section with this heading, up to the next heading of the same or a higher
+    /// level.
+    #[arg(long, value_name = "TITLE")]
+    pub section: Option<String>,
+    /// Only render the given source lines, e.g. 100-150, or 30- for all lines from line 30.
+    #[arg(long, value_name = "START-END", value_parser = parse_line_range)]
+    pub lines: Option<LineRange>,
+    /// Resolve relative references, e.g. images, against this directory instead of the
+    /// directory of each input file, or the working directory for standard input.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::DirPath, value_parser = parse_base_dir)]
+    pub base_dir: Option<PathBuf>,
+    /// Replace lines of the form {{include: PATH}} with the contents of PATH, relative to the
+    /// directory of the input, or to --base-dir.
+    #[arg(long, conflicts_with = "sandbox")]
+    pub enable_includes: bool,
+    /// Print the keys of YAML or TOML front matter before each document.
+    #[arg(long)]
+    pub show_front_matter: bool,
+    /// Render YAML or TOML front matter as a table of keys and values before each document.
+    #[arg(long = "frontmatter-as-table")]
+    pub front_matter_table: bool,
+    /// Do not render HTML comments.
+    #[arg(long)]
+    pub strip_comments: bool,
+    /// Do not render images, but only their alt text.
+    #[arg(long)]
+    pub no_images: bool,
+    /// Expand emoji shortcodes like :rocket: outside of code.
+    #[arg(long)]
+    pub emoji: bool,
+    /// Show a progress bar of checked tasks after every task list.
+    #[arg(long)]
+    pub task_progress: bool,
+    /// Do not parse pipe tables.
+    #[arg(long, overrides_with = "gfm")]
+    pub no_tables: bool,
+    /// Do not parse ~~strikethrough~~.
+    #[arg(long, overrides_with = "gfm")]
+    pub no_strikethrough: bool,
+    /// Do not parse task lists.
+    #[arg(long, overrides_with = "gfm")]
+    pub no_tasklists: bool,
+    /// Parse all GitHub flavoured markdown extensions (default). Overrides earlier --no-tables, --no-strikethrough and --no-tasklists.
+    #[arg(long, overrides_with_all = ["no_tables", "no_strikethrough", "no_tasklists"])]
+    pub gfm: bool,
+    /// Highlight fenced code blocks with an external command, replacing {lang} with the language of the block.
+    #[arg(long, value_name = "CMD", value_hint = ValueHint::CommandString, value_parser = parse_highlighter)]
+    pub highlighter: Option<ExternalHighlighter>,
+    /// Render straight quotes, dashes and ellipses as typographic punctuation.
+    #[arg(long)]
+    pub smart_punctuation: bool,
+    /// Render definition lists, i.e. terms followed by ": Definition" lines.
+    #[arg(long)]
+    pub definition_lists: bool,
+    /// Render inline math in $…$ and display math in $$…$$ as styled text.
+    #[arg(long)]
+    pub math: bool,
+    /// Expand tabs in code blocks to tab stops every N columns.
+    #[arg(long, value_name = "N", default_value_t = 8, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=32))]
+    pub tab_width: usize,
+    /// How to handle lines in code blocks which are wider than the terminal: off to let the
+    /// terminal wrap them, char to wrap them at the last character which fits, or none to
+    /// truncate them.
+    #[arg(long, value_name = "MODE", value_enum, default_value_t = CodeWrap::Off)]
+    pub wrap_code: CodeWrap,
+    /// Download all remote images concurrently before rendering each document.
+    #[arg(long)]
+    pub prefetch: bool,
     /// Generate completions for a shell to standard output and exit.
     #[arg(long)]
     pub completions: Option<Shell>,
 }
 
+/// The default separator between files: A horizontal rule, and the name of the file.
+pub const DEFAULT_FILE_SEPARATOR: &str = "---\n\n`{file}`";
+
+fn parse_reading_speed(value: &str) -> Result<NonZeroUsize, String> {
+    let wpm = value.parse::<usize>().map_err(|error| error.to_string())?;
+    NonZeroUsize::new(wpm).ok_or_else(|| "reading speed must be greater than zero".to_string())
+}
+
+fn parse_columns(value: &str) -> Result<Columns, String> {
+    if value == "auto" {
+        return Ok(Columns::Auto);
+    }
+    if let Some(percentage) = value.strip_suffix('%') {
+        return match percentage.parse::<u8>() {
+            Ok(percentage @ 1..=100) => Ok(Columns::Percentage(percentage)),
+            Ok(_) => Err(format!(
+                "invalid columns {value:?}: percentage must be between 1% and 100%"
+            )),
+            Err(error) => Err(format!("invalid columns {value:?}: {error}")),
+        };
+    }
+    match value.parse::<u16>() {
+        Ok(0) => Err("columns must be at least 1".to_string()),
+        Ok(columns) => Ok(Columns::Fixed(columns)),
+        Err(error) => Err(format!("invalid columns {value:?}: {error}")),
+    }
+}
+
+/// Parse a size in bytes, with an optional binary suffix `K`, `M`, or `G`.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, factor) = match value.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&value[..index], 1 << 10),
+        Some((index, 'm' | 'M')) => (&value[..index], 1 << 20),
+        Some((index, 'g' | 'G')) => (&value[..index], 1 << 30),
+        _ => (value, 1),
+    };
+    number
+        .parse::<u64>()
+        .map_err(|error| format!("invalid size {value:?}: {error}"))?
+        .checked_mul(factor)
+        .ok_or_else(|| format!("size {value:?} too large"))
+}
+
+fn parse_stopwords(path: &str) -> Result<Stopwords, String> {
+    std::fs::read_to_string(path)
+        .map(|contents| Stopwords::parse(&contents))
+        .map_err(|error| format!("failed to read stopwords from {path}: {error}"))
+}
+
+fn parse_highlighter(command: &str) -> Result<ExternalHighlighter, String> {
+    ExternalHighlighter::parse(command).map_err(|error| format!("{error:#}"))
+}
+
+fn parse_line_range(value: &str) -> Result<LineRange, String> {
+    let (start, end) = value
+        .split_once('-')
+        .ok_or_else(|| format!("invalid line range {value:?}, expected START-END"))?;
+    let parse_line = |line: &str| match line.trim().parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("invalid line {line:?} in line range {value:?}")),
+        Ok(line) => Ok(line),
+    };
+    let start = parse_line(start)?;
+    let end = if end.trim().is_empty() {
+        None
+    } else {
+        Some(parse_line(end)?)
+    };
+    match end {
+        Some(end) if end < start => Err(format!("invalid line range {value:?}, end before start")),
+        _ => Ok(LineRange { start, end }),
+    }
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    match value.parse::<f64>() {
+        Ok(seconds) if 0.0 < seconds => {
+            Duration::try_from_secs_f64(seconds).map_err(|error| format!("{error}"))
+        }
+        _ => Err(format!(
+            "invalid timeout {value:?}, expected a positive number of seconds"
+        )),
+    }
+}
+
+fn parse_base_dir(value: &str) -> Result<PathBuf, String> {
+    let path = std::fs::canonicalize(value)
+        .map_err(|error| format!("invalid base directory {value}: {error}"))?;
+    if path.is_dir() {
+        Ok(path)
+    } else {
+        Err(format!("base directory {value} is not a directory"))
+    }
+}
+
+fn parse_user_agent(value: &str) -> Result<String, String> {
+    if value.trim().is_empty() {
+        Err("user agent must not be empty".to_string())
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+fn parse_syntax_theme(value: &str) -> Result<SyntaxTheme, String> {
+    SyntaxTheme::from_name(value).ok_or_else(|| {
+        let names = SyntaxTheme::ALL.map(SyntaxTheme::name);
+        format!(
+            "unknown syntax theme {value:?}, available themes: {}",
+            names.join(", ")
+        )
+    })
+}
+
+fn parse_language_syntax_theme(value: &str) -> Result<(String, SyntaxTheme), String> {
+    match value.split_once('=') {
+        Some((language, theme)) if !language.trim().is_empty() => Ok((
+            language.trim().to_lowercase(),
+            parse_syntax_theme(theme.trim())?,
+        )),
+        _ => Err(format!("invalid value {value:?}, expected \"LANG=NAME\"")),
+    }
+}
+
+fn parse_header(value: &str) -> Result<String, String> {
+    match value.split_once(':') {
+        Some((name, _)) if !name.trim().is_empty() => Ok(value.to_string()),
+        _ => Err(format!(
+            "invalid header {value:?}, expected \"NAME: VALUE\""
+        )),
+    }
+}
+
+fn parse_resolve(value: &str) -> Result<String, String> {
+    let invalid = || format!("invalid value {value:?}, expected \"HOST:PORT:ADDR\"");
+    let mut parts = value.splitn(3, ':');
+    let (Some(host), Some(port), Some(addresses)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    if host.is_empty() || port.parse::<u16>().is_err() || addresses.is_empty() {
+        return Err(invalid());
+    }
+    // Like curl accept a comma-separated list of addresses, and IPv6 addresses in brackets
+    for address in addresses.split(',') {
+        let address = address
+            .strip_prefix('[')
+            .and_then(|address| address.strip_suffix(']'))
+            .unwrap_or(address);
+        if address.parse::<std::net::IpAddr>().is_err() {
+            return Err(format!("invalid IP address {address:?} in {value:?}"));
+        }
+    }
+    Ok(value.to_string())
+}
+
+/// The level of tracing output for `verbose` occurrences of `--verbose`, or `--quiet`.
+///
+/// Disable tracing output by default and if `quiet`, and emit debug or trace information for one
+/// or more `verbose` flags respectively.  `quiet` never enables more output than the default.
+pub fn log_level(verbose: u8, quiet: bool) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) | (false, 0) => LevelFilter::OFF,
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    }
+}
+
+/// Names of terminal programs for `--terminal` and `$MDCAT_TERMINAL`.
+const TERMINAL_NAMES: [(&str, TerminalProgram); 8] = [
+    ("dumb", TerminalProgram::Dumb),
+    ("ansi", TerminalProgram::Ansi),
+    ("iterm2", TerminalProgram::ITerm2),
+    ("terminology", TerminalProgram::Terminology),
+    ("kitty", TerminalProgram::Kitty),
+    ("wezterm", TerminalProgram::WezTerm),
+    ("vscode", TerminalProgram::VSCode),
+    ("ghostty", TerminalProgram::Ghostty),
+];
+
+/// Parse the name of a terminal program, ignoring case.
+pub fn parse_terminal(value: &str) -> Result<TerminalProgram, String> {
+    TERMINAL_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|(_, terminal)| *terminal)
+        .ok_or_else(|| {
+            let names = TERMINAL_NAMES.map(|(name, _)| name);
+            format!(
+                "unknown terminal {value:?}, expected one of {}",
+                names.join(", ")
+            )
+        })
+}
+
+/// Resolve the terminal program to use instead of detecting the terminal.
+///
+/// Use the `flag` given on the command line if any, and otherwise look up `$MDCAT_TERMINAL` with
+/// `lookup`.  Ignore `$MDCAT_TERMINAL` if empty, and return `None` if neither is set.
+pub fn terminal_override<F>(
+    flag: Option<TerminalProgram>,
+    lookup: F,
+) -> anyhow::Result<Option<TerminalProgram>>
+where
+    F: Fn(&str) -> anyhow::Result<Option<String>>,
+{
+    if flag.is_some() {
+        return Ok(flag);
+    }
+    match lookup("MDCAT_TERMINAL")? {
+        Some(name) if !name.is_empty() => parse_terminal(&name)
+            .map(Some)
+            .map_err(|error| anyhow::anyhow!("Invalid value of MDCAT_TERMINAL: {error}")),
+        _ => Ok(None),
+    }
+}
+
+/// How many columns to use for output.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Columns {
+    /// Use the width of the terminal, or a default width if the output is no terminal.
+    #[default]
+    Auto,
+    /// Always use this number of columns.
+    Fixed(u16),
+    /// Use this percentage of the width of the terminal, but at least [`MIN_PERCENTAGE_COLUMNS`].
+    Percentage(u8),
+}
+
+/// The minimum number of columns for a percentage of the terminal width.
+pub const MIN_PERCENTAGE_COLUMNS: u16 = 20;
+
+impl Columns {
+    /// Get the number of columns for a terminal which is `width` columns wide.
+    ///
+    /// Never use more columns than `width` for a percentage, even if `width` is less than
+    /// [`MIN_PERCENTAGE_COLUMNS`].
+    pub fn of_width(self, width: u16) -> u16 {
+        match self {
+            Columns::Auto => width,
+            Columns::Fixed(columns) => columns,
+            Columns::Percentage(percentage) => {
+                let columns = u32::from(width) * u32::from(percentage) / 100;
+                // A percentage is at most 100, so the result always fits into the width
+                u16::try_from(columns)
+                    .unwrap_or(width)
+                    .max(MIN_PERCENTAGE_COLUMNS.min(width))
+            }
+        }
+    }
+}
+
+/// When to use colours and other styles.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColourMode {
+    /// Use colours and styles if the output supports them.
+    #[default]
+    Auto,
+    /// Always use at least ANSI colours and styles, even if the output does not support them.
+    Always,
+    /// Never use colours and styles.
+    Never,
+}
+
+/// How to handle lines in code blocks which are wider than the terminal.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CodeWrap {
+    /// Leave long lines alone, and let the terminal wrap them.
+    #[default]
+    Off,
+    /// Wrap long lines at the last character which fits, and indent continuation lines.
+    Char,
+    /// Truncate long lines, and mark them with an ellipsis.
+    #[value(name = "none")]
+    Truncate,
+}
+
+/// The colour theme to render with.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeName {
+    /// The default theme, for dark backgrounds.
+    #[default]
+    Dark,
+    /// A theme for light backgrounds.
+    Light,
+    /// The Dracula colour palette.
+    Dracula,
+}
+
+impl ThemeName {
+    /// Get the theme of this name.
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeName::Dark => Theme::default(),
+            ThemeName::Light => Theme::light(),
+            ThemeName::Dracula => Theme::dracula(),
+        }
+    }
+}
+
+/// How wide to draw horizontal rules.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum HrWidth {
+    /// The full width of the text.
+    #[default]
+    Full,
+    /// Half the width of the text.
+    Half,
+}
+
+impl HrWidth {
+    /// Get the rule width for this option.
+    pub fn rule_width(self) -> RuleWidth {
+        match self {
+            HrWidth::Full => RuleWidth::Full,
+            HrWidth::Half => RuleWidth::Half,
+        }
+    }
+}
+
+/// An ANSI colour for line numbers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum LineNumberColour {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl LineNumberColour {
+    /// Get the ANSI colour.
+    pub fn colour(self) -> AnsiColor {
+        match self {
+            LineNumberColour::Black => AnsiColor::Black,
+            LineNumberColour::Red => AnsiColor::Red,
+            LineNumberColour::Green => AnsiColor::Green,
+            LineNumberColour::Yellow => AnsiColor::Yellow,
+            LineNumberColour::Blue => AnsiColor::Blue,
+            LineNumberColour::Magenta => AnsiColor::Magenta,
+            LineNumberColour::Cyan => AnsiColor::Cyan,
+            LineNumberColour::White => AnsiColor::White,
+            LineNumberColour::BrightBlack => AnsiColor::BrightBlack,
+            LineNumberColour::BrightRed => AnsiColor::BrightRed,
+            LineNumberColour::BrightGreen => AnsiColor::BrightGreen,
+            LineNumberColour::BrightYellow => AnsiColor::BrightYellow,
+            LineNumberColour::BrightBlue => AnsiColor::BrightBlue,
+            LineNumberColour::BrightMagenta => AnsiColor::BrightMagenta,
+            LineNumberColour::BrightCyan => AnsiColor::BrightCyan,
+            LineNumberColour::BrightWhite => AnsiColor::BrightWhite,
+        }
+    }
+}
+
+/// How to print the detected terminal.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DetectFormat {
+    /// Only the name of the terminal.
+    #[default]
+    Text,
+    /// A JSON object with the name and the capabilities of the terminal.
+    Json,
+}
+
+/// The format to render documents to.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Formatted text for the terminal.
+    #[default]
+    Terminal,
+    /// HTML, keeping all URLs as they are.
+    Html,
+}
+
+impl OutputFormat {
+    /// The file extension for documents in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Terminal => "txt",
+            OutputFormat::Html => "html",
+        }
+    }
+}
+
+/// How to report progress.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressFormat {
+    /// One JSON object per line and file.
+    Json,
+    /// A progress bar for large files, if standard error is a terminal.
+    Bar,
+}
+
 /// What resources mdcat may access.
 #[derive(Debug, Copy, Clone)]
 pub enum ResourceAccess {
+    /// Only allow resources embedded in the document, i.e. `data:` URLs.
+    Sandbox,
     /// Only allow local resources.
     LocalOnly,
     /// Allow remote resources
@@ -128,23 +893,470 @@ pub enum ResourceAccess {
 }
 
 impl CommonArgs {
+    /// Whether output goes to files, either a single file or one file per input.
+    pub fn writes_to_file(&self) -> bool {
+        self.output.is_some() || self.output_dir.is_some()
+    }
+
     /// Whether remote resource access is permitted.
     pub fn resource_access(&self) -> ResourceAccess {
-        if self.local_only {
+        if self.sandbox {
+            ResourceAccess::Sandbox
+        } else if self.local_only {
             ResourceAccess::LocalOnly
         } else {
             ResourceAccess::Remote
         }
     }
+
+    /// The directory to cache remote images in, if caching is enabled.
+    pub fn cache_directory(&self) -> Option<PathBuf> {
+        match &self.cache_dir {
+            Some(directory) => Some(directory.clone()),
+            None if self.cache => default_cache_directory(),
+            None => None,
+        }
+    }
+
+    /// Options for fetching remote resources.
+    pub fn curl_options(&self) -> CurlOptions {
+        CurlOptions {
+            timeout: Duration::from_secs(self.resource_timeout),
+            proxy: self.proxy.clone(),
+            headers: self.headers.clone(),
+            resolve: self.resolve.clone(),
+            user_agent: self.user_agent.clone(),
+            retries: self.resource_retries,
+        }
+    }
+
+    /// Options for the resource handler.
+    pub fn resource_options(&self) -> ResourceOptions {
+        ResourceOptions {
+            read_limit: self.max_resource_size,
+            follow_symlinks: !self.no_follow_symlinks,
+            curl: self.curl_options(),
+            cache_directory: self.cache_directory(),
+        }
+    }
+
+    /// The maximum heading level for the table of contents, if a table of contents was requested.
+    pub fn toc(&self) -> Option<u8> {
+        self.toc.then_some(self.toc_max_depth)
+    }
+
+    /// When to use colours, taking `--no-colour` and `--plain` into account.
+    pub fn colour_mode(&self) -> ColourMode {
+        if self.no_colour || self.plain {
+            ColourMode::Never
+        } else {
+            self.colour
+        }
+    }
+
+    /// When to use colours, also taking `$NO_COLOR` into account.
+    ///
+    /// If `no_color` is true, i.e. `$NO_COLOR` has a non-empty value, disable colours unless
+    /// `--color=always` explicitly enables them.
+    pub fn colour_mode_with_no_color(&self, no_color: bool) -> ColourMode {
+        match self.colour_mode() {
+            ColourMode::Auto if no_color => ColourMode::Never,
+            mode => mode,
+        }
+    }
+
+    /// The markdown extensions to parse.
+    pub fn markdown_extensions(&self) -> MarkdownExtensions {
+        MarkdownExtensions {
+            tables: !self.no_tables,
+            strikethrough: !self.no_strikethrough,
+            tasklists: !self.no_tasklists,
+            smart_punctuation: self.smart_punctuation,
+            definition_lists: self.definition_lists,
+            math: self.math,
+        }
+    }
+
+    /// Options for processing each file.
+    pub fn process_options(&self) -> ProcessOptions {
+        ProcessOptions {
+            line_numbers: self.line_numbers,
+            line_number_options: LineNumberOptions {
+                separator: self.line_number_separator.clone(),
+                colour: self.line_number_colour.map(LineNumberColour::colour),
+                start: self.line_number_start,
+            },
+            stats: self.stats_options(),
+            toc_max_depth: self.toc(),
+            dump_events: self.dump_events,
+            list_anchors: self.list_anchors,
+            print_resources: self.print_resources.then(|| self.resource_access()),
+            check_links: self.check_links,
+            strict_links: self.fail_fast,
+            check_headings: self.check_headings,
+            strict_headings: self.fail_fast,
+            lint: self.strict_markdown,
+            strict_lint: self.fail_fast,
+            dry_run: self.dry_run,
+            max_heading_level: self.max_heading_level,
+            section: self.section.clone(),
+            show_front_matter: self.show_front_matter,
+            front_matter_table: self.front_matter_table,
+            no_images: self.no_images,
+            emoji: self.emoji,
+            task_progress: self.task_progress,
+            tab_width: self.tab_width,
+            wrap_code: self.wrap_code,
+            lines: self.lines,
+            literal: self.literal,
+            max_input_size: self.max_input_size,
+            enable_includes: self.enable_includes,
+            strict_includes: self.fail_fast,
+            base_dir: self.base_dir.clone(),
+            prefetch: self.prefetch,
+            highlighter: self.highlighter.clone(),
+            extensions: self.markdown_extensions(),
+            output_format: self.to,
+            strip_comments: self.strip_comments,
+            progress_bar: self.progress == Some(ProgressFormat::Bar),
+            render_timeout: self.render_timeout,
+            strict_pipe: self.strict_pipe,
+        }
+    }
+
+    /// The colour theme, with horizontal rules as requested.
+    pub fn colour_theme(&self) -> Theme {
+        self.theme
+            .theme()
+            .with_rule(self.hr_char, self.hr_width.rule_width())
+    }
+
+    /// Syntax themes for highlighted code blocks.
+    pub fn syntax_themes(&self) -> SyntaxThemes {
+        SyntaxThemes {
+            default: self.syntax_theme,
+            languages: self.syntax_theme_for.iter().cloned().collect(),
+        }
+    }
+
+    /// Options for document statistics, if statistics were requested.
+    pub fn stats_options(&self) -> Option<StatsOptions> {
+        let stats = self.stats || self.stats_summary || self.count_only || self.since_git.is_some();
+        stats.then(|| StatsOptions {
+            format: self.stats_format,
+            summary: self.stats_summary,
+            count_only: self.count_only,
+            words_per_minute: self.reading_speed,
+            exclude_code: self.stats_exclude_code,
+            graphemes: self.stats_graphemes,
+            columns: match self.columns {
+                Columns::Fixed(columns) => Some(columns),
+                Columns::Auto | Columns::Percentage(_) => None,
+            },
+            sections: self.stats_sections,
+            top_words: self.stats_top_words,
+            stopwords: self.stopwords.clone().unwrap_or_default(),
+            extensions: self.markdown_extensions(),
+            since_git: self.since_git.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Args;
-    use clap::CommandFactory;
+    use super::{
+        log_level, parse_line_range, parse_resolve, parse_size, parse_terminal, terminal_override,
+        Args, ColourMode, Columns,
+    };
+    use crate::{LineRange, MarkdownExtensions, DEFAULT_RESOURCE_READ_LIMIT};
+    use clap::{CommandFactory, Parser};
+    use pulldown_cmark_mdcat::{SyntaxTheme, SyntaxThemes, TerminalProgram};
+    use tracing::level_filters::LevelFilter;
 
     #[test]
     fn verify_app() {
         Args::command().debug_assert();
     }
+
+    #[test]
+    fn syntax_themes() {
+        let args = Args::parse_from([
+            "mdcat",
+            "--syntax-theme",
+            "Bright",
+            "--syntax-theme-for",
+            "SQL=monochrome",
+        ]);
+        let themes = args.command.syntax_themes();
+        assert_eq!(themes.default, SyntaxTheme::Bright);
+        assert_eq!(themes.for_language(["sql"]), SyntaxTheme::Monochrome);
+        assert_eq!(themes.for_language(["rust"]), SyntaxTheme::Bright);
+        assert_eq!(
+            Args::parse_from(["mdcat"]).command.syntax_themes(),
+            SyntaxThemes::default()
+        );
+
+        let error = Args::try_parse_from(["mdcat", "--syntax-theme", "neon"]).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("available themes: solarized, bright, monochrome"),
+            "{error}"
+        );
+        assert!(Args::try_parse_from(["mdcat", "--syntax-theme-for", "sql"]).is_err());
+    }
+
+    #[test]
+    fn proxy_in_curl_options() {
+        let args = Args::parse_from(["mdcat", "--proxy", "http://proxy.invalid:3128"]);
+        assert_eq!(
+            args.command.curl_options().proxy.as_deref(),
+            Some("http://proxy.invalid:3128")
+        );
+        let args = Args::parse_from(["mdcat"]);
+        assert_eq!(args.command.curl_options().proxy, None);
+    }
+
+    #[test]
+    fn plain_disables_pager() {
+        assert!(Args::parse_from(["mdless"]).command.paginate());
+        assert!(!Args::parse_from(["mdless", "--plain"]).command.paginate());
+        assert!(!Args::parse_from(["mdcat", "--paginate", "--plain"])
+            .command
+            .paginate());
+    }
+
+    #[test]
+    fn auto_paginate_by_default() {
+        let auto_paginate = |args: &[&str]| Args::parse_from(args).command.auto_paginate();
+        assert!(auto_paginate(&["mdcat"]));
+        assert!(!auto_paginate(&["mdcat", "--no-pager"]));
+        assert!(!auto_paginate(&["mdcat", "--paginate"]));
+        assert!(!auto_paginate(&["mdcat", "--plain"]));
+        assert!(!auto_paginate(&["mdcat", "--output", "out.txt"]));
+        assert!(!auto_paginate(&["mdless"]));
+        assert!(!auto_paginate(&["mdless", "--no-pager"]));
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn clipboard_conflicts_with_filenames() {
+        assert!(Args::try_parse_from(["mdcat", "--clipboard"]).is_ok());
+        assert!(Args::try_parse_from(["mdcat", "--clipboard", "README.md"]).is_err());
+        assert!(Args::try_parse_from(["mdcat", "--clipboard", "-"]).is_err());
+    }
+
+    #[test]
+    fn gfm_overrides_disabled_extensions() {
+        let extensions = |args: &[&str]| {
+            Args::parse_from(["mdcat"].iter().chain(args))
+                .command
+                .markdown_extensions()
+        };
+        assert_eq!(extensions(&[]), MarkdownExtensions::default());
+        assert_eq!(
+            extensions(&["--no-tables", "--no-tasklists"]),
+            MarkdownExtensions {
+                tables: false,
+                strikethrough: true,
+                tasklists: false,
+                smart_punctuation: false,
+                definition_lists: false,
+                math: false
+            }
+        );
+        assert_eq!(
+            extensions(&["--no-tables", "--no-strikethrough", "--gfm"]),
+            MarkdownExtensions::default()
+        );
+        assert_eq!(
+            extensions(&["--gfm", "--no-strikethrough"]),
+            MarkdownExtensions {
+                tables: true,
+                strikethrough: false,
+                tasklists: true,
+                smart_punctuation: false,
+                definition_lists: false,
+                math: false
+            }
+        );
+    }
+
+    #[test]
+    fn parse_line_ranges() {
+        assert_eq!(
+            parse_line_range("10-20"),
+            Ok(LineRange {
+                start: 10,
+                end: Some(20)
+            })
+        );
+        assert_eq!(
+            parse_line_range("30-"),
+            Ok(LineRange {
+                start: 30,
+                end: None
+            })
+        );
+        assert!(parse_line_range("20-10").is_err());
+        assert!(parse_line_range("0-10").is_err());
+        assert!(parse_line_range("10").is_err());
+        assert!(parse_line_range("a-b").is_err());
+    }
+
+    #[test]
+    fn parse_columns() {
+        assert_eq!(super::parse_columns("auto"), Ok(Columns::Auto));
+        assert_eq!(super::parse_columns("100"), Ok(Columns::Fixed(100)));
+        assert!(super::parse_columns("0").is_err());
+        assert!(super::parse_columns("wide").is_err());
+        assert_eq!(super::parse_columns("60%"), Ok(Columns::Percentage(60)));
+        assert_eq!(super::parse_columns("100%"), Ok(Columns::Percentage(100)));
+        assert!(super::parse_columns("0%").is_err());
+        assert!(super::parse_columns("101%").is_err());
+        assert!(super::parse_columns("-5%").is_err());
+        assert!(super::parse_columns("%").is_err());
+        let args = Args::parse_from(["mdcat"]);
+        assert_eq!(args.command.columns, Columns::Auto);
+    }
+
+    #[test]
+    fn columns_of_width() {
+        assert_eq!(Columns::Auto.of_width(200), 200);
+        assert_eq!(Columns::Fixed(80).of_width(200), 80);
+        assert_eq!(Columns::Percentage(60).of_width(200), 120);
+        assert_eq!(Columns::Percentage(100).of_width(80), 80);
+        // Clamp to at least 20 columns, but never more than the terminal has
+        assert_eq!(Columns::Percentage(10).of_width(100), 20);
+        assert_eq!(Columns::Percentage(50).of_width(30), 20);
+        assert_eq!(Columns::Percentage(50).of_width(15), 15);
+    }
+
+    #[test]
+    fn log_levels() {
+        assert_eq!(log_level(0, false), LevelFilter::OFF);
+        assert_eq!(log_level(1, false), LevelFilter::DEBUG);
+        assert_eq!(log_level(2, false), LevelFilter::TRACE);
+        assert_eq!(log_level(5, false), LevelFilter::TRACE);
+        assert_eq!(log_level(0, true), LevelFilter::OFF);
+        assert_eq!(log_level(2, true), LevelFilter::OFF);
+
+        let args = Args::parse_from(["mdcat", "-vv"]);
+        assert_eq!(
+            log_level(args.command.verbose, args.command.quiet),
+            LevelFilter::TRACE
+        );
+        let args = Args::parse_from(["mdcat", "-v", "--verbose"]);
+        assert_eq!(args.command.verbose, 2);
+        assert!(Args::try_parse_from(["mdcat", "-q", "-v"]).is_err());
+    }
+
+    #[test]
+    fn no_color() {
+        let args = Args::parse_from(["mdcat"]).command;
+        assert_eq!(args.colour_mode_with_no_color(false), ColourMode::Auto);
+        assert_eq!(args.colour_mode_with_no_color(true), ColourMode::Never);
+        let args = Args::parse_from(["mdcat", "--color=always"]).command;
+        assert_eq!(args.colour_mode_with_no_color(true), ColourMode::Always);
+        let args = Args::parse_from(["mdcat", "--no-colour"]).command;
+        assert_eq!(args.colour_mode_with_no_color(false), ColourMode::Never);
+    }
+
+    #[test]
+    fn terminal_overrides() {
+        assert_eq!(parse_terminal("kitty"), Ok(TerminalProgram::Kitty));
+        assert_eq!(parse_terminal("iTerm2"), Ok(TerminalProgram::ITerm2));
+        assert_eq!(parse_terminal("WezTerm"), Ok(TerminalProgram::WezTerm));
+        let error = parse_terminal("xterm").unwrap_err();
+        assert!(error.contains("dumb, ansi, iterm2"), "{error}");
+
+        let env = |value: &'static str| move |_: &str| Ok(Some(value.to_string()));
+        assert_eq!(
+            terminal_override(Some(TerminalProgram::Dumb), env("kitty")).unwrap(),
+            Some(TerminalProgram::Dumb)
+        );
+        assert_eq!(
+            terminal_override(None, env("kitty")).unwrap(),
+            Some(TerminalProgram::Kitty)
+        );
+        assert_eq!(terminal_override(None, env("")).unwrap(), None);
+        assert_eq!(terminal_override(None, |_| Ok(None)).unwrap(), None);
+        assert!(terminal_override(None, env("xterm")).is_err());
+
+        let args = Args::parse_from(["mdcat", "--terminal", "ansi"]);
+        assert_eq!(args.command.terminal, Some(TerminalProgram::Ansi));
+        assert!(Args::try_parse_from(["mdcat", "--terminal", "xterm"]).is_err());
+    }
+
+    #[test]
+    fn reject_empty_user_agent() {
+        let args = Args::parse_from(["mdcat", "--user-agent", "curl/8.0"]);
+        assert_eq!(
+            args.command.curl_options().user_agent.as_deref(),
+            Some("curl/8.0")
+        );
+        assert!(Args::try_parse_from(["mdcat", "--user-agent", ""]).is_err());
+    }
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!(parse_size("1K"), Ok(1024));
+        assert_eq!(parse_size("2M"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_size("3g"), Ok(3 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("99999999999999999G").is_err());
+    }
+
+    #[test]
+    fn default_max_resource_size() {
+        let args = Args::parse_from(["mdcat"]);
+        assert_eq!(args.command.max_resource_size, DEFAULT_RESOURCE_READ_LIMIT);
+    }
+
+    #[test]
+    fn headers_accumulate() {
+        let args = Args::parse_from([
+            "mdcat",
+            "--header",
+            "Authorization: Bearer secret",
+            "--header",
+            "X-Foo:bar",
+        ]);
+        assert_eq!(
+            args.command.curl_options().headers,
+            vec!["Authorization: Bearer secret", "X-Foo:bar"]
+        );
+    }
+
+    #[test]
+    fn reject_header_without_colon() {
+        let error = Args::try_parse_from(["mdcat", "--header", "Authorization"]).unwrap_err();
+        assert!(
+            error.to_string().contains("expected \"NAME: VALUE\""),
+            "{error}"
+        );
+        assert!(Args::try_parse_from(["mdcat", "--header", ": value"]).is_err());
+    }
+
+    #[test]
+    fn parse_resolve_triplets() {
+        assert_eq!(
+            parse_resolve("images.example.com:443:127.0.0.1"),
+            Ok("images.example.com:443:127.0.0.1".to_string())
+        );
+        assert!(parse_resolve("example.com:80:[::1]").is_ok());
+        assert!(parse_resolve("example.com:80:::1").is_ok());
+        assert!(parse_resolve("example.com:80:10.0.0.1,10.0.0.2").is_ok());
+        assert!(parse_resolve("example.com:80")
+            .unwrap_err()
+            .contains("expected \"HOST:PORT:ADDR\""));
+        assert!(parse_resolve(":80:127.0.0.1").is_err());
+        assert!(parse_resolve("example.com:http:127.0.0.1").is_err());
+        assert!(parse_resolve("example.com:80:").is_err());
+        assert!(parse_resolve("example.com:80:localhost")
+            .unwrap_err()
+            .contains("invalid IP address \"localhost\""));
+    }
 }
@@ -0,0 +1,62 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{event, Level};
+
+/// Commands which print the contents of the clipboard on the current platform, in order of
+/// preference.
+fn paste_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(windows) {
+        vec![(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                "Get-Clipboard -Raw",
+            ],
+        )]
+    } else if cfg!(target_os = "macos") {
+        vec![("pbpaste", &[])]
+    } else {
+        let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-paste", &["--no-newline"]));
+        }
+        commands.push(("xclip", &["-selection", "clipboard", "-out"]));
+        commands.push(("xsel", &["--clipboard", "--output"]));
+        commands
+    }
+}
+
+/// Read the text contents of the clipboard.
+///
+/// Run the standard command line tool of the current platform to read the clipboard, i.e.
+/// `Get-Clipboard` on Windows, `pbpaste` on macOS, and `wl-paste`, `xclip`, or `xsel` on other
+/// systems, whichever is available first.
+pub fn read_clipboard() -> Result<String> {
+    let mut errors = Vec::new();
+    for (program, args) in paste_commands() {
+        let result = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output();
+        match result {
+            Ok(output) if output.status.success() => {
+                event!(Level::DEBUG, "Read clipboard with {}", program);
+                return String::from_utf8(output.stdout)
+                    .with_context(|| "Clipboard contents are not valid UTF-8".to_string());
+            }
+            Ok(output) => errors.push(format!("{program} failed with {}", output.status)),
+            Err(error) => errors.push(format!("{program}: {error}")),
+        }
+    }
+    Err(anyhow!("Failed to read clipboard: {}", errors.join(", ")))
+}
@@ -0,0 +1,269 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::BTreeMap;
+use std::env::VarError;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+/// Default options for mdcat, from a configuration file.
+///
+/// mdcat turns these options into command line arguments in front of all arguments given
+/// explicitly, so explicit arguments override the configuration file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Maximum number of columns to use for output.
+    pub columns: Option<u16>,
+    /// Disable all colours and other styles.
+    pub no_colour: Option<bool>,
+    /// Whether to paginate output.
+    pub paginate: Option<bool>,
+    /// Whether to cache remote images.
+    pub cache: Option<bool>,
+    /// Reading speed in words per minute.
+    pub reading_speed: Option<usize>,
+    /// The name of the colour theme.
+    pub theme: Option<String>,
+    /// The name of the syntax theme for code blocks.
+    pub syntax_theme: Option<String>,
+    /// Names of syntax themes for code blocks in specific languages, by language.
+    pub syntax_themes: Option<BTreeMap<String, String>>,
+}
+
+impl Config {
+    /// Parse a configuration from TOML `contents`.
+    pub fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Read a configuration from the TOML file at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read configuration file {}", path.display()))?;
+        Self::from_toml(&contents)
+            .with_context(|| format!("Invalid configuration file {}", path.display()))
+    }
+
+    /// Convert this configuration into equivalent command line arguments.
+    pub fn to_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if let Some(columns) = self.columns {
+            args.push(format!("--columns={columns}").into());
+        }
+        if self.no_colour == Some(true) {
+            args.push("--no-colour".into());
+        }
+        match self.paginate {
+            Some(true) => args.push("--paginate".into()),
+            Some(false) => args.push("--no-pager".into()),
+            None => {}
+        }
+        match self.cache {
+            Some(true) => args.push("--cache".into()),
+            Some(false) => args.push("--no-cache".into()),
+            None => {}
+        }
+        if let Some(wpm) = self.reading_speed {
+            args.push(format!("--reading-speed={wpm}").into());
+        }
+        if let Some(theme) = &self.theme {
+            args.push(format!("--theme={theme}").into());
+        }
+        if let Some(theme) = &self.syntax_theme {
+            args.push(format!("--syntax-theme={theme}").into());
+        }
+        for (language, theme) in self.syntax_themes.iter().flatten() {
+            args.push(format!("--syntax-theme-for={language}={theme}").into());
+        }
+        args
+    }
+}
+
+/// Get the default path of the configuration file.
+///
+/// Use `$XDG_CONFIG_HOME/mdcat/config.toml` or `~/.config/mdcat/config.toml`, and
+/// `%APPDATA%\mdcat\config.toml` on Windows.  Return `None` if the relevant environment
+/// variables are not set.
+pub fn default_config_path() -> Option<PathBuf> {
+    let non_empty = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    let base = if cfg!(windows) {
+        non_empty("APPDATA").map(PathBuf::from)
+    } else {
+        non_empty("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| non_empty("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    base.map(|base| base.join("mdcat").join("config.toml"))
+}
+
+/// Find the configuration file requested by command line `args`.
+///
+/// Return `None` if `args` contain `--no-config`, the path given with `--config` if any, and the
+/// default path otherwise.  The boolean tells whether the configuration file was requested
+/// explicitly, in which case it must exist.
+fn requested_config(args: &[OsString]) -> Option<(PathBuf, bool)> {
+    let mut explicit = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == "--no-config" {
+            return None;
+        } else if arg == "--config" {
+            explicit = args.next().map(PathBuf::from);
+        } else if let Some(path) = arg.to_str().and_then(|s| s.strip_prefix("--config=")) {
+            explicit = Some(PathBuf::from(path));
+        }
+    }
+    match explicit {
+        Some(path) => Some((path, true)),
+        None => default_config_path().map(|path| (path, false)),
+    }
+}
+
+/// Split the value of `$MDCAT_OPTS` into arguments.
+///
+/// Follow shell quoting rules, so that quoted arguments may contain whitespace.
+pub fn parse_opts(value: &str) -> Result<Vec<OsString>> {
+    let words = shell_words::split(value)
+        .with_context(|| format!("Failed to parse value {value} of MDCAT_OPTS"))?;
+    Ok(words.into_iter().map(OsString::from).collect())
+}
+
+/// Insert options from `$MDCAT_OPTS` in front of command line `args`.
+///
+/// `args` includes the program name.
+pub fn args_with_env(mut args: Vec<OsString>) -> Result<Vec<OsString>> {
+    let opts = match std::env::var("MDCAT_OPTS") {
+        Ok(value) => parse_opts(&value)?,
+        Err(VarError::NotPresent) => Vec::new(),
+        Err(VarError::NotUnicode(value)) => bail!("Value of MDCAT_OPTS not unicode: {value:?}"),
+    };
+    let position = 1.min(args.len());
+    args.splice(position..position, opts);
+    Ok(args)
+}
+
+/// Insert options from the configuration file in front of command line `args`.
+///
+/// `args` includes the program name.  Take the configuration file from `--config` and fall back
+/// to the default path, unless `args` contain `--no-config`.  Ignore a missing configuration
+/// file at the default path.
+pub fn args_with_config(mut args: Vec<OsString>) -> Result<Vec<OsString>> {
+    let config = match requested_config(args.get(1..).unwrap_or_default()) {
+        Some((path, explicit)) if explicit || path.is_file() => Config::from_file(&path)?,
+        _ => Config::default(),
+    };
+    let position = 1.min(args.len());
+    args.splice(position..position, config.to_args());
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn load_sample_config() {
+        let config = Config::from_toml(
+            "columns = 80\nno_colour = true\npaginate = false\ncache = true\nreading_speed = 300\ntheme = \"light\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                columns: Some(80),
+                no_colour: Some(true),
+                paginate: Some(false),
+                cache: Some(true),
+                reading_speed: Some(300),
+                theme: Some("light".to_string()),
+                ..Config::default()
+            }
+        );
+        assert_eq!(
+            config.to_args(),
+            vec![
+                "--columns=80",
+                "--no-colour",
+                "--no-pager",
+                "--cache",
+                "--reading-speed=300",
+                "--theme=light"
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_themes_in_config() {
+        let config = Config::from_toml(
+            "syntax_theme = \"bright\"\n\n[syntax_themes]\nsql = \"monochrome\"\nrust = \"solarized\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.to_args(),
+            vec![
+                "--syntax-theme=bright",
+                "--syntax-theme-for=rust=solarized",
+                "--syntax-theme-for=sql=monochrome"
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_unknown_and_invalid_fields() {
+        let error = Config::from_toml("colums = 80\n").unwrap_err();
+        assert!(
+            error.to_string().contains("unknown field `colums`"),
+            "{error}"
+        );
+        let error = Config::from_toml("columns = \"wide\"\n").unwrap_err();
+        assert!(error.to_string().contains("columns"), "{error}");
+    }
+
+    #[test]
+    fn parse_opts_with_quotes() {
+        assert_eq!(
+            parse_opts("--columns 80  --file-separator '--- {file} ---' \"a b.md\" c\\ d.md")
+                .unwrap(),
+            vec![
+                "--columns",
+                "80",
+                "--file-separator",
+                "--- {file} ---",
+                "a b.md",
+                "c d.md"
+            ]
+        );
+        assert!(parse_opts("").unwrap().is_empty());
+        assert!(parse_opts("--file-separator 'unterminated").is_err());
+    }
+
+    #[test]
+    fn config_arguments() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(requested_config(&args(&["--no-config", "a.md"])), None);
+        assert_eq!(
+            requested_config(&args(&["--config", "mdcat.toml", "a.md"])),
+            Some((PathBuf::from("mdcat.toml"), true))
+        );
+        assert_eq!(
+            requested_config(&args(&["--config=mdcat.toml"])),
+            Some((PathBuf::from("mdcat.toml"), true))
+        );
+        // Filenames after -- are no options
+        assert_eq!(
+            requested_config(&args(&["--", "--no-config"])),
+            default_config_path().map(|path| (path, false))
+        );
+    }
+}
@@ -0,0 +1,633 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::borrow::Cow;
+
+/// Common GitHub emoji shortcodes and their unicode characters, sorted by shortcode.
+static SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("1234", "🔢"),
+    ("8ball", "🎱"),
+    ("a", "🅰️"),
+    ("ab", "🆎"),
+    ("abc", "🔤"),
+    ("airplane", "✈️"),
+    ("alarm_clock", "⏰"),
+    ("alien", "👽"),
+    ("ambulance", "🚑"),
+    ("anchor", "⚓"),
+    ("angel", "👼"),
+    ("anger", "💢"),
+    ("angry", "😠"),
+    ("ant", "🐜"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇️"),
+    ("arrow_forward", "▶️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("arrows_counterclockwise", "🔄"),
+    ("art", "🎨"),
+    ("astonished", "😲"),
+    ("atom_symbol", "⚛️"),
+    ("b", "🅱️"),
+    ("baby", "👶"),
+    ("balloon", "🎈"),
+    ("bamboo", "🎍"),
+    ("banana", "🍌"),
+    ("bangbang", "‼️"),
+    ("bank", "🏦"),
+    ("bar_chart", "📊"),
+    ("baseball", "⚾"),
+    ("basketball", "🏀"),
+    ("bat", "🦇"),
+    ("bath", "🛀"),
+    ("battery", "🔋"),
+    ("bear", "🐻"),
+    ("bee", "🐝"),
+    ("beer", "🍺"),
+    ("beers", "🍻"),
+    ("beetle", "🐞"),
+    ("beginner", "🔰"),
+    ("bell", "🔔"),
+    ("bento", "🍱"),
+    ("bike", "🚲"),
+    ("bird", "🐦"),
+    ("birthday", "🎂"),
+    ("black_circle", "⚫"),
+    ("blossom", "🌼"),
+    ("blue_book", "📘"),
+    ("blue_heart", "💙"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("bookmark", "🔖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("boot", "👢"),
+    ("bouquet", "💐"),
+    ("bow", "🙇"),
+    ("bowling", "🎳"),
+    ("boy", "👦"),
+    ("bread", "🍞"),
+    ("bricks", "🧱"),
+    ("broken_heart", "💔"),
+    ("brown_heart", "🤎"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("bus", "🚌"),
+    ("butterfly", "🦋"),
+    ("cactus", "🌵"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("calling", "📲"),
+    ("camel", "🐫"),
+    ("camera", "📷"),
+    ("candle", "🕯️"),
+    ("candy", "🍬"),
+    ("car", "🚗"),
+    ("card_index", "📇"),
+    ("carrot", "🥕"),
+    ("cat", "🐱"),
+    ("cd", "💿"),
+    ("chart_with_downwards_trend", "📉"),
+    ("chart_with_upwards_trend", "📈"),
+    ("checkered_flag", "🏁"),
+    ("cherries", "🍒"),
+    ("chestnut", "🌰"),
+    ("chicken", "🐔"),
+    ("children_crossing", "🚸"),
+    ("chocolate_bar", "🍫"),
+    ("christmas_tree", "🎄"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("clock1", "🕐"),
+    ("closed_book", "📕"),
+    ("closed_lock_with_key", "🔐"),
+    ("cloud", "☁️"),
+    ("clown_face", "🤡"),
+    ("coffee", "☕"),
+    ("cold_sweat", "😰"),
+    ("collision", "💥"),
+    ("computer", "💻"),
+    ("confetti_ball", "🎊"),
+    ("confounded", "😖"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("construction_worker", "👷"),
+    ("cookie", "🍪"),
+    ("cool", "🆒"),
+    ("cop", "👮"),
+    ("copyright", "©️"),
+    ("corn", "🌽"),
+    ("cow", "🐮"),
+    ("crab", "🦀"),
+    ("credit_card", "💳"),
+    ("crescent_moon", "🌙"),
+    ("crocodile", "🐊"),
+    ("crossed_fingers", "🤞"),
+    ("crown", "👑"),
+    ("cry", "😢"),
+    ("crying_cat_face", "😿"),
+    ("crystal_ball", "🔮"),
+    ("cupid", "💘"),
+    ("cyclone", "🌀"),
+    ("dancer", "💃"),
+    ("dart", "🎯"),
+    ("dash", "💨"),
+    ("date", "📅"),
+    ("deer", "🦌"),
+    ("desktop_computer", "🖥️"),
+    ("detective", "🕵️"),
+    ("diamond_shape_with_a_dot_inside", "💠"),
+    ("disappointed", "😞"),
+    ("dizzy", "💫"),
+    ("dizzy_face", "😵"),
+    ("dna", "🧬"),
+    ("dog", "🐶"),
+    ("dollar", "💵"),
+    ("dolphin", "🐬"),
+    ("door", "🚪"),
+    ("doughnut", "🍩"),
+    ("dove", "🕊️"),
+    ("dragon", "🐉"),
+    ("droplet", "💧"),
+    ("duck", "🦆"),
+    ("e-mail", "📧"),
+    ("eagle", "🦅"),
+    ("ear", "👂"),
+    ("earth_africa", "🌍"),
+    ("earth_americas", "🌎"),
+    ("earth_asia", "🌏"),
+    ("egg", "🥚"),
+    ("eggplant", "🍆"),
+    ("elephant", "🐘"),
+    ("email", "📧"),
+    ("envelope", "✉️"),
+    ("euro", "💶"),
+    ("exclamation", "❗"),
+    ("expressionless", "😑"),
+    ("eyes", "👀"),
+    ("facepalm", "🤦"),
+    ("factory", "🏭"),
+    ("fallen_leaf", "🍂"),
+    ("fast_forward", "⏩"),
+    ("fax", "📠"),
+    ("fearful", "😨"),
+    ("feet", "🐾"),
+    ("file_folder", "📁"),
+    ("fire", "🔥"),
+    ("fire_engine", "🚒"),
+    ("fireworks", "🎆"),
+    ("fish", "🐟"),
+    ("fist", "✊"),
+    ("flags", "🎏"),
+    ("flashlight", "🔦"),
+    ("floppy_disk", "💾"),
+    ("flushed", "😳"),
+    ("fog", "🌫️"),
+    ("football", "🏈"),
+    ("fork_and_knife", "🍴"),
+    ("four_leaf_clover", "🍀"),
+    ("fox_face", "🦊"),
+    ("frog", "🐸"),
+    ("fuelpump", "⛽"),
+    ("gear", "⚙️"),
+    ("gem", "💎"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("gift_heart", "💝"),
+    ("girl", "👧"),
+    ("globe_with_meridians", "🌐"),
+    ("goat", "🐐"),
+    ("grapes", "🍇"),
+    ("green_apple", "🍏"),
+    ("green_book", "📗"),
+    ("green_heart", "💚"),
+    ("grey_exclamation", "❕"),
+    ("grey_question", "❔"),
+    ("grimacing", "😬"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("guitar", "🎸"),
+    ("gun", "🔫"),
+    ("hamburger", "🍔"),
+    ("hammer", "🔨"),
+    ("hammer_and_wrench", "🛠️"),
+    ("hamster", "🐹"),
+    ("hand", "✋"),
+    ("handshake", "🤝"),
+    ("hankey", "💩"),
+    ("hash", "#️⃣"),
+    ("hatching_chick", "🐣"),
+    ("headphones", "🎧"),
+    ("hear_no_evil", "🙉"),
+    ("heart", "❤️"),
+    ("heart_eyes", "😍"),
+    ("heartbeat", "💓"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_dollar_sign", "💲"),
+    ("heavy_exclamation_mark", "❗"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_multiplication_x", "✖️"),
+    ("heavy_plus_sign", "➕"),
+    ("hedgehog", "🦔"),
+    ("helicopter", "🚁"),
+    ("herb", "🌿"),
+    ("hibiscus", "🌺"),
+    ("high_brightness", "🔆"),
+    ("hocho", "🔪"),
+    ("honey_pot", "🍯"),
+    ("horse", "🐴"),
+    ("hospital", "🏥"),
+    ("hotel", "🏨"),
+    ("hourglass", "⌛"),
+    ("hourglass_flowing_sand", "⏳"),
+    ("house", "🏠"),
+    ("hugs", "🤗"),
+    ("hushed", "😯"),
+    ("ice_cream", "🍨"),
+    ("icecream", "🍦"),
+    ("id", "🆔"),
+    ("imp", "👿"),
+    ("inbox_tray", "📥"),
+    ("incoming_envelope", "📨"),
+    ("information_source", "ℹ️"),
+    ("innocent", "😇"),
+    ("iphone", "📱"),
+    ("jack_o_lantern", "🎃"),
+    ("jeans", "👖"),
+    ("joy", "😂"),
+    ("joy_cat", "😹"),
+    ("key", "🔑"),
+    ("keyboard", "⌨️"),
+    ("kiss", "💋"),
+    ("kissing", "😗"),
+    ("kissing_heart", "😘"),
+    ("kiwi_fruit", "🥝"),
+    ("koala", "🐨"),
+    ("label", "🏷️"),
+    ("lady_beetle", "🐞"),
+    ("ladybug", "🐞"),
+    ("laughing", "😆"),
+    ("leaves", "🍃"),
+    ("ledger", "📒"),
+    ("lemon", "🍋"),
+    ("leopard", "🐆"),
+    ("light_rail", "🚈"),
+    ("link", "🔗"),
+    ("lion", "🦁"),
+    ("lipstick", "💄"),
+    ("lizard", "🦎"),
+    ("lock", "🔒"),
+    ("lock_with_ink_pen", "🔏"),
+    ("lollipop", "🍭"),
+    ("loudspeaker", "📢"),
+    ("love_letter", "💌"),
+    ("mag", "🔍"),
+    ("mag_right", "🔎"),
+    ("mailbox", "📫"),
+    ("man", "👨"),
+    ("mans_shoe", "👞"),
+    ("maple_leaf", "🍁"),
+    ("mask", "😷"),
+    ("medal_sports", "🏅"),
+    ("mega", "📣"),
+    ("memo", "📝"),
+    ("microphone", "🎤"),
+    ("microscope", "🔬"),
+    ("milky_way", "🌌"),
+    ("minidisc", "💽"),
+    ("money_with_wings", "💸"),
+    ("moneybag", "💰"),
+    ("monkey", "🐒"),
+    ("monkey_face", "🐵"),
+    ("moon", "🌔"),
+    ("mortar_board", "🎓"),
+    ("mountain", "⛰️"),
+    ("mouse", "🐭"),
+    ("movie_camera", "🎥"),
+    ("muscle", "💪"),
+    ("mushroom", "🍄"),
+    ("musical_note", "🎵"),
+    ("nail_care", "💅"),
+    ("necktie", "👔"),
+    ("nerd_face", "🤓"),
+    ("neutral_face", "😐"),
+    ("new", "🆕"),
+    ("new_moon", "🌑"),
+    ("newspaper", "📰"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("no_mouth", "😶"),
+    ("nose", "👃"),
+    ("notebook", "📓"),
+    ("notes", "🎶"),
+    ("nut_and_bolt", "🔩"),
+    ("o", "⭕"),
+    ("ocean", "🌊"),
+    ("octopus", "🐙"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("older_man", "👴"),
+    ("open_book", "📖"),
+    ("open_file_folder", "📂"),
+    ("open_hands", "👐"),
+    ("open_mouth", "😮"),
+    ("orange_book", "📙"),
+    ("orange_heart", "🧡"),
+    ("owl", "🦉"),
+    ("ox", "🐂"),
+    ("package", "📦"),
+    ("page_facing_up", "📄"),
+    ("page_with_curl", "📃"),
+    ("pager", "📟"),
+    ("palm_tree", "🌴"),
+    ("panda_face", "🐼"),
+    ("paperclip", "📎"),
+    ("parking", "🅿️"),
+    ("partly_sunny", "⛅"),
+    ("partying_face", "🥳"),
+    ("passport_control", "🛂"),
+    ("peach", "🍑"),
+    ("peanuts", "🥜"),
+    ("pear", "🍐"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("penguin", "🐧"),
+    ("pensive", "😔"),
+    ("persevere", "😣"),
+    ("phone", "☎️"),
+    ("pig", "🐷"),
+    ("pill", "💊"),
+    ("pineapple", "🍍"),
+    ("pizza", "🍕"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("point_up_2", "👆"),
+    ("poop", "💩"),
+    ("popcorn", "🍿"),
+    ("post_office", "🏣"),
+    ("potato", "🥔"),
+    ("pouting_cat", "😾"),
+    ("pray", "🙏"),
+    ("pretzel", "🥨"),
+    ("printer", "🖨️"),
+    ("punch", "👊"),
+    ("purple_heart", "💜"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rabbit", "🐰"),
+    ("racehorse", "🐎"),
+    ("radio", "📻"),
+    ("rage", "😡"),
+    ("rainbow", "🌈"),
+    ("raised_hands", "🙌"),
+    ("ram", "🐏"),
+    ("recycle", "♻️"),
+    ("red_circle", "🔴"),
+    ("registered", "®️"),
+    ("relaxed", "☺️"),
+    ("relieved", "😌"),
+    ("repeat", "🔁"),
+    ("rewind", "⏪"),
+    ("rhinoceros", "🦏"),
+    ("ribbon", "🎀"),
+    ("rice", "🍚"),
+    ("ring", "💍"),
+    ("robot", "🤖"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("roll_eyes", "🙄"),
+    ("rooster", "🐓"),
+    ("rose", "🌹"),
+    ("rotating_light", "🚨"),
+    ("round_pushpin", "📍"),
+    ("running", "🏃"),
+    ("sa", "🈂️"),
+    ("sake", "🍶"),
+    ("sandwich", "🥪"),
+    ("santa", "🎅"),
+    ("satellite", "📡"),
+    ("satisfied", "😆"),
+    ("saxophone", "🎷"),
+    ("scissors", "✂️"),
+    ("scream", "😱"),
+    ("scroll", "📜"),
+    ("see_no_evil", "🙈"),
+    ("seedling", "🌱"),
+    ("shark", "🦈"),
+    ("sheep", "🐑"),
+    ("shell", "🐚"),
+    ("ship", "🚢"),
+    ("shirt", "👕"),
+    ("shrug", "🤷"),
+    ("shushing_face", "🤫"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("sleepy", "😪"),
+    ("slightly_frowning_face", "🙁"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smile_cat", "😸"),
+    ("smiley", "😃"),
+    ("smiley_cat", "😺"),
+    ("smiling_imp", "😈"),
+    ("smirk", "😏"),
+    ("snail", "🐌"),
+    ("snake", "🐍"),
+    ("sneezing_face", "🤧"),
+    ("snowflake", "❄️"),
+    ("snowman", "⛄"),
+    ("sob", "😭"),
+    ("soccer", "⚽"),
+    ("sos", "🆘"),
+    ("sound", "🔉"),
+    ("space_invader", "👾"),
+    ("sparkle", "❇️"),
+    ("sparkler", "🎇"),
+    ("sparkles", "✨"),
+    ("sparkling_heart", "💖"),
+    ("speak_no_evil", "🙊"),
+    ("speaker", "🔈"),
+    ("speech_balloon", "💬"),
+    ("spider", "🕷️"),
+    ("spider_web", "🕸️"),
+    ("spiral_notepad", "🗒️"),
+    ("squid", "🦑"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("star_struck", "🤩"),
+    ("stars", "🌠"),
+    ("stop_sign", "🛑"),
+    ("stopwatch", "⏱️"),
+    ("strawberry", "🍓"),
+    ("stuck_out_tongue", "😛"),
+    ("stuck_out_tongue_closed_eyes", "😝"),
+    ("stuck_out_tongue_winking_eye", "😜"),
+    ("sun_with_face", "🌞"),
+    ("sunflower", "🌻"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀️"),
+    ("sunrise", "🌅"),
+    ("sushi", "🍣"),
+    ("sweat", "😓"),
+    ("sweat_drops", "💦"),
+    ("sweat_smile", "😅"),
+    ("syringe", "💉"),
+    ("taco", "🌮"),
+    ("tada", "🎉"),
+    ("tangerine", "🍊"),
+    ("taxi", "🚕"),
+    ("tea", "🍵"),
+    ("telephone", "☎️"),
+    ("telescope", "🔭"),
+    ("tennis", "🎾"),
+    ("tent", "⛺"),
+    ("test_tube", "🧪"),
+    ("thermometer", "🌡️"),
+    ("thinking", "🤔"),
+    ("thought_balloon", "💭"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("ticket", "🎫"),
+    ("tiger", "🐯"),
+    ("tired_face", "😫"),
+    ("tm", "™️"),
+    ("toilet", "🚽"),
+    ("tomato", "🍅"),
+    ("tongue", "👅"),
+    ("toolbox", "🧰"),
+    ("tophat", "🎩"),
+    ("tractor", "🚜"),
+    ("traffic_light", "🚥"),
+    ("train", "🚋"),
+    ("triangular_flag_on_post", "🚩"),
+    ("trident", "🔱"),
+    ("triumph", "😤"),
+    ("trophy", "🏆"),
+    ("tropical_fish", "🐠"),
+    ("truck", "🚚"),
+    ("trumpet", "🎺"),
+    ("tulip", "🌷"),
+    ("turkey", "🦃"),
+    ("turtle", "🐢"),
+    ("tv", "📺"),
+    ("two_hearts", "💕"),
+    ("umbrella", "☔"),
+    ("unamused", "😒"),
+    ("underage", "🔞"),
+    ("unicorn", "🦄"),
+    ("unlock", "🔓"),
+    ("upside_down_face", "🙃"),
+    ("v", "✌️"),
+    ("vertical_traffic_light", "🚦"),
+    ("video_camera", "📹"),
+    ("video_game", "🎮"),
+    ("violin", "🎻"),
+    ("volcano", "🌋"),
+    ("vomiting_face", "🤮"),
+    ("warning", "⚠️"),
+    ("wastebasket", "🗑️"),
+    ("watch", "⌚"),
+    ("watermelon", "🍉"),
+    ("wave", "👋"),
+    ("weary", "😩"),
+    ("whale", "🐳"),
+    ("wheelchair", "♿"),
+    ("white_check_mark", "✅"),
+    ("white_circle", "⚪"),
+    ("white_flower", "💮"),
+    ("wilted_flower", "🥀"),
+    ("wind_chime", "🎐"),
+    ("wine_glass", "🍷"),
+    ("wink", "😉"),
+    ("wolf", "🐺"),
+    ("woman", "👩"),
+    ("world_map", "🗺️"),
+    ("worried", "😟"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("yellow_heart", "💛"),
+    ("yen", "💴"),
+    ("yum", "😋"),
+    ("zany_face", "🤪"),
+    ("zap", "⚡"),
+    ("zebra", "🦓"),
+    ("zipper_mouth_face", "🤐"),
+    ("zzz", "💤"),
+];
+
+/// Look up the emoji for `shortcode`, without surrounding colons.
+pub fn lookup(shortcode: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by_key(&shortcode, |(name, _)| name)
+        .ok()
+        .map(|index| SHORTCODES[index].1)
+}
+
+/// Whether `c` can appear in a shortcode.
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-')
+}
+
+/// Expand all known emoji shortcodes like `:rocket:` in `text`.
+///
+/// Leave unknown shortcodes alone.
+pub fn expand_shortcodes(text: &str) -> Cow<'_, str> {
+    if !text.contains(':') {
+        return Cow::Borrowed(text);
+    }
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let emoji = after
+            .find(|c| !is_shortcode_char(c))
+            .filter(|&end| after[end..].starts_with(':'))
+            .and_then(|end| Some((end, lookup(&after[..end])?)));
+        match emoji {
+            Some((end, emoji)) => {
+                expanded.push_str(emoji);
+                rest = &after[end + 1..];
+            }
+            None => {
+                expanded.push(':');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Cow::Owned(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn shortcodes_are_sorted() {
+        assert!(SHORTCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn expand() {
+        assert_eq!(expand_shortcodes("Ship it :rocket:!"), "Ship it 🚀!");
+        assert_eq!(expand_shortcodes(":+1::tada:"), "👍🎉");
+        assert_eq!(
+            expand_shortcodes("At 10:30 :unknown: :fire:"),
+            "At 10:30 :unknown: 🔥"
+        );
+        assert_eq!(expand_shortcodes("::rocket:"), ":🚀");
+        assert_eq!(expand_shortcodes(":rocket"), ":rocket");
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Display, Formatter};
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use url::Url;
+
+/// A failure to read a resource, e.g. an image or a remote input file.
+#[derive(Debug)]
+pub struct ResourceError {
+    url: Url,
+    source: std::io::Error,
+}
+
+impl ResourceError {
+    /// Create a new error for the resource at `url`, which failed with `source`.
+    pub fn new(url: Url, source: std::io::Error) -> Self {
+        Self { url, source }
+    }
+
+    /// The URL of the resource which failed to read.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl Display for ResourceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to read resource {}: {}", self.url, self.source)
+    }
+}
+
+impl std::error::Error for ResourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Rendering a document took longer than the given timeout.
+#[derive(Debug)]
+pub struct RenderTimeout(pub Duration);
+
+impl Display for RenderTimeout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rendering took longer than {:?}", self.0)
+    }
+}
+
+impl std::error::Error for RenderTimeout {}
+
+/// An input file is larger than the given maximum input size, in bytes.
+#[derive(Debug)]
+pub struct InputTooLarge(pub u64);
+
+impl Display for InputTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Input exceeds the maximum input size of {} bytes",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InputTooLarge {}
+
+/// Classes of errors, each with a distinct exit code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Any error without a more specific class, e.g. a failure to render.
+    Generic,
+    /// Invalid arguments or configuration.
+    Usage,
+    /// A resource failed to read.
+    Resource,
+    /// An input file does not exist.
+    FileNotFound,
+}
+
+impl ErrorClass {
+    /// Classify `error`.
+    ///
+    /// Look for a [`ResourceError`] first, so that missing resources count as resource errors and
+    /// not as missing files.
+    pub fn of(error: &anyhow::Error) -> Self {
+        if error.chain().any(|cause| cause.is::<ResourceError>()) {
+            Self::Resource
+        } else if error.chain().any(|cause| {
+            cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|error| error.kind() == ErrorKind::NotFound)
+        }) {
+            Self::FileNotFound
+        } else {
+            Self::Generic
+        }
+    }
+
+    /// The exit code for this class of errors.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Generic => 1,
+            // Clap also exits with 2 for invalid arguments
+            Self::Usage => 2,
+            Self::Resource => 3,
+            Self::FileNotFound => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+    use similar_asserts::assert_eq;
+    use std::io::Error;
+
+    #[test]
+    fn classify_errors() {
+        let not_found = Error::new(ErrorKind::NotFound, "No such file");
+        let error = Err::<(), _>(not_found)
+            .context("Failed to open foo.md")
+            .unwrap_err();
+        assert_eq!(ErrorClass::of(&error), ErrorClass::FileNotFound);
+
+        let url = Url::parse("https://example.com/foo.png").unwrap();
+        let error = anyhow::Error::new(ResourceError::new(
+            url,
+            Error::new(ErrorKind::NotFound, "HTTP 404"),
+        ));
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Resource);
+
+        let error = anyhow!("1 broken link");
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Generic);
+        let error = anyhow::Error::new(Error::new(ErrorKind::BrokenPipe, "Broken pipe"));
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Generic);
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(ErrorClass::Generic.exit_code(), 1);
+        assert_eq!(ErrorClass::Usage.exit_code(), 2);
+        assert_eq!(ErrorClass::Resource.exit_code(), 3);
+        assert_eq!(ErrorClass::FileNotFound.exit_code(), 4);
+    }
+}
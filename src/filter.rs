@@ -0,0 +1,802 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cell::Cell;
+use std::time::Instant;
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, Tag, TagEnd, TextMergeStream};
+use tracing::{event, Level};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::emoji;
+use crate::highlighter::ExternalHighlighter;
+
+/// Omit all sections with headings deeper than `max_level` from `events`.
+///
+/// Hide a heading deeper than `max_level` and all content up to the next heading of at most
+/// `max_level`.  Always keep content before the first heading.
+///
+/// Only consider top-level headings, and ignore headings nested in other blocks, e.g. in block
+/// quotes or lists, to keep the event stream well-formed.
+pub fn max_heading_level<'a, I>(events: I, max_level: u8) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    let mut depth = 0_usize;
+    let mut hidden = false;
+    events.filter(move |event| {
+        match event {
+            Event::Start(tag) => {
+                if let (0, Tag::Heading { level, .. }) = (depth, tag) {
+                    hidden = max_level < *level as u8;
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        !hidden
+    })
+}
+
+/// Whether a heading with `heading` as text matches the section `title`.
+///
+/// Compare both case-insensitively, and ignore leading and trailing whitespace.
+pub fn is_section_title(heading: &str, title: &str) -> bool {
+    heading.trim().to_lowercase() == title.trim().to_lowercase()
+}
+
+/// Only keep the first section titled `title` from `events`.
+///
+/// Keep the first heading whose text matches `title` according to [`is_section_title`], and all
+/// content up to the next heading of the same or a higher level, including all nested
+/// subsections.  Omit everything else.
+///
+/// Like [`max_heading_level`] only consider top-level headings.
+pub fn section<'a, I>(events: I, title: String) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    let mut depth = 0_usize;
+    // The events and the text of the current top-level heading
+    let mut heading: Option<(Vec<Event<'a>>, String)> = None;
+    // The level of the matching section, while inside it
+    let mut section_level: Option<HeadingLevel> = None;
+    let mut done = false;
+    events.flat_map(move |event| {
+        match &event {
+            Event::Start(tag) => {
+                if let (0, Tag::Heading { .. }) = (depth, tag) {
+                    heading = Some((Vec::new(), String::new()));
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        match (event, heading.as_mut()) {
+            (Event::End(TagEnd::Heading(level)), Some(_)) if depth == 0 => {
+                let (mut events, text) = heading.take().unwrap();
+                events.push(Event::End(TagEnd::Heading(level)));
+                match section_level {
+                    Some(section) if level <= section => {
+                        section_level = None;
+                        done = true;
+                    }
+                    None if !done && is_section_title(&text, &title) => {
+                        section_level = Some(level);
+                    }
+                    _ => {}
+                }
+                if section_level.is_some() {
+                    events
+                } else {
+                    Vec::new()
+                }
+            }
+            (event, Some((events, text))) => {
+                match &event {
+                    Event::Text(t) | Event::Code(t) => text.push_str(t),
+                    Event::SoftBreak | Event::HardBreak => text.push(' '),
+                    _ => {}
+                }
+                events.push(event);
+                Vec::new()
+            }
+            (event, None) if section_level.is_some() => vec![event],
+            (_, None) => Vec::new(),
+        }
+    })
+}
+
+/// Replace all images in `events` with their alt text.
+pub fn images_as_text<'a, I>(events: I) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    events.filter(|event| {
+        !matches!(
+            event,
+            Event::Start(Tag::Image { .. }) | Event::End(TagEnd::Image)
+        )
+    })
+}
+
+/// The number of cells in a task progress bar.
+const TASK_PROGRESS_WIDTH: usize = 8;
+
+/// Format a progress bar for `checked` of `total` tasks, e.g. `[████░░░░] 50%`.
+pub fn task_progress_bar(checked: usize, total: usize) -> String {
+    let filled = (checked * TASK_PROGRESS_WIDTH + total / 2) / total;
+    format!(
+        "[{}{}] {}%",
+        "█".repeat(filled),
+        "░".repeat(TASK_PROGRESS_WIDTH - filled),
+        checked * 100 / total
+    )
+}
+
+/// Add a progress bar after every list with task items in `events`.
+///
+/// Count checked and total task items of each list separately, so that a nested task list gets
+/// its own progress bar, and does not count towards the progress of its parent list.
+pub fn task_progress<'a, I>(events: I) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    // Checked and total task items of each open list, innermost last
+    let mut lists: Vec<(usize, usize)> = Vec::new();
+    events.flat_map(move |event| match event {
+        Event::Start(Tag::List(_)) => {
+            lists.push((0, 0));
+            vec![event]
+        }
+        Event::TaskListMarker(checked) => {
+            if let Some((checked_items, total)) = lists.last_mut() {
+                *checked_items += usize::from(checked);
+                *total += 1;
+            }
+            vec![event]
+        }
+        Event::End(TagEnd::List(_)) => match lists.pop() {
+            Some((checked, total)) if 0 < total => vec![
+                event,
+                Event::Start(Tag::Paragraph),
+                Event::Text(task_progress_bar(checked, total).into()),
+                Event::End(TagEnd::Paragraph),
+            ],
+            _ => vec![event],
+        },
+        event => vec![event],
+    })
+}
+
+/// Expand emoji shortcodes like `:rocket:` in text of `events`.
+///
+/// Leave code spans, code blocks, and unknown shortcodes alone.  Merge adjacent text events
+/// first, because the parser may split a shortcode across text events.
+pub fn expand_emoji<'a, I>(events: I) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    let mut in_code_block = false;
+    TextMergeStream::new(events).map(move |event| match event {
+        Event::Start(Tag::CodeBlock(_)) => {
+            in_code_block = true;
+            event
+        }
+        Event::End(TagEnd::CodeBlock) => {
+            in_code_block = false;
+            event
+        }
+        Event::Text(text) if !in_code_block => match emoji::expand_shortcodes(&text) {
+            std::borrow::Cow::Borrowed(_) => Event::Text(text),
+            std::borrow::Cow::Owned(expanded) => Event::Text(expanded.into()),
+        },
+        event => event,
+    })
+}
+
+/// Expand tabs in code blocks of `events` to spaces, with tab stops every `width` columns.
+///
+/// Leave tabs outside of code blocks alone.
+pub fn expand_tabs_in_code<'a, I>(events: I, width: usize) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    let mut in_code_block = false;
+    // The column in the current line of the code block, across text events
+    let mut column = 0;
+    events.map(move |event| match event {
+        Event::Start(Tag::CodeBlock(_)) => {
+            in_code_block = true;
+            column = 0;
+            event
+        }
+        Event::End(TagEnd::CodeBlock) => {
+            in_code_block = false;
+            event
+        }
+        Event::Text(text) if in_code_block && text.contains('\t') => {
+            let mut expanded = String::with_capacity(text.len());
+            for c in text.chars() {
+                match c {
+                    '\t' => {
+                        let spaces = width - column % width;
+                        expanded.extend(std::iter::repeat_n(' ', spaces));
+                        column += spaces;
+                    }
+                    '\n' => {
+                        expanded.push(c);
+                        column = 0;
+                    }
+                    _ => {
+                        expanded.push(c);
+                        column += 1;
+                    }
+                }
+            }
+            Event::Text(expanded.into())
+        }
+        Event::Text(text) if in_code_block => {
+            match text.rfind('\n') {
+                Some(index) => column = text[index + 1..].chars().count(),
+                None => column += text.chars().count(),
+            }
+            Event::Text(text)
+        }
+        event => event,
+    })
+}
+
+/// Replace the text of every code block in `events` with the result of `f`.
+///
+/// Join all text of a code block and pass it to `f` as a whole.
+fn map_code_blocks<'a, I, F>(events: I, mut f: F) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+    F: FnMut(&str) -> String,
+{
+    let mut code: Option<String> = None;
+    events.flat_map(move |event| match (event, code.as_mut()) {
+        (event @ Event::Start(Tag::CodeBlock(_)), None) => {
+            code = Some(String::new());
+            vec![event]
+        }
+        (Event::Text(text), Some(code)) => {
+            code.push_str(&text);
+            Vec::new()
+        }
+        (event @ Event::End(TagEnd::CodeBlock), Some(_)) => {
+            let text = f(&code.take().unwrap());
+            vec![Event::Text(text.into()), event]
+        }
+        (event, _) => vec![event],
+    })
+}
+
+/// Apply `f` to every line of `text`, without its line ending.
+fn map_lines<F>(text: &str, f: F) -> String
+where
+    F: Fn(&str, &mut String),
+{
+    let mut result = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        match line.strip_suffix('\n') {
+            Some(line) => {
+                f(line, &mut result);
+                result.push('\n');
+            }
+            None => f(line, &mut result),
+        }
+    }
+    result
+}
+
+/// Truncate lines in code blocks of `events` which are wider than `width` columns.
+///
+/// Cut each such line short and end it with an ellipsis, so that it fits into `width` columns.
+pub fn truncate_code<'a, I>(events: I, width: usize) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    map_code_blocks(events, move |code| {
+        map_lines(code, |line, result| {
+            if line.width() <= width {
+                result.push_str(line);
+            } else {
+                // Leave room for the ellipsis
+                let mut columns = 1;
+                for c in line.chars() {
+                    columns += c.width().unwrap_or(0);
+                    if width < columns {
+                        break;
+                    }
+                    result.push(c);
+                }
+                result.push('\u{2026}');
+            }
+        })
+    })
+}
+
+/// The indentation of continuation lines of wrapped code, in columns.
+pub const CODE_CONTINUATION_INDENT: usize = 2;
+
+/// Wrap lines in code blocks of `events` which are wider than `width` columns.
+///
+/// Break such lines after the last character which fits into `width` columns, regardless of
+/// words, and indent each continuation line by [`CODE_CONTINUATION_INDENT`] columns.
+pub fn wrap_code<'a, I>(events: I, width: usize) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    let indent = " ".repeat(CODE_CONTINUATION_INDENT.min(width.saturating_sub(1)));
+    map_code_blocks(events, move |code| {
+        map_lines(code, |line, result| {
+            let mut columns = 0;
+            for c in line.chars() {
+                let char_width = c.width().unwrap_or(0);
+                // Always put at least one character on a line, even if it's too wide
+                if indent.len() < columns && width < columns + char_width {
+                    result.push('\n');
+                    result.push_str(&indent);
+                    columns = indent.len();
+                }
+                result.push(c);
+                columns += char_width;
+            }
+        })
+    })
+}
+
+/// Highlight fenced code blocks with a language in `events` with an external `highlighter`.
+///
+/// Replace the contents of every such code block with the output of `highlighter`, and drop the
+/// language of the block, so that the output is not highlighted again.  Keep a code block as it
+/// is if `highlighter` fails.
+pub fn highlight_with_command<'a, I>(
+    events: I,
+    highlighter: ExternalHighlighter,
+) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    // The language and the contents of the current code block
+    let mut block: Option<(CowStr<'a>, String)> = None;
+    events.flat_map(move |event| match (event, block.as_mut()) {
+        (Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))), None)
+            if !info.trim().is_empty() =>
+        {
+            block = Some((info, String::new()));
+            Vec::new()
+        }
+        (Event::Text(text), Some((_, code))) => {
+            code.push_str(&text);
+            Vec::new()
+        }
+        (Event::End(TagEnd::CodeBlock), Some(_)) => {
+            let (info, code) = block.take().unwrap();
+            let language = info.split_whitespace().next().unwrap_or_default();
+            let (info, code) = match highlighter.highlight(language, &code) {
+                Ok(highlighted) => (CowStr::Borrowed(""), highlighted),
+                Err(error) => {
+                    event!(
+                        Level::WARN,
+                        "Falling back to built-in highlighting for {}: {:#}",
+                        language,
+                        error
+                    );
+                    (info, code)
+                }
+            };
+            vec![
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))),
+                Event::Text(code.into()),
+                Event::End(TagEnd::CodeBlock),
+            ]
+        }
+        (event, _) => vec![event],
+    })
+}
+
+/// Whether `html` consists of nothing but complete HTML comments and whitespace.
+fn is_html_comment(html: &str) -> bool {
+    let mut rest = html.trim_start();
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        let Some(end) = rest
+            .strip_prefix("<!--")
+            .and_then(|comment| comment.find("-->"))
+        else {
+            return false;
+        };
+        rest = rest["<!--".len() + end + "-->".len()..].trim_start();
+    }
+    true
+}
+
+/// Remove HTML comments from `events`.
+///
+/// Drop inline HTML which is a single comment, and HTML blocks which contain nothing but
+/// comments.  Keep HTML blocks which mix comments with other HTML as they are.
+pub fn strip_html_comments<'a, I>(events: I) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    // The events of the current HTML block
+    let mut block: Option<Vec<Event<'a>>> = None;
+    events.flat_map(move |event| match (event, block.as_mut()) {
+        (Event::InlineHtml(html), None) if is_html_comment(&html) => Vec::new(),
+        (event @ Event::Start(Tag::HtmlBlock), None) => {
+            block = Some(vec![event]);
+            Vec::new()
+        }
+        (event @ Event::End(TagEnd::HtmlBlock), Some(_)) => {
+            let mut events = block.take().unwrap();
+            let html = events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Html(html) => Some(html.as_ref()),
+                    _ => None,
+                })
+                .collect::<String>();
+            if is_html_comment(&html) {
+                Vec::new()
+            } else {
+                events.push(event);
+                events
+            }
+        }
+        (event, Some(events)) => {
+            events.push(event);
+            Vec::new()
+        }
+        (event, None) => vec![event],
+    })
+}
+
+/// Stop `events` once `deadline` passed.
+///
+/// Set `timed_out` if the deadline cut `events` short, and close all open tags then, so that
+/// the event stream stays well-formed.  The output rendered from the remaining events is
+/// incomplete nonetheless, so it's best discarded.
+pub fn until_deadline<'a, 'b, I>(
+    events: I,
+    deadline: Instant,
+    timed_out: &'b Cell<bool>,
+) -> impl Iterator<Item = Event<'a>> + 'b
+where
+    'a: 'b,
+    I: Iterator<Item = Event<'a>> + 'b,
+{
+    until_expired(events, move || {
+        let expired = deadline <= Instant::now();
+        if expired {
+            timed_out.set(true);
+        }
+        expired
+    })
+}
+
+/// Stop `events` once `expired` returns `true`, and close all open tags.
+fn until_expired<'a, I, F>(mut events: I, mut expired: F) -> impl Iterator<Item = Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+    F: FnMut() -> bool,
+{
+    let mut open = Vec::new();
+    let mut stopped = false;
+    std::iter::from_fn(move || {
+        stopped = stopped || expired();
+        if stopped {
+            return open.pop().map(Event::End);
+        }
+        let event = events.next()?;
+        match &event {
+            Event::Start(tag) => open.push(tag.to_end()),
+            Event::End(_) => {
+                open.pop();
+            }
+            _ => {}
+        }
+        Some(event)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Options, Parser};
+    use similar_asserts::assert_eq;
+
+    fn text(markdown: &str, max_level: u8) -> Vec<String> {
+        max_heading_level(Parser::new(markdown), max_level)
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.into_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hide_deep_sections() {
+        let markdown =
+            "Intro\n\n# One\n\nFirst\n\n## Two\n\nSecond\n\n### Three\n\nThird\n\n## Four\n\nFourth\n";
+        assert_eq!(
+            text(markdown, 2),
+            ["Intro", "One", "First", "Two", "Second", "Four", "Fourth"]
+        );
+        assert_eq!(text(markdown, 1), ["Intro", "One", "First"]);
+        assert_eq!(
+            text(markdown, 6),
+            ["Intro", "One", "First", "Two", "Second", "Three", "Third", "Four", "Fourth"]
+        );
+    }
+
+    #[test]
+    fn extract_section() {
+        let markdown = "# Intro\n\nText\n\n## Installation \n\nInstall it.\n\n\
+                        ### From *source*\n\nBuild it.\n\n## Usage\n\nUse it.\n";
+        let texts = |title: &str| {
+            section(Parser::new(markdown), title.to_string())
+                .filter_map(|event| match event {
+                    Event::Text(text) => Some(text.into_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            texts(" installation"),
+            [
+                "Installation",
+                "Install it.",
+                "From ",
+                "source",
+                "Build it."
+            ]
+        );
+        assert_eq!(texts("Usage"), ["Usage", "Use it."]);
+        assert_eq!(
+            texts("intro")[..2],
+            ["Intro".to_string(), "Text".to_string()]
+        );
+        assert!(texts("Missing").is_empty());
+    }
+
+    #[test]
+    fn replace_images_with_alt_text() {
+        let events = images_as_text(Parser::new("See ![the *logo*](logo.png \"Title\").\n"))
+            .collect::<Vec<_>>();
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, Event::Start(Tag::Image { .. }))));
+        let text = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect::<String>();
+        assert_eq!(text, "See the logo.");
+        assert!(events.contains(&Event::Start(Tag::Emphasis)));
+    }
+
+    #[test]
+    fn task_progress_bars() {
+        assert_eq!(task_progress_bar(2, 4), "[████░░░░] 50%");
+        assert_eq!(task_progress_bar(0, 3), "[░░░░░░░░] 0%");
+        assert_eq!(task_progress_bar(1, 3), "[███░░░░░] 33%");
+        assert_eq!(task_progress_bar(3, 3), "[████████] 100%");
+    }
+
+    #[test]
+    fn add_task_progress_after_task_lists() {
+        let markdown = "- [x] one\n- [ ] two\n  - [x] nested\n- [x] three\n- [ ] four\n\n\
+                        Text\n\n- plain\n";
+        let texts = task_progress(Parser::new_ext(markdown, Options::ENABLE_TASKLISTS))
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.into_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                "one",
+                "two",
+                "nested",
+                "[████████] 100%",
+                "three",
+                "four",
+                "[████░░░░] 50%",
+                "Text",
+                "plain"
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_emoji_shortcodes() {
+        let markdown = "Ship it :rocket: :unknown:\n\nNot `:rocket:`\n\n```\n:rocket:\n```\n";
+        let events = expand_emoji(Parser::new(markdown)).collect::<Vec<_>>();
+        let texts = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["Ship it 🚀 :unknown:", "Not ", ":rocket:\n"]);
+        assert!(events.contains(&Event::Code(":rocket:".into())));
+    }
+
+    #[test]
+    fn expand_tabs_in_code_blocks() {
+        let events = expand_tabs_in_code(
+            Parser::new("Some\ttext\n\n```\n\tfoo\n  \tbar\tbaz\n```\n"),
+            4,
+        )
+        .collect::<Vec<_>>();
+        assert!(events.contains(&Event::Text("Some\ttext".into())));
+        let code = events
+            .iter()
+            .skip_while(|event| !matches!(event, Event::Start(Tag::CodeBlock(_))))
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect::<String>();
+        assert_eq!(code, "    foo\n    bar baz\n");
+    }
+
+    // Uses standard Unix tools as highlighters
+    #[cfg(unix)]
+    #[test]
+    fn highlight_code_blocks_with_command() {
+        let markdown = "```rust\nfn main() {}\n```\n\n```\nplain\n```\n";
+        let highlighter = ExternalHighlighter::parse("sed s/main/{lang}/").unwrap();
+        let events = highlight_with_command(Parser::new(markdown), highlighter).collect::<Vec<_>>();
+        assert_eq!(
+            events[..3],
+            [
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced("".into()))),
+                Event::Text("fn rust() {}\n".into()),
+                Event::End(TagEnd::CodeBlock)
+            ]
+        );
+        assert!(events.contains(&Event::Text("plain\n".into())));
+
+        let failing = ExternalHighlighter::parse("false").unwrap();
+        let events = highlight_with_command(Parser::new(markdown), failing).collect::<Vec<_>>();
+        assert_eq!(events, Parser::new(markdown).collect::<Vec<_>>());
+    }
+
+    fn code(events: impl Iterator<Item = Event<'static>>) -> String {
+        events
+            .skip_while(|event| !matches!(event, Event::Start(Tag::CodeBlock(_))))
+            .filter_map(|event| match event {
+                Event::Text(text) => Some(text.into_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn truncate_long_code_lines() {
+        let markdown = "A long paragraph\n\n```\nshort\n0123456789abcdef\n\u{4e2d}\u{6587}\u{4e2d}\u{6587}\u{4e2d}\u{6587}\n```\n";
+        assert_eq!(
+            code(truncate_code(Parser::new(markdown), 10)),
+            "short\n012345678\u{2026}\n\u{4e2d}\u{6587}\u{4e2d}\u{6587}\u{2026}\n"
+        );
+        assert_eq!(
+            code(truncate_code(Parser::new(markdown), 16)),
+            "short\n0123456789abcdef\n\u{4e2d}\u{6587}\u{4e2d}\u{6587}\u{4e2d}\u{6587}\n"
+        );
+        assert!(truncate_code(Parser::new(markdown), 10)
+            .any(|event| event == Event::Text("A long paragraph".into())));
+    }
+
+    #[test]
+    fn wrap_long_code_lines() {
+        let markdown = "```\nshort\n0123456789abcdefghij\n\u{4e2d}\u{6587}\u{4e2d}\u{6587}\u{4e2d}\u{6587}\n```\n";
+        assert_eq!(
+            code(wrap_code(Parser::new(markdown), 8)),
+            "short\n01234567\n  89abcd\n  efghij\n\u{4e2d}\u{6587}\u{4e2d}\u{6587}\n  \u{4e2d}\u{6587}\n"
+        );
+        assert_eq!(
+            code(wrap_code(Parser::new("```\nabc\n```\n"), 1)),
+            "a\nb\nc\n"
+        );
+    }
+
+    #[test]
+    fn detect_html_comments() {
+        assert!(is_html_comment("<!-- TODO -->"));
+        assert!(is_html_comment("<!-- one\ntwo -->\n<!-- three -->\n"));
+        assert!(!is_html_comment(""));
+        assert!(!is_html_comment("<!-- TODO --> <b>bold</b>"));
+        assert!(!is_html_comment("<!-- unterminated"));
+        assert!(!is_html_comment("<div>"));
+    }
+
+    #[test]
+    fn strip_comments() {
+        let markdown = "Text <!-- inline --> more <b>bold</b>\n\n<!-- block\nmulti -->\n\n\
+                        <!-- a --> <div>kept</div>\n\nAfter\n";
+        let events = strip_html_comments(Parser::new(markdown)).collect::<Vec<_>>();
+        let html = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Html(html) | Event::InlineHtml(html) => Some(html.as_ref()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(html, ["<b>", "</b>", "<!-- a --> <div>kept</div>\n"]);
+        assert!(events.contains(&Event::Text("After".into())));
+        assert!(events.contains(&Event::Text(" more ".into())));
+    }
+
+    #[test]
+    fn ignore_nested_headings() {
+        let events = max_heading_level(Parser::new("# One\n\n> ### Quoted\n\nText\n"), 1)
+            .collect::<Vec<_>>();
+        let starts = events
+            .iter()
+            .filter(|event| matches!(event, Event::Start(_)))
+            .count();
+        let ends = events
+            .iter()
+            .filter(|event| matches!(event, Event::End(_)))
+            .count();
+        assert_eq!(starts, ends);
+        assert_eq!(
+            text("# One\n\n> ### Quoted\n\nText\n", 1),
+            ["One", "Quoted", "Text"]
+        );
+    }
+
+    #[test]
+    fn stop_events_at_deadline() {
+        use std::time::Duration;
+
+        let timed_out = Cell::new(false);
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let events = until_deadline(Parser::new("# One\n\nTwo\n"), deadline, &timed_out);
+        assert_eq!(events.count(), 6);
+        assert!(!timed_out.get());
+
+        let events = until_deadline(Parser::new("# One\n\nTwo\n"), Instant::now(), &timed_out);
+        assert_eq!(events.count(), 0);
+        assert!(timed_out.get());
+    }
+
+    #[test]
+    fn close_open_tags_when_expired() {
+        let mut remaining = 2;
+        let events = until_expired(Parser::new("> # One\n>\n> Two\n"), || {
+            remaining -= 1;
+            remaining < 0
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                Event::Start(Tag::BlockQuote(None)),
+                Event::Start(Tag::Heading {
+                    level: HeadingLevel::H1,
+                    id: None,
+                    classes: Vec::new(),
+                    attrs: Vec::new()
+                }),
+                Event::End(TagEnd::Heading(HeadingLevel::H1)),
+                Event::End(TagEnd::BlockQuote(None)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::Display;
+
+/// The format of front matter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// YAML front matter, between `---` lines.
+    Yaml,
+    /// TOML front matter, between `+++` lines.
+    Toml,
+}
+
+impl Display for FrontMatterFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrontMatterFormat::Yaml => write!(f, "YAML"),
+            FrontMatterFormat::Toml => write!(f, "TOML"),
+        }
+    }
+}
+
+/// Front matter at the beginning of a markdown document.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrontMatter<'a> {
+    /// The format of the front matter.
+    pub format: FrontMatterFormat,
+    /// The contents of the front matter, without delimiters.
+    pub contents: &'a str,
+}
+
+impl FrontMatter<'_> {
+    /// Get all top-level keys of this front matter.
+    ///
+    /// Parse TOML front matter, and return its keys in sorted order; return no keys for invalid
+    /// TOML.  Take keys of YAML front matter from all unindented `key: value` lines, in order.
+    pub fn keys(&self) -> Vec<String> {
+        self.entries().into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// Get all top-level keys of this front matter together with their values.
+    ///
+    /// Return entries in the order of [`FrontMatter::keys`].  Stringify non-scalar values, i.e.
+    /// lists as `[a, b]` and mappings as `{key: value}`.  For YAML front matter take the value of
+    /// a key from the rest of its line, or from the indented lines below it.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        match self.format {
+            FrontMatterFormat::Toml => toml::from_str::<toml::Table>(self.contents)
+                .map(|table| {
+                    table
+                        .iter()
+                        .map(|(key, value)| (key.clone(), toml_value_to_string(value)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            FrontMatterFormat::Yaml => yaml_entries(self.contents),
+        }
+    }
+
+    /// Format the entries of this front matter as a markdown table of keys and values.
+    ///
+    /// Return an empty string if this front matter has no entries.
+    pub fn markdown_table(&self) -> String {
+        let entries = self.entries();
+        if entries.is_empty() {
+            return String::new();
+        }
+        let mut table = String::from("| Key | Value |\n| --- | --- |\n");
+        for (key, value) in entries {
+            table.push_str(&format!(
+                "| {} | {} |\n",
+                escape_table_cell(&key),
+                escape_table_cell(&value)
+            ));
+        }
+        table
+    }
+}
+
+/// Escape pipes in `cell` which would otherwise end a table cell.
+fn escape_table_cell(cell: &str) -> String {
+    cell.replace('|', "\\|")
+}
+
+/// Stringify a TOML `value`.
+fn toml_value_to_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => f.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(datetime) => datetime.to_string(),
+        toml::Value::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(toml_value_to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        toml::Value::Table(table) => format!(
+            "{{{}}}",
+            table
+                .iter()
+                .map(|(key, value)| format!("{key}: {}", toml_value_to_string(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Remove matching quotes around a YAML scalar `value`.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+}
+
+/// Take top-level entries from YAML `contents`.
+///
+/// Stringify nested lines below a key without value: a list of `- item` lines as `[a, b]`, and
+/// anything else as `{key: value}`.
+fn yaml_entries(contents: &str) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String, Vec<&str>)> = Vec::new();
+    for line in contents.lines() {
+        if line.starts_with([' ', '\t', '-']) {
+            if let Some((_, _, nested)) = entries.last_mut() {
+                let line = line.trim();
+                if !line.is_empty() && !line.starts_with('#') {
+                    nested.push(line);
+                }
+            }
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some((key, value)) = line.split_once(':') {
+            let key = unquote(key.trim());
+            if !key.is_empty() {
+                entries.push((
+                    key.to_string(),
+                    unquote(value.trim()).to_string(),
+                    Vec::new(),
+                ));
+            }
+        }
+    }
+    entries
+        .into_iter()
+        .map(|(key, value, nested)| {
+            if !value.is_empty() || nested.is_empty() {
+                (key, value)
+            } else if nested.iter().all(|line| line.starts_with('-')) {
+                let items = nested
+                    .iter()
+                    .map(|line| unquote(line.trim_start_matches('-').trim()))
+                    .collect::<Vec<_>>();
+                (key, format!("[{}]", items.join(", ")))
+            } else {
+                (key, format!("{{{}}}", nested.join(", ")))
+            }
+        })
+        .collect()
+}
+
+/// Split leading front matter off markdown `input`.
+///
+/// Recognize YAML front matter between `---` lines, and TOML front matter between `+++` lines,
+/// but only if `input` starts with the delimiter, and the delimiter is directly followed by a
+/// non-blank line; otherwise a leading `---` is a thematic break.
+///
+/// Return the front matter if any, and the remaining markdown.
+pub fn split_front_matter(input: &str) -> (Option<FrontMatter<'_>>, &str) {
+    for (delimiter, format) in [
+        ("---", FrontMatterFormat::Yaml),
+        ("+++", FrontMatterFormat::Toml),
+    ] {
+        let Some(rest) = input.strip_prefix(delimiter).and_then(|rest| {
+            rest.strip_prefix('\n')
+                .or_else(|| rest.strip_prefix("\r\n"))
+        }) else {
+            continue;
+        };
+        if rest
+            .lines()
+            .next()
+            .is_none_or(|line| line.trim().is_empty())
+        {
+            continue;
+        }
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == delimiter {
+                let front_matter = FrontMatter {
+                    format,
+                    contents: &rest[..offset],
+                };
+                return (Some(front_matter), &rest[offset + line.len()..]);
+            }
+            offset += line.len();
+        }
+    }
+    (None, input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn yaml_front_matter() {
+        let (front_matter, markdown) = split_front_matter(
+            "---\ntitle: \"Hello\"\ntags:\n  - a\n  - b\ndate: 2025-01-01\n---\n# Hello\n",
+        );
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.format, FrontMatterFormat::Yaml);
+        assert_eq!(front_matter.keys(), ["title", "tags", "date"]);
+        assert_eq!(markdown, "# Hello\n");
+    }
+
+    #[test]
+    fn front_matter_entries() {
+        let (front_matter, _) = split_front_matter(
+            "---\ntitle: \"Hello\"\ntags:\n  - a\n  - 'b'\n# Comment\nauthor:\n  name: Jane\n  mail: jane@example.com\n---\n",
+        );
+        assert_eq!(
+            front_matter.unwrap().entries(),
+            [
+                ("title".to_string(), "Hello".to_string()),
+                ("tags".to_string(), "[a, b]".to_string()),
+                (
+                    "author".to_string(),
+                    "{name: Jane, mail: jane@example.com}".to_string()
+                ),
+            ]
+        );
+
+        let (front_matter, _) =
+            split_front_matter("+++\ntitle = \"Hello\"\ntags = [\"a\", 1]\n+++\n");
+        assert_eq!(
+            front_matter.unwrap().entries(),
+            [
+                ("tags".to_string(), "[a, 1]".to_string()),
+                ("title".to_string(), "Hello".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn front_matter_markdown_table() {
+        let (front_matter, _) = split_front_matter("---\ntitle: Hi\nsep: a|b\n---\n");
+        assert_eq!(
+            front_matter.unwrap().markdown_table(),
+            "| Key | Value |\n| --- | --- |\n| title | Hi |\n| sep | a\\|b |\n"
+        );
+    }
+
+    #[test]
+    fn toml_front_matter() {
+        let (front_matter, markdown) =
+            split_front_matter("+++\ntitle = \"Hello\"\ndraft = true\n+++\n\nText\n");
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.format, FrontMatterFormat::Toml);
+        assert_eq!(front_matter.keys(), ["draft", "title"]);
+        assert_eq!(markdown, "\nText\n");
+    }
+
+    #[test]
+    fn thematic_break_is_no_front_matter() {
+        let input = "---\n\nSome text\n\n---\n\nMore text\n";
+        assert_eq!(split_front_matter(input), (None, input));
+        let input = "Text\n\n---\ntitle: No front matter\n---\n";
+        assert_eq!(split_front_matter(input), (None, input));
+    }
+
+    #[test]
+    fn unterminated_front_matter() {
+        let input = "---\ntitle: Hello\n\n# Heading\n";
+        assert_eq!(split_front_matter(input), (None, input));
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::normalize_line_endings;
+
+/// Extract all added lines from a unified `diff`.
+///
+/// Return the added lines, without the leading `+`, each terminated by a newline.  Skip file
+/// headers, hunk headers, and removed and context lines.
+pub fn added_lines(diff: &str) -> String {
+    let mut added = String::new();
+    // Whether we are in a hunk, to tell added lines which start with `++` from file headers
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if line.starts_with("diff ") {
+            in_hunk = false;
+        } else if line.starts_with("@@") {
+            in_hunk = true;
+        } else if let Some(line) = line.strip_prefix('+').filter(|_| in_hunk) {
+            added.push_str(line);
+            added.push('\n');
+        }
+    }
+    added
+}
+
+/// Create a git command which runs in `directory`.
+fn git(directory: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(directory);
+    command
+}
+
+/// Whether `git` runs successfully.
+fn succeeds(git: &mut Command) -> Result<bool> {
+    Ok(git.output().context("Failed to run git")?.status.success())
+}
+
+/// Get all lines added to the file at `path` since `git_ref`.
+///
+/// Diff the working tree version of `path` against `git_ref` and return the added lines, see
+/// [`added_lines`].  If `path` did not exist at `git_ref`, e.g. because it is untracked, return all
+/// of its lines.  Return `None` if `path` is not in a git repository, and fail if git fails, e.g.
+/// because `git_ref` does not exist.
+pub fn added_lines_since(path: &Path, git_ref: &str) -> Result<Option<String>> {
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+    let filename = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", path.display()))?;
+    if !succeeds(git(directory).args(["rev-parse", "--git-dir"]))? {
+        return Ok(None);
+    }
+    let commit = format!("{git_ref}^{{commit}}");
+    if !succeeds(git(directory).args(["rev-parse", "--verify", "--quiet", &commit]))? {
+        return Err(anyhow!("Unknown git ref {git_ref}"));
+    }
+    let mut object = std::ffi::OsString::from(format!("{git_ref}:./"));
+    object.push(filename);
+    if !succeeds(git(directory).args(["cat-file", "-e"]).arg(object))? {
+        // Like a diff against /dev/null, every line of a new file is added
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        return Ok(Some(normalize_line_endings(&contents).into_owned()));
+    }
+    let output = git(directory)
+        .args([
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--unified=0",
+            git_ref,
+            "--",
+        ])
+        .arg(filename)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git diff {git_ref} failed: {}", stderr.trim()));
+    }
+    let diff = String::from_utf8(output.stdout)
+        .with_context(|| format!("Diff of {} is not valid UTF-8", path.display()))?;
+    Ok(Some(added_lines(&normalize_line_endings(&diff))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn extract_added_lines() {
+        let diff = "diff --git a/README.md b/README.md
+index 3b18e51..a0b1c2d 100644
+--- a/README.md
++++ b/README.md
+@@ -1,2 +1,3 @@
+ # Title
+-Old intro.
++New intro with more words.
++++ Emphatic line.
+@@ -10 +11,0 @@ Usage
+-Removed line.
+\\ No newline at end of file
+diff --git a/other.md b/other.md
+--- a/other.md
++++ b/other.md
+@@ -0,0 +1 @@
++Other file.
+";
+        assert_eq!(
+            added_lines(diff),
+            "New intro with more words.\n++ Emphatic line.\nOther file.\n"
+        );
+        assert_eq!(added_lines(""), "");
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Display, Formatter};
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+/// A problem with the hierarchy of headings in a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadingProblem {
+    /// A heading skips levels below the previous heading, e.g. a level 3 heading after level 1.
+    SkippedLevel {
+        /// The level of the previous heading.
+        previous: u8,
+        /// The level of this heading.
+        level: u8,
+    },
+    /// Another level 1 heading after the first one.
+    MultipleTopLevel,
+}
+
+impl Display for HeadingProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SkippedLevel { previous, level } => {
+                write!(f, "heading level {level} skips from level {previous}")
+            }
+            Self::MultipleTopLevel => write!(f, "multiple level 1 headings"),
+        }
+    }
+}
+
+/// A warning about a heading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadingWarning {
+    /// The line of the heading in the markdown source, counting from 1.
+    pub line: usize,
+    /// What's wrong with the heading.
+    pub problem: HeadingProblem,
+}
+
+/// Check the hierarchy of headings in markdown `input`.
+///
+/// Warn about every heading which is more than one level deeper than the previous heading, and
+/// about every level 1 heading after the first one.  Headings may go up any number of levels,
+/// and the first heading may have any level.
+pub fn heading_warnings(input: &str, options: Options) -> Vec<HeadingWarning> {
+    let mut warnings = Vec::new();
+    let mut previous: Option<u8> = None;
+    let mut seen_top_level = false;
+    for (event, range) in Parser::new_ext(input, options).into_offset_iter() {
+        let Event::Start(Tag::Heading { level, .. }) = event else {
+            continue;
+        };
+        let level = level as u8;
+        let line = input[..range.start].matches('\n').count() + 1;
+        match previous {
+            Some(previous) if previous + 1 < level => warnings.push(HeadingWarning {
+                line,
+                problem: HeadingProblem::SkippedLevel { previous, level },
+            }),
+            _ => {}
+        }
+        if level == 1 {
+            if seen_top_level {
+                warnings.push(HeadingWarning {
+                    line,
+                    problem: HeadingProblem::MultipleTopLevel,
+                });
+            }
+            seen_top_level = true;
+        }
+        previous = Some(level);
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn skipped_levels() {
+        let input = "# One\n\n### Three\n\n## Two\n\n#### Four\n\n# One again\n";
+        assert_eq!(
+            heading_warnings(input, Options::empty()),
+            vec![
+                HeadingWarning {
+                    line: 3,
+                    problem: HeadingProblem::SkippedLevel {
+                        previous: 1,
+                        level: 3
+                    }
+                },
+                HeadingWarning {
+                    line: 7,
+                    problem: HeadingProblem::SkippedLevel {
+                        previous: 2,
+                        level: 4
+                    }
+                },
+                HeadingWarning {
+                    line: 9,
+                    problem: HeadingProblem::MultipleTopLevel
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn well_formed_headings() {
+        let input = "## Start deep\n\n### Down\n\n#### Further\n\n## Up\n\n# Top\n\n## Again\n";
+        assert_eq!(heading_warnings(input, Options::empty()), vec![]);
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Context, Result};
+
+/// An external command to highlight code blocks with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalHighlighter {
+    program: String,
+    args: Vec<String>,
+}
+
+impl ExternalHighlighter {
+    /// Parse a highlighter `command`.
+    ///
+    /// Split `command` into words according to shell quoting rules; the first word is the
+    /// program to run.
+    pub fn parse(command: &str) -> Result<Self> {
+        let mut words = shell_words::split(command)
+            .with_context(|| format!("Failed to parse highlighter command {command}"))?
+            .into_iter();
+        let program = words
+            .next()
+            .ok_or_else(|| anyhow!("Empty highlighter command"))?;
+        Ok(Self {
+            program,
+            args: words.collect(),
+        })
+    }
+
+    /// Highlight `code` written in `language`.
+    ///
+    /// Run the command with `{lang}` in its arguments replaced by `language`, write `code` to its
+    /// standard input, and return its standard output.  Fail if the command fails to start, exits
+    /// with a non-zero status, or prints anything but UTF-8.
+    pub fn highlight(&self, language: &str, code: &str) -> Result<String> {
+        let mut child = Command::new(&self.program)
+            .args(self.args.iter().map(|arg| arg.replace("{lang}", language)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to run highlighter {}", self.program))?;
+        let mut stdin = child.stdin.take().expect("Standard input must be piped");
+        let output = std::thread::scope(|scope| {
+            // Write in a separate thread, to avoid a deadlock if the highlighter fills up its
+            // output pipe before reading all input.  Ignore errors, because a highlighter may
+            // legitimately exit early; we check its exit status instead.
+            scope.spawn(move || {
+                let _ = stdin.write_all(code.as_bytes());
+            });
+            child.wait_with_output()
+        })?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Highlighter {} failed with {}",
+                self.program,
+                output.status
+            ));
+        }
+        String::from_utf8(output.stdout)
+            .with_context(|| format!("Output of highlighter {} is not UTF-8", self.program))
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use anyhow::{anyhow, bail, Context, Result};
+use pulldown_cmark_mdcat::ResourceUrlHandler;
+use tracing::{event, Level};
+use url::Url;
+
+use crate::{frontmatter, normalize_line_endings};
+
+/// The maximum depth of nested includes.
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Get the path of the include directive in `line`, if any.
+///
+/// An include directive is a line which only contains `{{include: path}}`, optionally surrounded
+/// by whitespace.
+pub fn include_path(line: &str) -> Option<&str> {
+    let path = line
+        .trim()
+        .strip_prefix("{{")?
+        .strip_suffix("}}")?
+        .trim_start()
+        .strip_prefix("include:")?
+        .trim();
+    (!path.is_empty()).then_some(path)
+}
+
+/// Whether `line` opens or closes a fenced code block.
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// Resolve symbolic links and relative components in a `file:` URL.
+///
+/// Return other URLs as they are.
+fn canonicalize(url: &Url) -> Result<Url> {
+    if url.scheme() != "file" {
+        return Ok(url.clone());
+    }
+    let path = url
+        .to_file_path()
+        .map_err(|_| anyhow!("Invalid file URL {url}"))?
+        .canonicalize()?;
+    let url = if path.is_dir() {
+        Url::from_directory_path(&path)
+    } else {
+        Url::from_file_path(&path)
+    };
+    url.map_err(|_| anyhow!("Cannot convert {} to URL", path.display()))
+}
+
+/// Resolve all include directives in markdown `input`.
+///
+/// Replace every include directive, see [`include_path`], with the contents of the referenced
+/// resource, relative to `base_url`, and resolve include directives in included resources
+/// relative to their own URL.  Read included resources with `resource_handler`, and refuse to
+/// include anything outside of the directory of `base_url`.  Drop front matter of included
+/// files, and leave directives in fenced code blocks alone.
+///
+/// Fail if includes form a cycle, nest deeper than [`MAX_INCLUDE_DEPTH`], or leave the base
+/// directory.  If `strict` fail if an included resource cannot be read; otherwise warn and leave
+/// the directive in place.
+pub fn resolve_includes(
+    input: &str,
+    base_url: &Url,
+    resource_handler: &dyn ResourceUrlHandler,
+    strict: bool,
+) -> Result<String> {
+    let mut includes = Includes {
+        base_url,
+        root: None,
+        resource_handler,
+        strict,
+        stack: Vec::new(),
+    };
+    let mut resolved = String::with_capacity(input.len());
+    includes.resolve_into(input, base_url, &mut resolved)?;
+    Ok(resolved)
+}
+
+struct Includes<'a> {
+    base_url: &'a Url,
+    /// The canonical directory of `base_url`, once resolved.
+    root: Option<Url>,
+    resource_handler: &'a dyn ResourceUrlHandler,
+    strict: bool,
+    stack: Vec<Url>,
+}
+
+impl Includes<'_> {
+    /// The directory all includes must be in.
+    ///
+    /// Resolve the directory lazily, to not fail on documents without include directives.
+    fn root(&mut self) -> Result<&Url> {
+        if self.root.is_none() {
+            let directory = self.base_url.join(".")?;
+            let root = canonicalize(&directory)
+                .with_context(|| format!("Cannot include files relative to {}", self.base_url))?;
+            self.root = Some(root);
+        }
+        Ok(self.root.as_ref().unwrap())
+    }
+
+    /// Read the resource at `path` relative to `base_url`.
+    ///
+    /// Return the canonical URL of the resource along with its contents, or `None` if the
+    /// resource cannot be read and includes are not strict.  Always fail if the resource is
+    /// outside of the base directory.
+    fn read(&mut self, base_url: &Url, path: &str) -> Result<Option<(Url, String)>> {
+        let url = base_url.join(path)?;
+        let canonical = match canonicalize(&url) {
+            Ok(canonical) => canonical,
+            Err(error) => return self.unreadable(path, error),
+        };
+        let root = self.root()?;
+        if !canonical.as_str().starts_with(root.as_str()) {
+            bail!("Refusing to include {path} from outside of {root}");
+        }
+        let contents = self
+            .resource_handler
+            .read_resource(&url)
+            .map_err(anyhow::Error::from)
+            .and_then(|resource| Ok(String::from_utf8(resource.data)?));
+        match contents {
+            Ok(contents) => Ok(Some((canonical, contents))),
+            Err(error) => self.unreadable(path, error),
+        }
+    }
+
+    /// Handle an `error` reading the include at `path`.
+    ///
+    /// Fail if includes are strict, and warn otherwise.
+    fn unreadable(&self, path: &str, error: anyhow::Error) -> Result<Option<(Url, String)>> {
+        if self.strict {
+            Err(error.context(format!("Failed to include {path}")))
+        } else {
+            event!(Level::WARN, %path, %error, "Failed to include file");
+            Ok(None)
+        }
+    }
+
+    fn resolve_into(&mut self, input: &str, base_url: &Url, resolved: &mut String) -> Result<()> {
+        let mut in_code_block = false;
+        for line in input.split_inclusive('\n') {
+            if is_fence(line) {
+                in_code_block = !in_code_block;
+            }
+            let Some(path) = include_path(line).filter(|_| !in_code_block) else {
+                resolved.push_str(line);
+                continue;
+            };
+            let Some((url, contents)) = self.read(base_url, path)? else {
+                resolved.push_str(line);
+                continue;
+            };
+            if self.stack.contains(&url) {
+                let cycle = self
+                    .stack
+                    .iter()
+                    .chain(std::iter::once(&url))
+                    .map(Url::as_str)
+                    .collect::<Vec<_>>();
+                return Err(anyhow!("Include cycle: {}", cycle.join(" -> ")));
+            }
+            if MAX_INCLUDE_DEPTH <= self.stack.len() {
+                return Err(anyhow!(
+                    "Includes nested deeper than {MAX_INCLUDE_DEPTH} levels at {url}"
+                ));
+            }
+            let contents = normalize_line_endings(&contents);
+            let (_, contents) = frontmatter::split_front_matter(&contents);
+            self.stack.push(url.clone());
+            self.resolve_into(contents, &url, resolved)?;
+            self.stack.pop();
+            if line.ends_with('\n') && !resolved.ends_with('\n') {
+                resolved.push('\n');
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark_mdcat::resources::FileResourceHandler;
+    use similar_asserts::assert_eq;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn resolve(input: &str, directory: &Path, strict: bool) -> Result<String> {
+        let base_url = Url::from_directory_path(directory).unwrap();
+        resolve_includes(input, &base_url, &FileResourceHandler::new(1024), strict)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("mdcat-include-{name}-{}", std::process::id()));
+        fs::create_dir_all(directory.join("parts")).unwrap();
+        directory
+    }
+
+    #[test]
+    fn parse_include_directives() {
+        assert_eq!(include_path("{{include: partial.md}}"), Some("partial.md"));
+        assert_eq!(
+            include_path("  {{ include:foo/bar.md }}\n"),
+            Some("foo/bar.md")
+        );
+        assert_eq!(include_path("{{include: }}"), None);
+        assert_eq!(include_path("See {{include: partial.md}}"), None);
+        assert_eq!(include_path("{{exclude: partial.md}}"), None);
+    }
+
+    #[test]
+    fn nested_includes() {
+        let directory = temp_dir("nested");
+        fs::write(
+            directory.join("parts/outer.md"),
+            "---\ntitle: Outer\n---\nOuter\n{{include: inner.md}}\n",
+        )
+        .unwrap();
+        fs::write(directory.join("parts/inner.md"), "Inner").unwrap();
+        let input =
+            "# Main\n\n{{include: parts/outer.md}}\n\n```\n{{include: parts/outer.md}}\n```\n";
+        let resolved = resolve(input, &directory, true).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            resolved,
+            "# Main\n\nOuter\nInner\n\n```\n{{include: parts/outer.md}}\n```\n"
+        );
+    }
+
+    #[test]
+    fn include_cycle() {
+        let directory = temp_dir("cycle");
+        fs::write(directory.join("self.md"), "Self\n{{include: self.md}}\n").unwrap();
+        let error = resolve("{{include: self.md}}\n", &directory, false).unwrap_err();
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(error.to_string().starts_with("Include cycle: "), "{error}");
+    }
+
+    #[test]
+    fn missing_include() {
+        let directory = temp_dir("missing");
+        let input = "{{include: missing.md}}\n";
+        let resolved = resolve(input, &directory, false).unwrap();
+        let error = resolve(input, &directory, true).unwrap_err();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(resolved, input);
+        assert_eq!(error.to_string(), "Failed to include missing.md");
+    }
+
+    #[test]
+    fn refuse_include_outside_of_base_directory() {
+        let directory = temp_dir("outside");
+        fs::write(directory.join("secret.md"), "Secret\n").unwrap();
+        fs::write(
+            directory.join("parts/escape.md"),
+            "{{include: ../secret.md}}\n",
+        )
+        .unwrap();
+        let base = directory.join("parts");
+        let error = resolve("{{include: ../secret.md}}\n", &base, false).unwrap_err();
+        let nested = resolve("{{include: escape.md}}\n", &base, false).unwrap_err();
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("Refusing to include ../secret.md"));
+        assert!(nested
+            .to_string()
+            .starts_with("Refusing to include ../secret.md"));
+    }
+}
@@ -12,162 +12,1376 @@
 #![deny(warnings, missing_docs, clippy::all)]
 #![forbid(unsafe_code)]
 
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::stdin;
-use std::io::{prelude::*, BufWriter};
-use std::path::PathBuf;
+use std::io::{prelude::*, BufWriter, ErrorKind, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
-use pulldown_cmark::{Options, Parser};
+use anyhow::{anyhow, Context, Result};
+use pulldown_cmark::{Event, Options, Parser, Tag};
 use pulldown_cmark_mdcat::resources::{
-    DispatchingResourceHandler, FileResourceHandler, ResourceUrlHandler,
+    DataUriResourceHandler, DispatchingResourceHandler, FileResourceHandler, ResourceUrlHandler,
 };
+use pulldown_cmark_mdcat::terminal::TerminalSize;
 use pulldown_cmark_mdcat::{Environment, Settings};
-use resources::CurlResourceHandler;
+use resources::{CachingResourceHandler, CurlOptions, CurlResourceHandler, DEFAULT_CACHE_MAX_AGE};
 use tracing::{event, instrument, Level};
+use url::Url;
 
-use args::ResourceAccess;
+use args::{CodeWrap, Columns, OutputFormat, ResourceAccess};
+use error::{InputTooLarge, RenderTimeout, ResourceError};
 use output::Output;
 
+/// Reading markdown and resources from archives.
+#[cfg(feature = "archive")]
+pub mod archive;
 /// Argument parsing for mdcat.
 #[allow(missing_docs)]
 pub mod args;
+/// Reading markdown from the clipboard.
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+/// Configuration file for mdcat.
+pub mod config;
+/// Emoji shortcodes.
+pub mod emoji;
+/// Error classes and exit codes.
+pub mod error;
+/// Filters for markdown events.
+pub mod filter;
+/// Front matter in markdown documents.
+pub mod frontmatter;
+/// Changes of markdown documents in git.
+pub mod git;
+/// Checking the hierarchy of headings in markdown documents.
+pub mod headings;
+/// External syntax highlighters.
+pub mod highlighter;
+/// Include directives in markdown documents.
+pub mod include;
+/// Checking links in markdown documents.
+pub mod links;
+/// Checking markdown source for constructs which are easy to get wrong.
+pub mod lint;
 /// Output handling for mdcat.
 pub mod output;
+/// Machine-readable progress information.
+pub mod progress;
 /// Resource handling for mdca.
 pub mod resources;
 /// Statistics and line number handling for mdcat.
 pub mod stats;
+/// Information about the terminal.
+pub mod terminal;
+/// Table of contents for mdcat.
+pub mod toc;
 
 /// Default read size limit for resources.
 pub static DEFAULT_RESOURCE_READ_LIMIT: u64 = 104_857_600;
 
+/// Filenames with glob patterns expanded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpandedFilenames {
+    /// All filenames, in order.
+    pub filenames: Vec<String>,
+    /// All patterns which matched no file.
+    pub unmatched: Vec<String>,
+}
+
+/// Expand glob patterns in `filenames`.
+///
+/// Expand each filename which contains glob metacharacters, unless a file with this literal
+/// name exists, or the pattern is invalid.  Keep all other filenames as they are.
+///
+/// Retain the order of `filenames`, sort the matches of each pattern, and omit matches which
+/// already occurred earlier.
+pub fn expand_globs<S: AsRef<str>>(filenames: &[S]) -> ExpandedFilenames {
+    let mut expanded = ExpandedFilenames::default();
+    let mut seen = HashSet::new();
+    for filename in filenames {
+        let filename = filename.as_ref();
+        let is_pattern = filename.contains(['*', '?', '['])
+            && remote_url(filename).is_none()
+            && !Path::new(filename).exists();
+        let matches = if is_pattern {
+            glob::glob(filename).ok().map(|paths| {
+                paths
+                    .filter_map(|path| path.ok())
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            })
+        } else {
+            None
+        };
+        match matches {
+            Some(matches) if matches.is_empty() => {
+                expanded.unmatched.push(filename.to_string());
+            }
+            Some(matches) => {
+                for path in matches {
+                    if seen.insert(path.clone()) {
+                        expanded.filenames.push(path);
+                    }
+                }
+            }
+            None => {
+                seen.insert(filename.to_string());
+                expanded.filenames.push(filename.to_string());
+            }
+        }
+    }
+    expanded
+}
+
+/// The markdown extensions to enable when parsing documents.
+///
+/// All extensions except smart punctuation and definition lists are enabled by default.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MarkdownExtensions {
+    /// Parse pipe tables.
+    pub tables: bool,
+    /// Parse `~~strikethrough~~`.
+    pub strikethrough: bool,
+    /// Parse task list items, i.e. `- [ ]` and `- [x]`.
+    pub tasklists: bool,
+    /// Render straight quotes, `--`, `---` and `...` as typographic punctuation.
+    pub smart_punctuation: bool,
+    /// Parse definition lists, i.e. terms followed by `: Definition` lines.
+    pub definition_lists: bool,
+    /// Parse inline math in `$…$` and display math in `$$…$$`.
+    pub math: bool,
+}
+
+impl Default for MarkdownExtensions {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            strikethrough: true,
+            tasklists: true,
+            smart_punctuation: false,
+            definition_lists: false,
+            math: false,
+        }
+    }
+}
+
+impl MarkdownExtensions {
+    /// The parser options for these extensions.
+    pub fn options(&self) -> Options {
+        let mut options = Options::empty();
+        options.set(Options::ENABLE_TABLES, self.tables);
+        options.set(Options::ENABLE_STRIKETHROUGH, self.strikethrough);
+        options.set(Options::ENABLE_TASKLISTS, self.tasklists);
+        options.set(Options::ENABLE_SMART_PUNCTUATION, self.smart_punctuation);
+        options.set(Options::ENABLE_DEFINITION_LIST, self.definition_lists);
+        options.set(Options::ENABLE_MATH, self.math);
+        options
+    }
+}
+
+/// The markdown extensions mdcat renders by default.
+fn markdown_options() -> Options {
+    MarkdownExtensions::default().options()
+}
+
+/// Parse `filename` as a remote URL, if it is one.
+///
+/// Return the URL if `filename` is a `http://` or `https://` URL.
+fn remote_url(filename: &str) -> Option<Url> {
+    Url::parse(filename)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Convert a directory to a base URL for resolving references.
+fn directory_url(directory: &Path) -> Result<Url> {
+    Url::from_directory_path(directory).map_err(|_| {
+        anyhow!(
+            "Base directory {} must be an absolute path",
+            directory.display()
+        )
+    })
+}
+
+/// The number of columns to use if the output is no terminal.
+pub const DEFAULT_COLUMNS: u16 = 80;
+
+/// Get the number of columns to render to `output` with.
+///
+/// Return a fixed number of `columns` as is.  Otherwise query the current width of the terminal
+/// if `output` is a terminal, and use [`DEFAULT_COLUMNS`] if it is not, or if the terminal size
+/// is unknown; then take a percentage of this width if requested.
+pub fn effective_columns(columns: Columns, output: &Output) -> u16 {
+    let width = match columns {
+        Columns::Fixed(columns) => return columns,
+        _ if output.is_terminal() => {
+            TerminalSize::detect().map_or(DEFAULT_COLUMNS, |size| size.columns)
+        }
+        _ => DEFAULT_COLUMNS,
+    };
+    columns.of_width(width)
+}
+
 /// Read input for `filename`.
 ///
-/// If `filename` is `-` read from standard input, otherwise try to open and
-/// read the given file.
-pub fn read_input<T: AsRef<str>>(filename: T) -> Result<(PathBuf, String)> {
+/// If `filename` is `-` read from standard input.  If `filename` is a `http://` or `https://` URL
+/// read it through the `resource_handler`; fail if the resource handler does not support remote
+/// URLs, i.e. if remote resources are disabled.  With the `archive` feature, also read `archive:`
+/// URLs of archive members through the `resource_handler`.  Otherwise try to open and read the
+/// given file.
+///
+/// If `options` have a maximum input size fail with [`InputTooLarge`] if the input is larger,
+/// without reading more than the maximum size from local files or standard input.
+///
+/// Return the base URL to resolve relative references in the input with, and the input.
+pub fn read_input<T: AsRef<str>>(
+    filename: T,
+    resource_handler: &dyn ResourceUrlHandler,
+    options: &ProcessOptions,
+) -> Result<(Url, String)> {
+    let max_size = options.max_input_size;
     let cd = std::env::current_dir()?;
-    let mut buffer = String::new();
+    let check_size = |size: usize| match max_size {
+        Some(max_size) if max_size < size as u64 => Err(InputTooLarge(max_size)),
+        _ => Ok(()),
+    };
+
+    #[cfg(feature = "archive")]
+    if let Some(url) = Url::parse(filename.as_ref())
+        .ok()
+        .filter(|url| url.scheme() == archive::ARCHIVE_SCHEME)
+    {
+        let data = resource_handler.read_resource(&url).map_err(|error| {
+            if error.kind() == ErrorKind::Unsupported {
+                anyhow!("Cannot read {url}: no archive given")
+            } else {
+                anyhow::Error::new(error)
+            }
+        })?;
+        check_size(data.data.len())?;
+        let buffer = String::from_utf8(data.data)
+            .with_context(|| format!("Archive member {url} is not valid UTF-8"))?;
+        return Ok((url.join("./")?, buffer));
+    }
 
     if filename.as_ref() == "-" {
-        stdin().read_to_string(&mut buffer)?;
-        Ok((cd, buffer))
+        let buffer = read_to_string_limited(stdin(), max_size)?;
+        Ok((directory_url(&cd)?, buffer))
+    } else if let Some(url) = remote_url(filename.as_ref()) {
+        let data = resource_handler.read_resource(&url).map_err(|error| {
+            if error.kind() == ErrorKind::Unsupported {
+                anyhow!("Cannot read remote input {url}: remote resources are disabled")
+            } else {
+                ResourceError::new(url.clone(), error).into()
+            }
+        })?;
+        check_size(data.data.len())?;
+        let buffer = String::from_utf8(data.data)
+            .with_context(|| format!("Remote input {url} is not valid UTF-8"))?;
+        let base_url = url.join("./")?;
+        Ok((base_url, buffer))
+    } else {
+        let buffer = read_to_string_limited(File::open(filename.as_ref())?, max_size)?;
+        let base_dir = input_directory(&cd, Path::new(filename.as_ref()));
+        Ok((directory_url(&base_dir)?, buffer))
+    }
+}
+
+/// Get the directory of the input file `filename`, relative to the current directory `cd`.
+///
+/// On Windows, turn extended-length paths like `\\?\C:\docs` or `\\?\UNC\server\share\docs`
+/// into regular paths first: extended-length paths take `/` literally, so a relative `filename`
+/// with forward slashes joined to an extended-length `cd` would otherwise get the wrong parent.
+fn input_directory(cd: &Path, filename: &Path) -> PathBuf {
+    let path = cd.join(filename);
+    #[cfg(windows)]
+    let path = without_verbatim_prefix(&path);
+    path.parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| cd.to_path_buf())
+}
+
+/// Get the path of the output file for the input `filename` in `directory`.
+///
+/// Name the file after the input, or the last segment of a remote URL, with the extension of
+/// `format`; standard input becomes `stdin`.
+pub fn output_path(directory: &Path, filename: &str, format: OutputFormat) -> PathBuf {
+    let url = remote_url(filename);
+    let name = match &url {
+        _ if filename == "-" => "stdin",
+        Some(url) => url
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+            .unwrap_or("index"),
+        None => filename,
+    };
+    let mut output = Path::new(name)
+        .file_stem()
+        .unwrap_or_else(|| "index".as_ref())
+        .to_os_string();
+    output.push(".");
+    output.push(format.extension());
+    directory.join(output)
+}
+
+/// Replace the extended-length prefix of `path` with a regular drive or UNC prefix.
+///
+/// Return other paths as they are.
+#[cfg(windows)]
+fn without_verbatim_prefix(path: &Path) -> PathBuf {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return path.to_path_buf();
+    };
+    let mut simplified = OsString::new();
+    match prefix.kind() {
+        Prefix::VerbatimDisk(letter) => simplified.push(format!("{}:", char::from(letter))),
+        Prefix::VerbatimUNC(server, share) => {
+            simplified.push(r"\\");
+            simplified.push(server);
+            simplified.push("\\");
+            simplified.push(share);
+        }
+        _ => return path.to_path_buf(),
+    }
+    simplified.push(components.as_path());
+    PathBuf::from(simplified)
+}
+
+/// Read all of `source` into a string.
+///
+/// If `max_size` is given read at most one byte more than `max_size`, and fail with
+/// [`InputTooLarge`] if `source` has more than `max_size` bytes.
+fn read_to_string_limited<R: Read>(mut source: R, max_size: Option<u64>) -> Result<String> {
+    let mut buffer = String::new();
+    match max_size {
+        None => {
+            source.read_to_string(&mut buffer)?;
+        }
+        Some(max_size) => {
+            let mut bytes = Vec::new();
+            source
+                .take(max_size.saturating_add(1))
+                .read_to_end(&mut bytes)?;
+            if max_size < bytes.len() as u64 {
+                return Err(InputTooLarge(max_size).into());
+            }
+            buffer = String::from_utf8(bytes)
+                .map_err(|error| std::io::Error::new(ErrorKind::InvalidData, error))?;
+        }
+    }
+    Ok(buffer)
+}
+
+/// Normalize Windows line endings in `input`.
+///
+/// Replace all CRLF line endings with LF, to keep carriage returns out of line numbers, line
+/// ranges, and statistics.  Return `input` as is if it has no CRLF line endings.
+pub fn normalize_line_endings(input: &str) -> Cow<'_, str> {
+    if input.contains("\r\n") {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Render markdown `input` to a string.
+///
+/// Render with the same markdown extensions as mdcat itself, in the given `env`, and read
+/// resources such as images with `resource_handler`.  Like mdcat itself, ignore broken pipes
+/// while rendering and return everything rendered up to that point.
+///
+/// ```
+/// use pulldown_cmark_mdcat::resources::NoopResourceHandler;
+/// use pulldown_cmark_mdcat::{Environment, Settings, TerminalProgram, TerminalSize, Theme};
+/// use syntect::parsing::SyntaxSet;
+///
+/// let syntax_set = SyntaxSet::load_defaults_newlines();
+/// let settings = Settings::new(
+///     TerminalProgram::Dumb.capabilities(),
+///     TerminalSize::default(),
+///     &syntax_set,
+///     Theme::default(),
+/// );
+/// let env = Environment::for_local_directory(&std::env::current_dir()?)?;
+/// let rendered = mdcat::render_markdown("# Hello", &settings, &env, &NoopResourceHandler)?;
+/// assert_eq!(rendered, "\u{2504}Hello\n");
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn render_markdown(
+    input: &str,
+    settings: &Settings,
+    env: &Environment,
+    resource_handler: &dyn ResourceUrlHandler,
+) -> Result<String> {
+    let parser = Parser::new_ext(input, markdown_options());
+    let mut buffer = Vec::new();
+    handle_broken_pipe(
+        pulldown_cmark_mdcat::push_tty(settings, env, resource_handler, &mut buffer, parser),
+        false,
+    )?;
+    String::from_utf8(buffer).with_context(|| "Rendered markdown is not UTF-8".to_string())
+}
+
+/// Render markdown `input` to `writer`.
+///
+/// Resolve relative references against `base_url`.  If `show_line_numbers` is `true` render
+/// the document first, and then prefix every line of the rendered output with its line number;
+/// line numbers never touch the markdown source, so they leave the document structure intact.
+pub fn write_markdown<W: Write>(
+    input: &str,
+    base_url: &Url,
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    writer: &mut W,
+    show_line_numbers: bool,
+) -> std::io::Result<()> {
+    write_events(
+        Parser::new_ext(input, markdown_options()),
+        base_url,
+        settings,
+        resource_handler,
+        writer,
+        show_line_numbers
+            .then(stats::LineNumberOptions::default)
+            .as_ref(),
+    )
+}
+
+/// Render markdown `events` to `writer`.
+///
+/// See [`write_markdown`].  If `line_numbers` is given, prefix every line with its line number
+/// formatted accordingly, but never colour line numbers if the terminal does not support styles.
+fn write_events<'a, W: Write, I: Iterator<Item = Event<'a>>>(
+    events: I,
+    base_url: &Url,
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    writer: &mut W,
+    line_numbers: Option<&stats::LineNumberOptions>,
+) -> std::io::Result<()> {
+    let env = Environment::for_localhost(base_url.clone())?;
+
+    if let Some(line_numbers) = line_numbers {
+        let mut rendered = Vec::new();
+        pulldown_cmark_mdcat::push_tty(settings, &env, resource_handler, &mut rendered, events)?;
+        let rendered = String::from_utf8_lossy(&rendered);
+        let mut options = line_numbers.clone();
+        if settings.terminal_capabilities.style.is_none() {
+            options.colour = None;
+        }
+        let mut formatter =
+            stats::LineNumberFormatter::new(true, rendered.lines().count()).with_options(options);
+        formatter.write_lines(writer, &rendered)
     } else {
-        let mut source = File::open(filename.as_ref())?;
-        source.read_to_string(&mut buffer)?;
-        let base_dir = cd
-            .join(filename.as_ref())
-            .parent()
-            .map(|p| p.to_path_buf())
-            .unwrap_or(cd);
-        Ok((base_dir, buffer))
+        pulldown_cmark_mdcat::push_tty(settings, &env, resource_handler, writer, events)
+    }
+}
+
+/// An inclusive range of source lines, counting from 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LineRange {
+    /// The first line.
+    pub start: usize,
+    /// The last line; `None` for the end of the input.
+    pub end: Option<usize>,
+}
+
+impl LineRange {
+    /// Get the lines of `input` in this range.
+    ///
+    /// Clamp the range to the lines of `input`, i.e. return an empty string if `input` has less
+    /// than `start` lines, and all remaining lines if `input` has less than `end` lines.
+    pub fn slice<'a>(&self, input: &'a str) -> &'a str {
+        let mut start = input.len();
+        let mut end = input.len();
+        let mut offset = 0;
+        for (index, line) in input.split_inclusive('\n').enumerate() {
+            let number = index + 1;
+            if number == self.start {
+                start = offset;
+            }
+            offset += line.len();
+            if Some(number) == self.end {
+                end = offset;
+                break;
+            }
+        }
+        &input[start.min(end)..end]
+    }
+}
+
+/// How to process a file.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOptions {
+    /// Show line numbers in the rendered output.
+    pub line_numbers: bool,
+    /// How to format line numbers.
+    pub line_number_options: stats::LineNumberOptions,
+    /// Only render these lines of the input.
+    pub lines: Option<LineRange>,
+    /// Print the input verbatim instead of rendering it, with line numbers if requested.
+    pub literal: bool,
+    /// Fail if an input has more than this many bytes.
+    pub max_input_size: Option<u64>,
+    /// Resolve include directives before parsing the input.
+    pub enable_includes: bool,
+    /// Fail if an included file does not exist, instead of leaving the include directive alone.
+    pub strict_includes: bool,
+    /// Resolve relative references against this absolute directory instead of the directory
+    /// of the input.
+    pub base_dir: Option<PathBuf>,
+    /// Print document statistics; if line numbers are disabled print only statistics.
+    pub stats: Option<stats::StatsOptions>,
+    /// Render a table of contents with headings up to this level before the document.
+    pub toc_max_depth: Option<u8>,
+    /// Print the raw markdown events instead of rendering the document.
+    pub dump_events: bool,
+    /// List the anchors of all headings instead of rendering the document.
+    pub list_anchors: bool,
+    /// List all images and links instead of rendering the document, and mark those which this
+    /// resource access blocks.
+    pub print_resources: Option<ResourceAccess>,
+    /// Report broken links instead of rendering the document.
+    pub check_links: bool,
+    /// Fail if the document has any broken links.
+    pub strict_links: bool,
+    /// Report headings which skip levels, and multiple level 1 headings, instead of rendering
+    /// the document.
+    pub check_headings: bool,
+    /// Fail if the document has any heading warnings.
+    pub strict_headings: bool,
+    /// Report tabs in list indentation, lists without a blank line before, and bare URLs
+    /// instead of rendering the document.
+    pub lint: bool,
+    /// Fail if the document has any markdown warnings.
+    pub strict_lint: bool,
+    /// Render the document without output, and fail if any image fails to load.
+    pub dry_run: bool,
+    /// Omit sections with headings deeper than this level.
+    pub max_heading_level: Option<u8>,
+    /// Only render the first section with this title.
+    pub section: Option<String>,
+    /// Print the keys of front matter before the document.
+    pub show_front_matter: bool,
+    /// Render front matter as a table of keys and values before the document.
+    pub front_matter_table: bool,
+    /// Render images as their alt text.
+    pub no_images: bool,
+    /// Expand emoji shortcodes like `:rocket:` outside of code.
+    pub emoji: bool,
+    /// Show a progress bar of checked tasks after every task list.
+    pub task_progress: bool,
+    /// Expand tabs in code blocks to this many columns, or leave tabs alone if 0.
+    pub tab_width: usize,
+    /// How to handle lines in code blocks which are wider than the terminal.
+    pub wrap_code: CodeWrap,
+    /// Prefetch all images before rendering, if the terminal can show images.
+    pub prefetch: bool,
+    /// Highlight code blocks with this external command, if the terminal supports styles.
+    pub highlighter: Option<highlighter::ExternalHighlighter>,
+    /// The markdown extensions to parse.
+    pub extensions: MarkdownExtensions,
+    /// The format to render the document to.
+    pub output_format: OutputFormat,
+    /// Remove HTML comments from the document.
+    pub strip_comments: bool,
+    /// Show a progress bar on standard error while rendering large documents.
+    pub progress_bar: bool,
+    /// Abort rendering a document if it takes longer than this.
+    pub render_timeout: Option<Duration>,
+    /// Fail on broken pipes instead of ignoring them.
+    pub strict_pipe: bool,
+}
+
+/// Collect the URLs of all images in markdown `input`, resolved against `base_url`.
+///
+/// Skip images with invalid URLs, and return every URL only once.
+fn image_urls(input: &str, base_url: &Url, extensions: MarkdownExtensions) -> Vec<Url> {
+    let mut urls = Vec::new();
+    for event in Parser::new_ext(input, extensions.options()) {
+        if let Event::Start(Tag::Image { dest_url, .. }) = event {
+            if let Ok(url) = base_url.join(&dest_url) {
+                if !urls.contains(&url) {
+                    urls.push(url);
+                }
+            }
+        }
     }
+    urls
 }
 
 /// Process a single file.
 ///
-/// Read from `filename` and render the contents to `output`.
+/// Read from `filename` and render the contents to `output`, according to `options`.
+///
+/// Return the statistics of the document if `options` request statistics.
 #[instrument(skip(output, settings, resource_handler), level = "debug")]
 pub fn process_file(
     filename: &str,
     settings: &Settings,
     resource_handler: &dyn ResourceUrlHandler,
     output: &mut Output,
-    show_line_numbers: bool,
-    show_stats: bool,
-) -> Result<()> {
-    let (base_dir, input) = read_input(filename)?;
-    event!(
-        Level::TRACE,
-        "Read input, using {} as base directory",
-        base_dir.display()
-    );
-    
-    // Calculate statistics if requested
-    if show_stats {
-        let stats = stats::DocumentStats::from_markdown(&input);
-        writeln!(output.writer(), "{}", stats.format())?;
-        if !show_line_numbers {
-            // If only stats are requested, don't render the full document
-            return Ok(());
+    options: &ProcessOptions,
+) -> Result<Option<stats::DocumentStats>> {
+    let (base_url, input) = read_input(filename, resource_handler, options)?;
+    let base_url = match &options.base_dir {
+        Some(directory) => directory_url(directory)?,
+        None => base_url,
+    };
+    event!(Level::TRACE, "Read input, using {} as base URL", base_url);
+    process_input(
+        filename,
+        &base_url,
+        &input,
+        settings,
+        resource_handler,
+        output,
+        options,
+    )
+}
+
+/// The name of merged files in messages.
+pub const MERGED_NAME: &str = "(merged)";
+
+/// Process `filenames` merged into a single document.
+///
+/// Read all `filenames` like [`process_file`], join them with blank lines, and render the result
+/// as a single document to `output`, according to `options`.  Drop front matter of all but the
+/// first file.  Resolve relative references against the directory of the first file, unless
+/// `options` have a base directory.
+#[instrument(skip(output, settings, resource_handler), level = "debug")]
+pub fn process_merged<T: AsRef<str> + std::fmt::Debug>(
+    filenames: &[T],
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    output: &mut Output,
+    options: &ProcessOptions,
+) -> Result<Option<stats::DocumentStats>> {
+    let mut base_url = None;
+    let mut merged = String::new();
+    for filename in filenames {
+        let filename = filename.as_ref();
+        let (file_base_url, input) = read_input(filename, resource_handler, options)
+            .with_context(|| format!("Failed to read {filename}"))?;
+        if base_url.is_none() {
+            base_url = Some(file_base_url);
+            merged.push_str(&input);
+        } else {
+            merged.push_str("\n\n");
+            merged.push_str(frontmatter::split_front_matter(&input).1);
         }
     }
-    
-    let parser = Parser::new_ext(
+    let base_url = match (&options.base_dir, base_url) {
+        (Some(directory), _) => directory_url(directory)?,
+        (None, Some(base_url)) => base_url,
+        (None, None) => directory_url(&std::env::current_dir()?)?,
+    };
+    event!(Level::TRACE, "Merged input, using {} as base URL", base_url);
+    process_input(
+        MERGED_NAME,
+        &base_url,
+        &merged,
+        settings,
+        resource_handler,
+        output,
+        options,
+    )
+}
+
+/// The name of the clipboard in messages.
+#[cfg(feature = "clipboard")]
+pub const CLIPBOARD_NAME: &str = "(clipboard)";
+
+/// Process markdown from the clipboard.
+///
+/// Like [`process_file`], but read markdown from the clipboard, and resolve relative references
+/// against the current directory, unless `options` have a base directory.
+#[cfg(feature = "clipboard")]
+#[instrument(skip(output, settings, resource_handler), level = "debug")]
+pub fn process_clipboard(
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    output: &mut Output,
+    options: &ProcessOptions,
+) -> Result<Option<stats::DocumentStats>> {
+    let input = clipboard::read_clipboard()?;
+    let base_url = match &options.base_dir {
+        Some(directory) => directory_url(directory)?,
+        None => directory_url(&std::env::current_dir()?)?,
+    };
+    process_input(
+        CLIPBOARD_NAME,
+        &base_url,
         &input,
-        Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
-    );
-    let env = Environment::for_local_directory(&base_dir)?;
-
-    let mut sink = BufWriter::new(output.writer());
-    
-    // If line numbers are enabled, we need to process the content differently
-    if show_line_numbers {
-        let total_lines = input.lines().count();
-        let line_number_width = total_lines.to_string().len();
-        
-        // Add line numbers to each line
-        let lines: Vec<String> = input.lines().enumerate().map(|(i, line)| {
-            format!("{:>width$} │ {}", i + 1, line, width = line_number_width)
-        }).collect();
-        
-        let numbered_input = lines.join("\n");
-        let parser = Parser::new_ext(
-            &numbered_input,
-            Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
+        settings,
+        resource_handler,
+        output,
+        options,
+    )
+}
+
+/// Process markdown `input` from a source named `name`.
+///
+/// Like [`process_file`], but take `input` directly instead of reading it from a file, and
+/// resolve relative references against `base_url`.  `name` refers to the input in messages.
+#[instrument(skip(input, output, settings, resource_handler), level = "debug")]
+pub fn process_input(
+    name: &str,
+    base_url: &Url,
+    input: &str,
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    output: &mut Output,
+    options: &ProcessOptions,
+) -> Result<Option<stats::DocumentStats>> {
+    let base_url = base_url.clone();
+    let input = normalize_line_endings(input);
+    let input = match options.lines {
+        Some(lines) => lines.slice(&input),
+        None => &input,
+    };
+
+    if options.literal {
+        let mut writer = output.writer();
+        if !options.line_numbers {
+            return handle_broken_pipe(writer.write_all(input.as_bytes()), options.strict_pipe)
+                .map(|_| None);
+        }
+        // Number source lines, also if only some lines are shown
+        let mut line_numbers = options.line_number_options.clone();
+        if let Some(lines) = options.lines {
+            line_numbers.start += lines.start.max(1) - 1;
+        }
+        if settings.terminal_capabilities.style.is_none() {
+            line_numbers.colour = None;
+        }
+        let mut formatter =
+            stats::LineNumberFormatter::new(true, input.lines().count()).with_options(line_numbers);
+        return handle_broken_pipe(
+            formatter.write_lines(&mut writer, input),
+            options.strict_pipe,
+        )
+        .map(|_| None);
+    }
+
+    let (front_matter, input) = frontmatter::split_front_matter(input);
+    if let Some(front_matter) = front_matter.filter(|_| options.show_front_matter) {
+        writeln!(
+            output.writer(),
+            "Front matter ({}): {}\n",
+            front_matter.format,
+            front_matter.keys().join(", ")
+        )?;
+    }
+
+    let included;
+    let input = if options.enable_includes {
+        included =
+            include::resolve_includes(input, &base_url, resource_handler, options.strict_includes)?;
+        &included
+    } else {
+        input
+    };
+
+    if let Some(title) = &options.section {
+        // Look for the section like rendering does, to ignore headings in block quotes or deeper
+        // than the maximum heading level
+        let events = filter::max_heading_level(
+            Parser::new_ext(input, options.extensions.options()),
+            options.max_heading_level.unwrap_or(6),
         );
-        
-        pulldown_cmark_mdcat::push_tty(settings, &env, resource_handler, &mut sink, parser)
-            .and_then(|_| {
-                event!(Level::TRACE, "Finished rendering, flushing output");
-                sink.flush()
-            })
-            .or_else(|error| {
-                if error.kind() == std::io::ErrorKind::BrokenPipe {
-                    event!(Level::TRACE, "Ignoring broken pipe");
-                    Ok(())
-                } else {
-                    event!(Level::ERROR, ?error, "Failed to process file: {:#}", error);
-                    Err(error)
+        if filter::section(events, title.clone()).next().is_none() {
+            return Err(anyhow!("No section titled {title}"));
+        }
+    }
+
+    if options.dump_events {
+        let writer = output.writer();
+        for event in Parser::new_ext(input, options.extensions.options()) {
+            writeln!(writer, "{event:?}")?;
+        }
+        return Ok(None);
+    }
+
+    if options.list_anchors {
+        let headings = toc::headings(input);
+        let writer = output.writer();
+        for (heading, anchor) in headings.iter().zip(toc::anchors(&headings)) {
+            writeln!(writer, "#{anchor}\t{}", heading.title)?;
+        }
+        return Ok(None);
+    }
+
+    if let Some(access) = options.print_resources {
+        let writer = output.writer();
+        for resource in links::resources(input, &base_url, options.extensions.options()) {
+            write!(writer, "{name}:{}: {resource}", resource.line)?;
+            if !resource.location.is_allowed(access) {
+                write!(writer, " (would be blocked)")?;
+            }
+            writeln!(writer)?;
+        }
+        return Ok(None);
+    }
+
+    if options.lint {
+        let warnings = lint::lint_warnings(input, options.extensions.options());
+        let writer = output.writer();
+        for warning in &warnings {
+            writeln!(
+                writer,
+                "{name}:{}: warning: {}",
+                warning.line, warning.problem
+            )?;
+        }
+        if options.strict_lint {
+            match warnings.len() {
+                0 => {}
+                1 => return Err(anyhow!("1 markdown warning")),
+                n => return Err(anyhow!("{n} markdown warnings")),
+            }
+        }
+        if !options.check_headings && !options.check_links && !options.dry_run {
+            return Ok(None);
+        }
+    }
+
+    if options.check_headings {
+        let warnings = headings::heading_warnings(input, options.extensions.options());
+        let writer = output.writer();
+        for warning in &warnings {
+            writeln!(
+                writer,
+                "{name}:{}: warning: {}",
+                warning.line, warning.problem
+            )?;
+        }
+        if options.strict_headings {
+            match warnings.len() {
+                0 => {}
+                1 => return Err(anyhow!("1 heading warning")),
+                n => return Err(anyhow!("{n} heading warnings")),
+            }
+        }
+        if !options.check_links && !options.dry_run {
+            return Ok(None);
+        }
+    }
+
+    if options.check_links {
+        let broken = links::broken_links(
+            input,
+            &base_url,
+            resource_handler,
+            options.extensions.options(),
+        );
+        let writer = output.writer();
+        for link in &broken {
+            writeln!(
+                writer,
+                "{name}:{}: {}: {}",
+                link.line, link.destination, link.reason
+            )?;
+        }
+        if options.strict_links {
+            match broken.len() {
+                0 => {}
+                1 => return Err(anyhow!("1 broken link")),
+                n => return Err(anyhow!("{n} broken links")),
+            }
+        }
+        if !options.dry_run {
+            return Ok(None);
+        }
+    }
+
+    if options.dry_run {
+        dry_run(input, &base_url, settings, resource_handler, options)?;
+        return Ok(None);
+    }
+
+    // Calculate statistics if requested
+    let mut document_stats = None;
+    if let Some(stats_options) = &options.stats {
+        // Only count lines added since the given git ref, if requested
+        let added;
+        let stats_input = match &stats_options.since_git {
+            Some(_) if name == "-" || name == MERGED_NAME || remote_url(name).is_some() => {
+                event!(
+                    Level::WARN,
+                    "Cannot diff {} with git: not a local file, counting the whole document",
+                    name
+                );
+                input
+            }
+            Some(git_ref) => match git::added_lines_since(Path::new(name), git_ref)? {
+                Some(lines) => {
+                    added = lines;
+                    added.as_str()
+                }
+                None => {
+                    event!(
+                        Level::WARN,
+                        "{} is not in a git repository, counting the whole document",
+                        name
+                    );
+                    input
                 }
-            })?;
+            },
+            None => input,
+        };
+        let stats = stats::document_stats(stats_input, stats_options);
+        let sections = stats_options.sections.then(|| {
+            stats::DocumentStats::sections_with_options(stats_input, stats_options.extensions)
+        });
+        writeln!(
+            output.stats_writer(),
+            "{}",
+            stats::format_document_stats(&stats, sections.as_deref(), stats_options)
+        )?;
+        if !options.line_numbers && !output.has_stats_output() {
+            // If only stats are requested, don't render the full document
+            return Ok(Some(stats));
+        }
+        document_stats = Some(stats);
+    }
+
+    if let Some(front_matter) = front_matter.filter(|_| options.front_matter_table) {
+        let table = front_matter.markdown_table();
+        if !table.is_empty() {
+            let events = Parser::new_ext(&table, markdown_options());
+            handle_broken_pipe(
+                render_to_output(
+                    events,
+                    &base_url,
+                    settings,
+                    resource_handler,
+                    output.writer(),
+                    options.output_format,
+                    None,
+                ),
+                options.strict_pipe,
+            )?;
+            writeln!(output.writer())?;
+        }
+    }
+
+    if let Some(max_depth) = options.toc_max_depth {
+        let toc = toc::markdown(&toc::headings(input), max_depth);
+        if !toc.is_empty() {
+            let events = Parser::new_ext(&toc, markdown_options());
+            handle_broken_pipe(
+                render_to_output(
+                    events,
+                    &base_url,
+                    settings,
+                    resource_handler,
+                    output.writer(),
+                    options.output_format,
+                    None,
+                ),
+                options.strict_pipe,
+            )?;
+            writeln!(output.writer())?;
+        }
+    }
+
+    if options.prefetch && !options.no_images && settings.terminal_capabilities.image.is_some() {
+        let urls = image_urls(input, &base_url, options.extensions);
+        event!(Level::DEBUG, "Prefetching {} images", urls.len());
+        resource_handler.prefetch(&urls);
+    }
+
+    // Only show progress if it does not end up in the middle of the rendered output
+    let show_progress = options.progress_bar
+        && progress::PROGRESS_BAR_MIN_BYTES <= input.len()
+        && std::io::stderr().is_terminal()
+        && !output.is_terminal();
+    // Start the clock before parsing, because parsing takes time as well
+    let started = Instant::now();
+    let events = document_events(input, settings, options, show_progress);
+    let line_numbers = options.line_numbers.then_some(&options.line_number_options);
+    match options.render_timeout {
+        Some(timeout) => {
+            // Stop rendering at the deadline, and render to a buffer first, to discard partial
+            // output if rendering takes too long
+            let timed_out = Cell::new(false);
+            let events = filter::until_deadline(events, started + timeout, &timed_out);
+            let mut buffer = Vec::new();
+            render_to_output(
+                events,
+                &base_url,
+                settings,
+                resource_handler,
+                &mut buffer,
+                options.output_format,
+                line_numbers,
+            )?;
+            if timed_out.get() {
+                return Err(RenderTimeout(timeout).into());
+            }
+            handle_broken_pipe(output.writer().write_all(&buffer), options.strict_pipe)?;
+        }
+        None => handle_broken_pipe(
+            render_to_output(
+                events,
+                &base_url,
+                settings,
+                resource_handler,
+                output.writer(),
+                options.output_format,
+                line_numbers,
+            ),
+            options.strict_pipe,
+        )?,
+    }
+    Ok(document_stats)
+}
+
+/// Parse markdown `input` into events, and apply all filters requested by `options`.
+///
+/// Only use an external highlighter if `settings` support styled output.  If `show_progress` is
+/// true show a progress bar while consuming events.
+fn document_events<'a>(
+    input: &'a str,
+    settings: &Settings,
+    options: &ProcessOptions,
+    show_progress: bool,
+) -> Box<dyn Iterator<Item = Event<'a>> + 'a> {
+    let parser = Parser::new_ext(input, options.extensions.options());
+    let mut events: Box<dyn Iterator<Item = Event>> = if show_progress {
+        Box::new(progress::with_progress_bar(
+            parser.into_offset_iter(),
+            input.len(),
+        ))
     } else {
-        pulldown_cmark_mdcat::push_tty(settings, &env, resource_handler, &mut sink, parser)
-            .and_then(|_| {
-                event!(Level::TRACE, "Finished rendering, flushing output");
-                sink.flush()
-            })
-            .or_else(|error| {
-                if error.kind() == std::io::ErrorKind::BrokenPipe {
-                    event!(Level::TRACE, "Ignoring broken pipe");
-                    Ok(())
-                } else {
-                    event!(Level::ERROR, ?error, "Failed to process file: {:#}", error);
-                    Err(error)
+        Box::new(parser)
+    };
+    events = Box::new(filter::max_heading_level(
+        events,
+        options.max_heading_level.unwrap_or(6),
+    ));
+    if let Some(title) = options.section.clone() {
+        events = Box::new(filter::section(events, title));
+    }
+    if options.strip_comments {
+        events = Box::new(filter::strip_html_comments(events));
+    }
+    if options.no_images {
+        events = Box::new(filter::images_as_text(events));
+    }
+    if options.emoji {
+        events = Box::new(filter::expand_emoji(events));
+    }
+    if options.task_progress {
+        events = Box::new(filter::task_progress(events));
+    }
+    if 0 < options.tab_width {
+        events = Box::new(filter::expand_tabs_in_code(events, options.tab_width));
+    }
+    // Nested code blocks are indented, so they may still overflow by a few columns
+    let columns = usize::from(settings.terminal_size.columns);
+    match options.wrap_code {
+        CodeWrap::Off => {}
+        CodeWrap::Char => events = Box::new(filter::wrap_code(events, columns)),
+        CodeWrap::Truncate => events = Box::new(filter::truncate_code(events, columns)),
+    }
+    if let Some(highlighter) = options
+        .highlighter
+        .clone()
+        .filter(|_| settings.terminal_capabilities.style.is_some())
+    {
+        events = Box::new(filter::highlight_with_command(events, highlighter));
+    }
+    events
+}
+
+/// Render markdown `input` without output, and read all images.
+///
+/// Discard the rendered document, but fail if any image fails to load.  Ignore images which
+/// `resource_handler` does not support, e.g. remote images if remote resources are disabled.
+fn dry_run(
+    input: &str,
+    base_url: &Url,
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    options: &ProcessOptions,
+) -> Result<()> {
+    write_events(
+        document_events(input, settings, options, false),
+        base_url,
+        settings,
+        resource_handler,
+        &mut std::io::sink(),
+        None,
+    )?;
+    if !options.no_images {
+        for url in image_urls(input, base_url, options.extensions) {
+            match resource_handler.read_resource(&url) {
+                Err(error) if error.kind() != ErrorKind::Unsupported => {
+                    return Err(ResourceError::new(url, error).into());
                 }
-            })?;
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render the separator between two files to `output`.
+///
+/// Replace `{file}` in the `separator` template with `filename`, and render the result as
+/// markdown to `output` in the given `format`, surrounded by blank lines.  Do nothing if
+/// `separator` is empty.  Ignore broken pipes unless `strict_pipe` is true.
+#[instrument(skip(output, settings, resource_handler), level = "debug")]
+pub fn process_file_separator(
+    separator: &str,
+    filename: &str,
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    output: &mut Output,
+    format: OutputFormat,
+    strict_pipe: bool,
+) -> Result<()> {
+    if separator.is_empty() {
+        return Ok(());
     }
-    
+    let input = separator.replace("{file}", filename);
+    let base_url = directory_url(&std::env::current_dir()?)?;
+    // Surround the separator with blank lines, like any other block
+    writeln!(output.writer())?;
+    let events = Parser::new_ext(&input, markdown_options());
+    handle_broken_pipe(
+        render_to_output(
+            events,
+            &base_url,
+            settings,
+            resource_handler,
+            output.writer(),
+            format,
+            None,
+        ),
+        strict_pipe,
+    )?;
+    writeln!(output.writer())?;
     Ok(())
 }
 
+/// Render markdown `events` to `writer` in the given `format`, and flush the writer.
+///
+/// HTML output ignores `line_numbers`, and keeps all URLs as they are.
+///
+/// See [`handle_broken_pipe`] for broken pipes.
+fn render_to_output<'a, I: Iterator<Item = Event<'a>>>(
+    events: I,
+    base_url: &Url,
+    settings: &Settings,
+    resource_handler: &dyn ResourceUrlHandler,
+    writer: &mut dyn Write,
+    format: OutputFormat,
+    line_numbers: Option<&stats::LineNumberOptions>,
+) -> std::io::Result<()> {
+    let mut sink = BufWriter::new(writer);
+    match format {
+        OutputFormat::Terminal => write_events(
+            events,
+            base_url,
+            settings,
+            resource_handler,
+            &mut sink,
+            line_numbers,
+        ),
+        OutputFormat::Html => pulldown_cmark::html::write_html_io(&mut sink, events),
+    }
+    .and_then(|_| {
+        event!(Level::TRACE, "Finished rendering, flushing output");
+        sink.flush()
+    })
+}
+
+/// Ignore a broken pipe in `result`, unless `strict_pipe` is true.
+///
+/// A broken pipe usually means that the reader of our output is no longer interested in it, e.g.
+/// `head` after reading enough lines, or a pager which the user closed early; this is no error
+/// for mdcat, so we ignore broken pipes by default.  With `strict_pipe` fail instead, e.g. to
+/// detect pipelines which fail unexpectedly.
+fn handle_broken_pipe(result: std::io::Result<()>, strict_pipe: bool) -> Result<()> {
+    result.or_else(|error| {
+        if error.kind() == std::io::ErrorKind::BrokenPipe && !strict_pipe {
+            event!(Level::TRACE, "Ignoring broken pipe");
+            Ok(())
+        } else {
+            event!(Level::ERROR, ?error, "Failed to process file: {:#}", error);
+            Err(error.into())
+        }
+    })
+}
+
+/// Options for the resource handler of mdcat.
+#[derive(Debug, Clone)]
+pub struct ResourceOptions {
+    /// The maximum size of local and remote resources, in bytes.
+    pub read_limit: u64,
+    /// Whether to read local resources which are symbolic links.
+    pub follow_symlinks: bool,
+    /// How to fetch remote resources.
+    pub curl: CurlOptions,
+    /// The directory to cache remote images in, if any.
+    pub cache_directory: Option<PathBuf>,
+}
+
+impl Default for ResourceOptions {
+    fn default() -> Self {
+        Self {
+            read_limit: DEFAULT_RESOURCE_READ_LIMIT,
+            follow_symlinks: true,
+            curl: CurlOptions::default(),
+            cache_directory: None,
+        }
+    }
+}
+
 /// Create the resource handler for mdcat.
-pub fn create_resource_handler(access: ResourceAccess) -> Result<DispatchingResourceHandler> {
-    let mut resource_handlers: Vec<Box<dyn ResourceUrlHandler>> = vec![Box::new(
-        FileResourceHandler::new(DEFAULT_RESOURCE_READ_LIMIT),
-    )];
+///
+/// Read local and remote resources according to `options`.  Always decode inline `data:` URLs,
+/// regardless of `access`.  Do not read any other resource in [`ResourceAccess::Sandbox`], not
+/// even local files.
+pub fn create_resource_handler(
+    access: ResourceAccess,
+    options: &ResourceOptions,
+) -> Result<DispatchingResourceHandler> {
+    let mut resource_handlers: Vec<Box<dyn ResourceUrlHandler>> =
+        vec![Box::new(DataUriResourceHandler)];
+    if let ResourceAccess::Sandbox = access {
+        event!(
+            target: "mdcat::main",
+            Level::DEBUG,
+            "Sandboxed, denying access to all local and remote resources"
+        );
+    } else {
+        resource_handlers.push(Box::new(
+            FileResourceHandler::new(options.read_limit)
+                .with_follow_symlinks(options.follow_symlinks),
+        ));
+    }
     if let ResourceAccess::Remote = access {
-        let user_agent = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+        let user_agent = options.curl.user_agent.as_deref().unwrap_or(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ));
         event!(
             target: "mdcat::main",
             Level::DEBUG,
             "Remote resource access permitted, creating HTTP client with user agent {}",
             user_agent
         );
-        let client = CurlResourceHandler::create(DEFAULT_RESOURCE_READ_LIMIT, user_agent)
+        let client = CurlResourceHandler::create(options.read_limit, user_agent, &options.curl)
             .with_context(|| "Failed to build HTTP client".to_string())?;
-        resource_handlers.push(Box::new(client));
+        match &options.cache_directory {
+            Some(directory) => {
+                event!(
+                    target: "mdcat::main",
+                    Level::DEBUG,
+                    "Caching remote images in {}",
+                    directory.display()
+                );
+                resource_handlers.push(Box::new(
+                    CachingResourceHandler::new(client, directory.clone(), DEFAULT_CACHE_MAX_AGE)
+                        .with_request_headers(options.curl.headers.clone()),
+                ));
+            }
+            None => resource_handlers.push(Box::new(client)),
+        }
     }
     Ok(DispatchingResourceHandler::new(resource_handlers))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    #[cfg(unix)]
+    fn input_directory_of_relative_path() {
+        assert_eq!(
+            input_directory(Path::new("/work"), Path::new("docs/README.md")),
+            Path::new("/work/docs")
+        );
+        assert_eq!(
+            input_directory(Path::new("/work"), Path::new("/srv/README.md")),
+            Path::new("/srv")
+        );
+    }
+
+    #[test]
+    fn output_paths() {
+        let directory = Path::new("out");
+        assert_eq!(
+            output_path(directory, "docs/README.md", OutputFormat::Html),
+            Path::new("out/README.html")
+        );
+        assert_eq!(
+            output_path(directory, "notes-1.2.md", OutputFormat::Terminal),
+            Path::new("out/notes-1.2.txt")
+        );
+        assert_eq!(
+            output_path(directory, "-", OutputFormat::Html),
+            Path::new("out/stdin.html")
+        );
+        assert_eq!(
+            output_path(
+                directory,
+                "https://example.com/docs/guide.md",
+                OutputFormat::Html
+            ),
+            Path::new("out/guide.html")
+        );
+        assert_eq!(
+            output_path(directory, "https://example.com/", OutputFormat::Html),
+            Path::new("out/index.html")
+        );
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn input_directory_of_extended_length_path() {
+        let directory = input_directory(Path::new(r"\\?\C:\work"), Path::new("docs/README.md"));
+        assert_eq!(directory, Path::new(r"C:\work\docs"));
+        assert_eq!(
+            directory_url(&directory).unwrap().as_str(),
+            "file:///C:/work/docs/"
+        );
+        let directory = input_directory(
+            Path::new(r"C:\other"),
+            Path::new(r"\\?\C:\work\docs\README.md"),
+        );
+        assert_eq!(directory, Path::new(r"C:\work\docs"));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn input_directory_of_unc_path() {
+        for filename in [
+            r"\\server\share\docs\README.md",
+            r"\\?\UNC\server\share\docs\README.md",
+        ] {
+            let directory = input_directory(Path::new(r"C:\other"), Path::new(filename));
+            assert_eq!(directory, Path::new(r"\\server\share\docs"), "{filename}");
+            assert_eq!(
+                directory_url(&directory).unwrap().as_str(),
+                "file://server/share/docs/",
+                "{filename}"
+            );
+        }
+    }
+}
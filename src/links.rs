@@ -0,0 +1,277 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Display, Formatter};
+use std::io::ErrorKind;
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag};
+use pulldown_cmark_mdcat::ResourceUrlHandler;
+use url::Url;
+
+use crate::args::ResourceAccess;
+
+/// A link whose target does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// The line of the link in the markdown source, counting from 1.
+    pub line: usize,
+    /// The destination of the link, as written in the markdown source.
+    pub destination: String,
+    /// Why the link is broken.
+    pub reason: String,
+}
+
+/// Check whether the target of the link to `url` exists.
+///
+/// Check local files directly, and all other URLs with `resource_handler`.  Return `None`
+/// if the target exists, or if its existence cannot be checked, e.g. because the resource
+/// handler does not support the URL.
+fn check_url(url: &Url, resource_handler: &dyn ResourceUrlHandler) -> Option<String> {
+    if url.scheme() == "file" {
+        return match url.to_file_path() {
+            Ok(path) if path.exists() => None,
+            Ok(_) => Some("No such file or directory".to_string()),
+            Err(_) => Some("Invalid file URL".to_string()),
+        };
+    }
+    match resource_handler.check_resource(url) {
+        Ok(_) => None,
+        // Not a broken link, we just didn't check it
+        Err(error)
+            if matches!(
+                error.kind(),
+                ErrorKind::Unsupported | ErrorKind::FileTooLarge
+            ) =>
+        {
+            None
+        }
+        Err(error) => Some(error.to_string()),
+    }
+}
+
+/// Find all broken links in markdown `input` parsed with `options`.
+///
+/// Resolve relative link destinations against `base_url` and report all links to local files
+/// which do not exist.  Check remote links with `resource_handler`; this reports remote links
+/// which fail to load, e.g. because the server responds with an error status, but skips remote
+/// links entirely if `resource_handler` does not support remote URLs.  Ignore links to anchors
+/// within the document and email addresses.
+pub fn broken_links(
+    input: &str,
+    base_url: &Url,
+    resource_handler: &dyn ResourceUrlHandler,
+    options: Options,
+) -> Vec<BrokenLink> {
+    let line_starts = std::iter::once(0)
+        .chain(input.match_indices('\n').map(|(index, _)| index + 1))
+        .collect::<Vec<_>>();
+    let mut broken = Vec::new();
+    for (event, range) in Parser::new_ext(input, options).into_offset_iter() {
+        let Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            ..
+        }) = event
+        else {
+            continue;
+        };
+        if link_type == LinkType::Email || dest_url.is_empty() || dest_url.starts_with('#') {
+            continue;
+        }
+        let reason = match base_url.join(&dest_url) {
+            Ok(url) => check_url(&url, resource_handler),
+            Err(error) => Some(format!("Invalid URL: {error}")),
+        };
+        if let Some(reason) = reason {
+            broken.push(BrokenLink {
+                line: line_starts.partition_point(|start| *start <= range.start),
+                destination: dest_url.into_string(),
+                reason,
+            });
+        }
+    }
+    broken
+}
+
+/// Whether a resource is an image or a link.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// An image, which mdcat reads to show it inline.
+    Image,
+    /// A link.
+    Link,
+}
+
+impl Display for ResourceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceKind::Image => write!(f, "image"),
+            ResourceKind::Link => write!(f, "link"),
+        }
+    }
+}
+
+/// Where a resource lives.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResourceLocation {
+    /// Embedded in the document, as a `data:` URL.
+    Embedded,
+    /// A local file.
+    Local,
+    /// A remote http or https URL.
+    Remote,
+}
+
+impl ResourceLocation {
+    /// Whether `access` permits reading resources at this location.
+    pub fn is_allowed(self, access: ResourceAccess) -> bool {
+        match (self, access) {
+            (ResourceLocation::Embedded, _) => true,
+            (ResourceLocation::Local, ResourceAccess::Sandbox) => false,
+            (ResourceLocation::Local, _) => true,
+            (ResourceLocation::Remote, ResourceAccess::Remote) => true,
+            (ResourceLocation::Remote, _) => false,
+        }
+    }
+}
+
+impl Display for ResourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceLocation::Embedded => write!(f, "embedded"),
+            ResourceLocation::Local => write!(f, "local"),
+            ResourceLocation::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+/// An image or link in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    /// The line of the resource in the markdown source, counting from 1.
+    pub line: usize,
+    /// Whether the resource is an image or a link.
+    pub kind: ResourceKind,
+    /// Where the resource lives.
+    pub location: ResourceLocation,
+    /// The resolved URL of the resource.
+    pub url: Url,
+}
+
+impl Display for Resource {
+    /// Format the kind and location of the resource, and its path if local, or its URL.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ", self.kind, self.location)?;
+        match self.url.to_file_path() {
+            Ok(path) if self.location == ResourceLocation::Local => write!(f, "{}", path.display()),
+            _ => write!(f, "{}", self.url),
+        }
+    }
+}
+
+/// Find all images and links in markdown `input` parsed with `options`.
+///
+/// Resolve relative destinations against `base_url`.  Skip links to anchors within the
+/// document, email addresses, and all destinations which are no data URL, local file, or http or
+/// https URL.
+pub fn resources(input: &str, base_url: &Url, options: Options) -> Vec<Resource> {
+    let mut resources = Vec::new();
+    for (event, range) in Parser::new_ext(input, options).into_offset_iter() {
+        let (kind, link_type, dest_url) = match event {
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) => (ResourceKind::Image, link_type, dest_url),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            }) => (ResourceKind::Link, link_type, dest_url),
+            _ => continue,
+        };
+        if link_type == LinkType::Email || dest_url.is_empty() || dest_url.starts_with('#') {
+            continue;
+        }
+        let Ok(url) = base_url.join(&dest_url) else {
+            continue;
+        };
+        let location = match url.scheme() {
+            "data" => ResourceLocation::Embedded,
+            "file" => ResourceLocation::Local,
+            "http" | "https" => ResourceLocation::Remote,
+            _ => continue,
+        };
+        resources.push(Resource {
+            line: input[..range.start].matches('\n').count() + 1,
+            kind,
+            location,
+            url,
+        });
+    }
+    resources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark_mdcat::resources::NoopResourceHandler;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn find_missing_relative_links() {
+        let base_url = Url::from_directory_path(std::env::current_dir().unwrap()).unwrap();
+        let input = "See [the readme](README.md) and [the missing file](missing.md#section).\n\n\
+                     Also [an anchor](#top), [a mail](mailto:foo@example.com), <foo@example.com> and\n\
+                     [a website](https://example.com).\n\n\
+                     - [Another missing file](sample/missing.md)\n";
+        let links = broken_links(input, &base_url, &NoopResourceHandler, Options::empty());
+        assert_eq!(
+            links,
+            vec![
+                BrokenLink {
+                    line: 1,
+                    destination: "missing.md#section".to_string(),
+                    reason: "No such file or directory".to_string()
+                },
+                BrokenLink {
+                    line: 6,
+                    destination: "sample/missing.md".to_string(),
+                    reason: "No such file or directory".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn find_resources() {
+        let base_url = Url::parse("file:///work/docs/").unwrap();
+        let input = "![Logo](images/logo.png) and [the guide](../guide.md#usage)\n\n\
+                     ![Badge](https://example.com/badge.svg)\n\n\
+                     [an anchor](#top), <foo@example.com>, [FTP](ftp://example.com)\n\n\
+                     ![Dot](data:image/png;base64,iVBORw0KGgo=)\n";
+        let resources = resources(input, &base_url, Options::empty());
+        let lines = resources
+            .iter()
+            .map(|resource| format!("{}: {resource}", resource.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "1: image local /work/docs/images/logo.png",
+                "1: link local /work/guide.md",
+                "3: image remote https://example.com/badge.svg",
+                "7: image embedded data:image/png;base64,iVBORw0KGgo=",
+            ]
+        );
+        assert!(resources[1].location.is_allowed(ResourceAccess::LocalOnly));
+        assert!(!resources[1].location.is_allowed(ResourceAccess::Sandbox));
+        assert!(!resources[2].location.is_allowed(ResourceAccess::LocalOnly));
+        assert!(resources[2].location.is_allowed(ResourceAccess::Remote));
+        assert!(resources[3].location.is_allowed(ResourceAccess::Sandbox));
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// A construct in markdown source which CommonMark parses, but which is easy to get wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintProblem {
+    /// A tab in the indentation of a list item, which makes nesting depend on tab stops.
+    TabInList,
+    /// A list right after a paragraph, without a blank line in between.
+    NoBlankLineBeforeList,
+    /// A URL in text, which CommonMark does not turn into a link.
+    BareUrl(String),
+}
+
+impl Display for LintProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TabInList => write!(f, "tab in list item indentation"),
+            Self::NoBlankLineBeforeList => write!(f, "no blank line before list"),
+            Self::BareUrl(url) => write!(f, "bare URL {url}, use <{url}> instead"),
+        }
+    }
+}
+
+/// A warning about the markdown source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// The line of the problem in the markdown source, counting from 1.
+    pub line: usize,
+    /// What's wrong with the source.
+    pub problem: LintProblem,
+}
+
+/// Whether the indentation of `line`, including block quote markers, contains a tab.
+fn indentation_has_tab(line: &str) -> bool {
+    line.chars()
+        .take_while(|c| c.is_whitespace() || *c == '>')
+        .any(|c| c == '\t')
+}
+
+/// Whether the whitespace after the list marker at the start of `item` contains a tab.
+fn marker_has_tab(item: &str) -> bool {
+    let rest = item.trim_start_matches(|c: char| c.is_ascii_digit());
+    let rest = rest.strip_prefix(['-', '*', '+', '.', ')']).unwrap_or(rest);
+    indentation_has_tab(rest)
+}
+
+/// Find bare http and https URLs in `text`.
+///
+/// Return the byte offset and the URL of every bare URL, without trailing punctuation.
+fn bare_urls(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.match_indices("http").filter_map(|(index, _)| {
+        let rest = &text[index..];
+        let starts_word = text[..index]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        if !starts_word || !(rest.starts_with("http://") || rest.starts_with("https://")) {
+            return None;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let url = rest[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'', '"']);
+        Some((index, url))
+    })
+}
+
+/// Check markdown `input` parsed with `options` for constructs which are easy to get wrong.
+///
+/// Warn about tabs in the indentation of list items, lists directly after a paragraph without a
+/// blank line, and bare URLs in text outside of links and code.  Return warnings ordered by
+/// line.
+pub fn lint_warnings(input: &str, options: Options) -> Vec<LintWarning> {
+    let line_starts = std::iter::once(0)
+        .chain(input.match_indices('\n').map(|(index, _)| index + 1))
+        .collect::<Vec<_>>();
+    let line_of = |offset: usize| line_starts.partition_point(|start| *start <= offset);
+    let mut warnings = Vec::new();
+    let mut tab_lines = BTreeSet::new();
+    // Tabs in code are content, not indentation
+    let mut code_lines = BTreeSet::new();
+    let mut paragraph_end = None;
+    let mut item_start = false;
+    let mut item_paragraph = false;
+    let mut in_link = 0;
+    let mut in_code_block = false;
+    for (event, range) in Parser::new_ext(input, options).into_offset_iter() {
+        match &event {
+            Event::Start(Tag::Item) => {
+                let line_start = input[..range.start].rfind('\n').map_or(0, |i| i + 1);
+                let first_line = line_of(range.start);
+                for (n, line) in input[line_start..range.end].lines().enumerate() {
+                    if indentation_has_tab(line) {
+                        tab_lines.insert(first_line + n);
+                    }
+                }
+                // Tabs in the indentation may put the start of the item before its marker
+                let marker = input[range.clone()].trim_start();
+                if marker_has_tab(marker) {
+                    tab_lines.insert(line_of(range.end - marker.len()));
+                }
+            }
+            Event::Start(Tag::Paragraph) => item_paragraph = item_start,
+            // The paragraph ends after its last line, so any further line break is a blank line
+            Event::Start(Tag::List(_)) => match paragraph_end {
+                Some(end)
+                    if input
+                        .get(end..range.start)
+                        .is_some_and(|gap| !gap.contains('\n')) =>
+                {
+                    warnings.push(LintWarning {
+                        line: line_of(range.start),
+                        problem: LintProblem::NoBlankLineBeforeList,
+                    })
+                }
+                _ => {}
+            },
+            Event::Start(Tag::Link { .. } | Tag::Image { .. }) => in_link += 1,
+            Event::End(TagEnd::Link | TagEnd::Image) => in_link -= 1,
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(_) if in_code_block => {
+                code_lines.extend(line_of(range.start)..=line_of(range.end.saturating_sub(1)));
+            }
+            Event::Text(text) if in_link == 0 => {
+                // Locate URLs in the source, if the text is verbatim source
+                let verbatim = &input[range.clone()] == text.as_ref();
+                for (index, url) in bare_urls(text) {
+                    let offset = if verbatim {
+                        range.start + index
+                    } else {
+                        range.start
+                    };
+                    warnings.push(LintWarning {
+                        line: line_of(offset),
+                        problem: LintProblem::BareUrl(url.to_string()),
+                    });
+                }
+            }
+            _ => {}
+        }
+        // Sublists directly below the first paragraph of an item are fine
+        paragraph_end = match event {
+            Event::End(TagEnd::Paragraph) if !item_paragraph => Some(range.end),
+            _ => None,
+        };
+        item_start = matches!(event, Event::Start(Tag::Item));
+    }
+    warnings.extend(tab_lines.difference(&code_lines).map(|line| LintWarning {
+        line: *line,
+        problem: LintProblem::TabInList,
+    }));
+    warnings.sort_by_key(|warning| warning.line);
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    fn problems(input: &str) -> Vec<(usize, String)> {
+        lint_warnings(input, Options::empty())
+            .into_iter()
+            .map(|warning| (warning.line, warning.problem.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn list_without_blank_line() {
+        assert_eq!(
+            problems("Text\n- a\n- b\n\n> Quote\n> 1. one\n"),
+            vec![
+                (2, "no blank line before list".to_string()),
+                (6, "no blank line before list".to_string())
+            ]
+        );
+        assert_eq!(
+            problems("- a\n\n  Text\n  - b\n"),
+            vec![(4, "no blank line before list".to_string())]
+        );
+        assert_eq!(
+            problems("Text\n\n- a\n\n> Quote\n>\n> - a\n\n- b\n\n- c\n  - d\n"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn tabs_in_lists() {
+        assert_eq!(
+            problems("-\tfoo\n\n\tbar\n\n- a\n\t- b\n\n1. one\n"),
+            vec![
+                (1, "tab in list item indentation".to_string()),
+                (3, "tab in list item indentation".to_string()),
+                (6, "tab in list item indentation".to_string())
+            ]
+        );
+        assert_eq!(problems("Some\ttext\n\n- a\tb\n"), vec![]);
+        assert_eq!(
+            problems("- Makefile:\n\n  ```make\n  all:\n  \techo all\n  ```\n\n- Indented:\n\n        \tcode\n"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn bare_urls_outside_links_and_code() {
+        assert_eq!(
+            problems(
+                "See https://example.com/docs, and *this*\nhttp://example.com.\n\n\
+                 [https://example.com](https://example.com) <https://example.com>\n\n\
+                 `https://example.com`\n\n```\nhttps://example.com\n```\n\nnothttp://example.com\n"
+            ),
+            vec![
+                (
+                    1,
+                    "bare URL https://example.com/docs, use <https://example.com/docs> instead"
+                        .to_string()
+                ),
+                (
+                    2,
+                    "bare URL http://example.com, use <http://example.com> instead".to_string()
+                ),
+            ]
+        );
+    }
+}
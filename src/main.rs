@@ -9,28 +9,58 @@
 
 //! Show CommonMark documents on TTYs.
 
-use clap::{CommandFactory, Parser};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{ErrorKind, IsTerminal};
+use std::time::Instant;
+
+use anyhow::Context;
+use clap::{CommandFactory, Parser, ValueEnum};
 use clap_complete::generate;
-use mdcat::{create_resource_handler, process_file};
+use mdcat::{
+    create_resource_handler, effective_columns, expand_globs, process_file, process_file_separator,
+    ExpandedFilenames,
+};
+#[cfg(feature = "archive")]
+use pulldown_cmark_mdcat::resources::DispatchingResourceHandler;
 use pulldown_cmark_mdcat::terminal::{TerminalProgram, TerminalSize};
-use pulldown_cmark_mdcat::{Settings, Theme};
+use pulldown_cmark_mdcat::Settings;
 use syntect::parsing::SyntaxSet;
 use tracing::{event, Level};
-use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
-use mdcat::args::Args;
-use mdcat::output::Output;
+use mdcat::args::{
+    log_level, terminal_override, Args, ColourMode, DetectFormat, OutputFormat, ProgressFormat,
+    ThemeName,
+};
+use mdcat::config::{args_with_config, args_with_env};
+use mdcat::error::{ErrorClass, RenderTimeout, ResourceError};
+use mdcat::output::{parse_env_var, Output};
+use mdcat::progress::FileProgress;
+use mdcat::resources::TimeoutTrackingResourceHandler;
+use mdcat::stats::{format_total, DocumentStats};
+use mdcat::terminal::TerminalInfo;
 
 fn main() {
     // Initialize curl for remote resources
     curl::init();
 
+    // Options from the configuration file come first, then $MDCAT_OPTS, and explicit arguments
+    // last, so that each overrides the former.
+    let args = match args_with_env(std::env::args_os().collect()).and_then(args_with_config) {
+        Ok(args) => Args::parse_from(args).command,
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+            std::process::exit(ErrorClass::Usage.exit_code());
+        }
+    };
+
     // Setup tracing
     let filter = EnvFilter::builder()
         // Disable all logging by default, to avoid interfering with regular output at all cost.
-        // tracing is a debugging tool here so we expect it to be enabled explicitly.
-        .with_default_directive(LevelFilter::OFF.into())
+        // tracing is a debugging tool here so we expect it to be enabled explicitly, with
+        // --verbose or $MDCAT_LOG; the latter takes precedence.
+        .with_default_directive(log_level(args.verbose, args.quiet).into())
         .with_env_var("MDCAT_LOG")
         .from_env_lossy();
     tracing_subscriber::fmt::Subscriber::builder()
@@ -38,8 +68,6 @@ fn main() {
         .with_env_filter(filter)
         .with_writer(std::io::stderr)
         .init();
-
-    let args = Args::parse().command;
     event!(target: "mdcat::main", Level::TRACE, ?args, "mdcat arguments");
 
     if let Some(shell) = args.completions {
@@ -53,37 +81,205 @@ fn main() {
         std::process::exit(0);
     }
 
-    let terminal = if args.no_colour {
+    if args.list_themes {
+        for theme in ThemeName::value_variants() {
+            println!("{}", theme.to_possible_value().unwrap().get_name());
+        }
+        std::process::exit(0);
+    }
+
+    if args.list_languages {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut languages = syntax_set
+            .syntaxes()
+            .iter()
+            .map(|syntax| {
+                (
+                    syntax.name.to_lowercase(),
+                    syntax.file_extensions.join(", "),
+                )
+            })
+            .collect::<Vec<_>>();
+        languages.sort();
+        for (name, aliases) in languages {
+            if aliases.is_empty() {
+                println!("{name}");
+            } else {
+                println!("{name}: {aliases}");
+            }
+        }
+        std::process::exit(0);
+    }
+
+    // clap can't conflict with a single value, and an explicit --to terminal is what --literal
+    // does anyway
+    if args.literal && args.to == OutputFormat::Html {
+        Args::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "the argument '--literal' cannot be used with '--to html'",
+            )
+            .exit();
+    }
+
+    let terminal_override = match terminal_override(args.terminal, parse_env_var) {
+        Ok(terminal) => terminal,
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+            std::process::exit(ErrorClass::Usage.exit_code());
+        }
+    };
+
+    // See https://no-color.org
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let colour = args.colour_mode_with_no_color(no_color);
+    let terminal = if colour == ColourMode::Never || args.to == OutputFormat::Html {
+        // HTML needs no terminal features
         TerminalProgram::Dumb
-    } else if args.paginate() || args.ansi_only {
-        // A pager won't support any terminal-specific features
+    } else if let Some(terminal) = terminal_override {
+        // An explicit terminal beats all guesses
+        terminal
+    } else if args.writes_to_file() && !args.ansi_only && colour == ColourMode::Auto {
+        // A file is no terminal, so there's nothing to detect
+        TerminalProgram::Dumb
+    } else if args.writes_to_file() || args.paginate() || args.ansi_only {
+        // A pager or file won't support any terminal-specific features
         TerminalProgram::Ansi
     } else {
-        TerminalProgram::detect()
+        match TerminalProgram::detect() {
+            TerminalProgram::Dumb if colour == ColourMode::Always => TerminalProgram::Ansi,
+            terminal => terminal,
+        }
     };
 
     if args.detect_and_exit {
-        println!("Terminal: {terminal}");
+        match args.detect_format {
+            DetectFormat::Text => println!("Terminal: {terminal}"),
+            DetectFormat::Json => {
+                let mut info = TerminalInfo::detect(terminal, TerminalSize::detect());
+                info.hyperlinks &= !args.no_hyperlinks;
+                println!("{}", info.to_json());
+            }
+        }
     } else {
         // Enable Ansi color processing on Windows
         #[cfg(windows)]
         anstyle_query::windows::enable_ansi_colors();
 
-        let terminal_size = TerminalSize::detect().unwrap_or_default();
-        let terminal_size = if let Some(max_columns) = args.columns {
-            terminal_size.with_max_columns(max_columns)
+        // Query the terminal size again for every file, because the terminal may have been resized
+        let terminal_size = |output: &Output| {
+            TerminalSize::detect()
+                .unwrap_or_default()
+                .with_max_columns(effective_columns(args.columns, output))
+        };
+
+        #[cfg(feature = "archive")]
+        let archive = match args.archive.as_deref().map(mdcat::archive::Archive::open) {
+            Some(Ok(archive)) => Some(archive),
+            Some(Err(error)) => {
+                eprintln!("Error: {error:#}");
+                std::process::exit(ErrorClass::of(&error).exit_code());
+            }
+            None => None,
+        };
+        // Filenames name members of the archive, if any, so don't look for them on disk
+        #[cfg(feature = "archive")]
+        let no_glob = args.no_glob || archive.is_some();
+        #[cfg(not(feature = "archive"))]
+        let no_glob = args.no_glob;
+
+        let expanded = if no_glob {
+            ExpandedFilenames {
+                filenames: args.filenames.clone(),
+                unmatched: Vec::new(),
+            }
         } else {
-            terminal_size
+            expand_globs(&args.filenames)
         };
+        for pattern in &expanded.unmatched {
+            if args.fail_fast {
+                eprintln!("Error: {pattern}: No files match");
+                std::process::exit(ErrorClass::FileNotFound.exit_code());
+            }
+            if !args.quiet {
+                eprintln!("Warning: {pattern}: No files match");
+            }
+        }
 
-        let exit_code = match Output::new(args.paginate()) {
+        #[cfg(feature = "archive")]
+        let expanded = if archive.is_some() {
+            ExpandedFilenames {
+                filenames: expanded
+                    .filenames
+                    .iter()
+                    .map(|name| mdcat::archive::member_url(name).to_string())
+                    .collect(),
+                unmatched: expanded.unmatched,
+            }
+        } else {
+            expanded
+        };
+
+        // Process merged files as a single file
+        let (expanded, merged) = if args.merge {
+            let merged = ExpandedFilenames {
+                filenames: vec![mdcat::MERGED_NAME.to_string()],
+                unmatched: Vec::new(),
+            };
+            (merged, expanded.filenames)
+        } else {
+            (expanded, Vec::new())
+        };
+
+        #[cfg(feature = "clipboard")]
+        let expanded = if args.clipboard {
+            ExpandedFilenames {
+                filenames: vec![mdcat::CLIPBOARD_NAME.to_string()],
+                unmatched: Vec::new(),
+            }
+        } else {
+            expanded
+        };
+
+        let output = match &args.output {
+            Some(path) => Output::to_file(path),
+            None if args.paginate() && args.no_pager_if_fits => Ok(Output::buffered()),
+            // Pagers can't show images or marks, so only paginate automatically if the terminal
+            // has none of these anyway
+            None if args.auto_paginate()
+                && std::io::stdout().is_terminal()
+                && terminal.capabilities().image.is_none()
+                && terminal.capabilities().marks.is_none() =>
+            {
+                Ok(Output::buffered())
+            }
+            None => Output::new(args.paginate(), args.pager.as_deref(), args.quiet),
+        }
+        .and_then(|output| match &args.stats_output {
+            Some(path) if path.as_os_str() == "-" => {
+                Ok(output.with_stats_output(Box::new(std::io::stderr())))
+            }
+            Some(path) => File::create(path)
+                .with_context(|| format!("Failed to create stats output {}", path.display()))
+                .map(|file| output.with_stats_output(Box::new(file))),
+            None => Ok(output),
+        });
+        let exit_code = match output {
             Ok(mut output) => {
-                let settings = Settings {
-                    terminal_capabilities: terminal.capabilities(),
-                    terminal_size,
-                    syntax_set: &SyntaxSet::load_defaults_newlines(),
-                    theme: Theme::default(),
-                };
+                let mut capabilities = terminal.capabilities();
+                if args.no_hyperlinks {
+                    capabilities.links = None;
+                }
+                let syntax_set = SyntaxSet::load_defaults_newlines();
+                let mut settings = Settings::new(
+                    capabilities,
+                    terminal_size(&output),
+                    &syntax_set,
+                    args.colour_theme(),
+                )
+                .with_prose_width(args.prose_width)
+                .with_list_indent(args.list_indent)
+                .with_syntax_themes(args.syntax_themes());
                 event!(
                     target: "mdcat::main",
                     Level::TRACE,
@@ -92,22 +288,177 @@ fn main() {
                     "settings"
                 );
                 // TODO: Handle this error properly
-                let resource_handler = create_resource_handler(args.resource_access()).unwrap();
-                args.filenames
+                let resource_handler =
+                    create_resource_handler(args.resource_access(), &args.resource_options())
+                        .unwrap();
+                // Read members of the archive first
+                #[cfg(feature = "archive")]
+                let resource_handler = match archive {
+                    Some(archive) => DispatchingResourceHandler::new(vec![
+                        Box::new(mdcat::archive::ArchiveResourceHandler::new(
+                            archive,
+                            args.max_resource_size,
+                        )),
+                        Box::new(resource_handler),
+                    ]),
+                    None => resource_handler,
+                };
+                let resource_handler = TimeoutTrackingResourceHandler::new(resource_handler);
+                let process_options = args.process_options();
+                let mut total_stats = DocumentStats::default();
+                let mut stats_files = 0;
+                let mut output_paths = HashSet::new();
+                let exit_code = expanded
+                    .filenames
                     .iter()
-                    .try_fold(0, |code, filename| {
-                        process_file(filename, &settings, &resource_handler, &mut output, args.line_numbers, args.stats)
-                            .map(|_| code)
-                            .or_else(|error| {
-                                eprintln!("Error: {filename}: {error}");
-                                if args.fail_fast {
-                                    Err(error)
-                                } else {
-                                    Ok(1)
+                    .enumerate()
+                    .try_fold(0, |code, (index, filename)| {
+                        let start = Instant::now();
+                        // With an output directory write each file to its own output
+                        let (mut file_output, opened) = match args
+                            .output_dir
+                            .as_deref()
+                            .map(|directory| {
+                                Output::in_directory(
+                                    directory,
+                                    filename,
+                                    args.to,
+                                    &mut output_paths,
+                                )
+                            })
+                            .transpose()
+                        {
+                            Ok(file_output) => (file_output, Ok(())),
+                            Err(error) => (None, Err(error)),
+                        };
+                        let output = match file_output.as_mut() {
+                            Some(file_output) => file_output,
+                            None => &mut output,
+                        };
+                        settings.terminal_size = terminal_size(output);
+                        let bytes_before = output.bytes_written();
+                        // Separate each file from the previous one
+                        let separator = if 0 < index && !args.dry_run && args.output_dir.is_none() {
+                            args.file_separator.as_str()
+                        } else {
+                            ""
+                        };
+                        let result = opened
+                            .and_then(|_| {
+                                process_file_separator(
+                                    separator,
+                                    filename,
+                                    &settings,
+                                    &resource_handler,
+                                    output,
+                                    args.to,
+                                    args.strict_pipe,
+                                )
+                            })
+                            .and_then(|_| {
+                                #[cfg(feature = "clipboard")]
+                                if args.clipboard {
+                                    return mdcat::process_clipboard(
+                                        &settings,
+                                        &resource_handler,
+                                        output,
+                                        &process_options,
+                                    );
+                                }
+                                if args.merge {
+                                    return mdcat::process_merged(
+                                        &merged,
+                                        &settings,
+                                        &resource_handler,
+                                        output,
+                                        &process_options,
+                                    );
+                                }
+                                process_file(
+                                    filename,
+                                    &settings,
+                                    &resource_handler,
+                                    output,
+                                    &process_options,
+                                )
+                            })
+                            .map(|stats| {
+                                if let Some(stats) = stats {
+                                    total_stats.merge(&stats);
+                                    stats_files += 1;
                                 }
                             })
+                            .and_then(|_| {
+                                // Rendering skips resources which timed out, but we fail on these if
+                                // requested to fail fast.
+                                let timed_out = resource_handler.take_timed_out();
+                                match timed_out.first() {
+                                    Some(url) if args.fail_fast => {
+                                        let error = ResourceError::new(
+                                            url.clone(),
+                                            ErrorKind::TimedOut.into(),
+                                        );
+                                        Err(anyhow::Error::new(error)
+                                            .context(format!("Timed out reading resource {url}")))
+                                    }
+                                    _ => Ok(()),
+                                }
+                            });
+                        if let Some(ProgressFormat::Json) = args.progress {
+                            let progress = FileProgress::new(
+                                filename,
+                                &result,
+                                output.bytes_written() - bytes_before,
+                                start.elapsed(),
+                            );
+                            eprintln!("{}", progress.to_json_line());
+                        }
+                        result.map(|_| code).or_else(|error| {
+                            // A file which takes too long to render is only a warning, unless we
+                            // fail fast
+                            let timed_out = error.is::<RenderTimeout>() && !args.fail_fast;
+                            // Progress information already contains the error
+                            if timed_out {
+                                if args.progress != Some(ProgressFormat::Json) && !args.quiet {
+                                    eprintln!("Warning: {filename}: {error}");
+                                }
+                                return Ok(code);
+                            }
+                            if args.progress != Some(ProgressFormat::Json) {
+                                eprintln!("Error: {filename}: {error}");
+                            }
+                            if args.fail_fast {
+                                Err(error)
+                            } else if code == 0 {
+                                // Exit with the class of the first error
+                                Ok(ErrorClass::of(&error).exit_code())
+                            } else {
+                                Ok(code)
+                            }
+                        })
                     })
-                    .unwrap_or(1)
+                    .unwrap_or_else(|error| ErrorClass::of(&error).exit_code());
+                if let Some(stats_options) = process_options.stats.as_ref() {
+                    if 1 < stats_files {
+                        let total = format_total(&total_stats, stats_files, stats_options);
+                        if let Err(error) = writeln!(output.stats_writer(), "{total}") {
+                            eprintln!("Error: {error}");
+                        }
+                    }
+                }
+                let rows = TerminalSize::detect().unwrap_or_default().rows;
+                match output.finish(rows, args.pager.as_deref(), args.quiet) {
+                    Ok(()) => exit_code,
+                    Err(error) => {
+                        eprintln!("Error: {error:#}");
+                        // Exit with the class of the first error
+                        if exit_code == 0 {
+                            ErrorClass::of(&error).exit_code()
+                        } else {
+                            exit_code
+                        }
+                    }
+                }
             }
             Err(error) => {
                 eprintln!("Error: {error:#}");
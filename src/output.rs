@@ -4,49 +4,125 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::io::Write;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::*;
 
 use anyhow::{bail, Context, Result};
 use tracing::{event, Level};
 
-/// The output for mdcat
-pub enum Output {
+use crate::args::OutputFormat;
+
+/// Where output goes.
+enum Target {
     /// Standard output
     Stdout(std::io::Stdout),
     /// A pager
     Pager(Child),
+    /// A file
+    File(File),
+    /// A buffer in memory, to decide whether to paginate later
+    Buffer(Vec<u8>),
+}
+
+/// The output of mdcat.
+///
+/// Keeps track of the number of bytes written.  Statistics go to the same output, unless a
+/// separate output for statistics is set.
+pub struct Output {
+    target: Target,
+    bytes_written: u64,
+    stats: Option<Box<dyn Write>>,
 }
 
 impl Drop for Output {
     /// Drop the output.
     ///
-    /// When outputting to a pager wait for the pager to exit.
+    /// When outputting to a pager wait for the pager to exit.  Write any remaining buffered
+    /// output to standard output.
     fn drop(&mut self) {
-        if let Output::Pager(ref mut child) = *self {
-            let _ = child.wait();
+        match self.target {
+            Target::Pager(ref mut child) => {
+                let _ = child.wait();
+            }
+            Target::Buffer(ref buffer) if !buffer.is_empty() => {
+                let _ = std::io::stdout().write_all(buffer);
+            }
+            _ => {}
         }
     }
 }
 
-fn parse_env_var(name: &str) -> Result<Option<Vec<String>>> {
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = match &mut self.target {
+            Target::Stdout(handle) => handle.write(buf),
+            Target::Pager(child) => child.stdin.as_mut().unwrap().write(buf),
+            Target::File(file) => file.write(buf),
+            Target::Buffer(buffer) => buffer.write(buf),
+        }?;
+        self.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.target {
+            Target::Stdout(handle) => handle.flush(),
+            Target::Pager(child) => child.stdin.as_mut().unwrap().flush(),
+            Target::File(file) => file.flush(),
+            Target::Buffer(_) => Ok(()),
+        }
+    }
+}
+
+/// Whether `rendered` output fits on a terminal with `rows` lines.
+///
+/// Output fits if it has less lines than the terminal, to leave a line for the prompt.
+pub fn fits_on_screen(rendered: &[u8], rows: u16) -> bool {
+    let mut lines = rendered.iter().filter(|b| **b == b'\n').count();
+    if rendered.last().is_some_and(|b| *b != b'\n') {
+        // Count an unterminated last line
+        lines += 1;
+    }
+    lines < usize::from(rows)
+}
+
+/// Read the environment variable `name`.
+///
+/// Return `None` if the variable is not set, and fail if its value is not unicode.
+pub fn parse_env_var(name: &str) -> Result<Option<String>> {
     use std::env::VarError;
     match std::env::var(name) {
-        Ok(value) => shell_words::split(&value)
-            .with_context(|| format!("Failed to parse value {} of {}", &value, &name))
-            .map(Some),
+        Ok(value) => Ok(Some(value)),
         Err(VarError::NotPresent) => Ok(None),
         Err(VarError::NotUnicode(value)) => bail!("Value of {} not unicode: {:?}", name, value),
     }
 }
 
-fn pager_from_env() -> Result<Vec<String>> {
+/// Resolve the pager command.
+///
+/// Use the `flag` given on the command line if any, and otherwise look up `$MDCAT_PAGER` and
+/// `$PAGER` in this order with `lookup`.  Default to `less -r` if neither is set.  Split the
+/// command into words according to shell quoting rules.
+pub fn resolve_pager<F>(flag: Option<&str>, lookup: F) -> Result<Vec<String>>
+where
+    F: Fn(&str) -> Result<Option<String>>,
+{
+    if let Some(command) = flag {
+        event!(Level::INFO, "Using {:?} from --pager as pager", command);
+        return shell_words::split(command)
+            .with_context(|| format!("Failed to parse pager command {command}"));
+    }
     for envvar in ["MDCAT_PAGER", "PAGER"] {
         event!(Level::TRACE, envvar, "looking for pager in environment");
-        match parse_env_var(envvar) {
+        match lookup(envvar) {
             // Continue looking
             Ok(None) => {}
-            Ok(Some(command)) => {
+            Ok(Some(value)) => {
+                let command = shell_words::split(&value)
+                    .with_context(|| format!("Failed to parse value {} of {}", &value, &envvar))?;
                 event!(Level::INFO, envvar, "Using {:?} as pager", command);
                 return Ok(command);
             }
@@ -61,33 +137,148 @@ fn pager_from_env() -> Result<Vec<String>> {
 }
 
 impl Output {
+    fn with_target(target: Target) -> Output {
+        Output {
+            target,
+            bytes_written: 0,
+            stats: None,
+        }
+    }
+
+    /// Write statistics to `writer` instead of this output.
+    pub fn with_stats_output(mut self, writer: Box<dyn Write>) -> Output {
+        self.stats = Some(writer);
+        self
+    }
+
+    /// Whether statistics go to a separate output.
+    pub fn has_stats_output(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    /// Get the writer to write statistics to.
+    ///
+    /// This is the separate output for statistics if any, and this output otherwise.
+    pub fn stats_writer(&mut self) -> &mut dyn Write {
+        match self.stats {
+            Some(ref mut writer) => writer,
+            None => self,
+        }
+    }
+
     /// Get the writer to write to the output.
     ///
-    /// When outputting to a pager returns the stdin handle to the pager.
+    /// When outputting to a pager this writes to the stdin handle of the pager.
     pub fn writer(&mut self) -> &mut dyn Write {
-        match self {
-            Output::Stdout(handle) => handle,
-            Output::Pager(child) => child.stdin.as_mut().unwrap(),
+        self
+    }
+
+    /// Whether this output ends up on a terminal.
+    ///
+    /// A pager shows output on the terminal, but standard output may be redirected, and a file
+    /// is never a terminal.
+    pub fn is_terminal(&self) -> bool {
+        match &self.target {
+            Target::Stdout(handle) => handle.is_terminal(),
+            Target::Pager(_) => true,
+            Target::File(_) => false,
+            // Buffered output goes to standard output or a pager eventually
+            Target::Buffer(_) => std::io::stdout().is_terminal(),
         }
     }
 
+    /// Get the total number of bytes written to this output so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Create a new output writing to the file at `path`.
+    ///
+    /// Create the file if it does not exist, and truncate it otherwise.
+    pub fn to_file<P: AsRef<Path>>(path: P) -> Result<Output> {
+        let path = path.as_ref();
+        File::create(path)
+            .with_context(|| format!("Failed to create output file {}", path.display()))
+            .map(|file| Output::with_target(Target::File(file)))
+    }
+
+    /// Create a new output for the input `filename` in `directory`.
+    ///
+    /// Create `directory` if it does not exist, and name the file after the input with the
+    /// extension of `format`, see [`crate::output_path`].  Fail if a previous input already wrote
+    /// to the same file, as recorded in `written`, instead of overwriting its output.
+    pub fn in_directory(
+        directory: &Path,
+        filename: &str,
+        format: OutputFormat,
+        written: &mut HashSet<PathBuf>,
+    ) -> Result<Output> {
+        let path = crate::output_path(directory, filename, format);
+        if !written.insert(path.clone()) {
+            bail!("Output file {} already written", path.display());
+        }
+        std::fs::create_dir_all(directory).with_context(|| {
+            format!("Failed to create output directory {}", directory.display())
+        })?;
+        Output::to_file(path)
+    }
+
+    /// Create a new output which buffers everything in memory.
+    ///
+    /// Use [`Output::finish`] to paginate the buffered output only if it does not fit on the
+    /// screen.
+    pub fn buffered() -> Output {
+        Output::with_target(Target::Buffer(Vec::new()))
+    }
+
+    /// Finish this output.
+    ///
+    /// If this output is buffered, write all buffered output to standard output if it fits on a
+    /// terminal with `rows` lines, and to a `pager` otherwise, as in [`Output::new`].  Do nothing
+    /// for other outputs.
+    pub fn finish(mut self, rows: u16, pager: Option<&str>, quiet: bool) -> Result<()> {
+        let Target::Buffer(buffer) = &mut self.target else {
+            return Ok(());
+        };
+        let buffer = std::mem::take(buffer);
+        let paginate = !fits_on_screen(&buffer, rows);
+        event!(
+            Level::DEBUG,
+            paginate,
+            rows,
+            "Writing {} bytes of buffered output",
+            buffer.len()
+        );
+        let mut output = Output::new(paginate, pager, quiet)?;
+        output
+            .write_all(&buffer)
+            .and_then(|_| output.flush())
+            .or_else(|error| match error.kind() {
+                // The user closed the pager early
+                std::io::ErrorKind::BrokenPipe => Ok(()),
+                _ => Err(error),
+            })?;
+        Ok(())
+    }
+
     /// Create a new output.
     ///
     /// If `try_paginate` is `true` try to output to a pager.  If stdout is not a TTY, that is, if
     /// there's no terminal to paginate on, print to stdout nonetheless.
     ///
-    /// Take the pager command from `$MDCAT_PAGER` or `$PAGER`, and default to `less -R` if both are
-    /// unset.  If any of the variables is empty use stdout (assuming that the user
-    /// wanted to disabled paging explicitly).
-    pub fn new(try_paginate: bool) -> Result<Output> {
+    /// Take the pager command from `pager`, or from `$MDCAT_PAGER` or `$PAGER`, and default to
+    /// `less -r`; see [`resolve_pager`].  If the command is empty use stdout (assuming that the
+    /// user wanted to disabled paging explicitly).  If the pager fails to start, print a warning
+    /// to stderr unless `quiet` is `true`, and use stdout as well.
+    pub fn new(try_paginate: bool, pager: Option<&str>, quiet: bool) -> Result<Output> {
         if try_paginate {
-            match pager_from_env()?.split_first() {
+            match resolve_pager(pager, parse_env_var)?.split_first() {
                 None => {
                     event!(
                         Level::WARN,
                         "Empty pager command, falling back to standard output"
                     );
-                    Ok(Output::Stdout(std::io::stdout()))
+                    Ok(Output::with_target(Target::Stdout(std::io::stdout())))
                 }
                 Some((command, args)) => {
                     event!(
@@ -96,18 +287,98 @@ impl Output {
                         command,
                         args
                     );
-                    Command::new(command)
+                    match Command::new(command)
                         .args(args)
                         .stdin(Stdio::piped())
                         .spawn()
-                        .with_context(|| {
-                            format!("Failed to spawn pager {command} with args {args:?}")
-                        })
-                        .map(Output::Pager)
+                    {
+                        Ok(child) => Ok(Output::with_target(Target::Pager(child))),
+                        Err(error) => {
+                            if !quiet {
+                                eprintln!(
+                                    "Warning: Failed to spawn pager {command}: {error}; printing to standard output"
+                                );
+                            }
+                            Ok(Output::with_target(Target::Stdout(std::io::stdout())))
+                        }
+                    }
                 }
             }
         } else {
-            Ok(Output::Stdout(std::io::stdout()))
+            Ok(Output::with_target(Target::Stdout(std::io::stdout())))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{fits_on_screen, resolve_pager, Output};
+    use anyhow::{anyhow, Result};
+    use std::io::IsTerminal;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Result<Option<String>> + 'a {
+        move |name| {
+            Ok(vars
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string()))
+        }
+    }
+
+    #[test]
+    fn pager_precedence() {
+        let all = [("MDCAT_PAGER", "most -s"), ("PAGER", "more")];
+        assert_eq!(
+            resolve_pager(Some("bat --paging 'always'"), env(&all)).unwrap(),
+            ["bat", "--paging", "always"]
+        );
+        assert_eq!(resolve_pager(None, env(&all)).unwrap(), ["most", "-s"]);
+        assert_eq!(
+            resolve_pager(None, env(&[("PAGER", "more")])).unwrap(),
+            ["more"]
+        );
+        assert_eq!(resolve_pager(None, env(&[])).unwrap(), ["less", "-r"]);
+    }
+
+    #[test]
+    fn empty_or_invalid_pager() {
+        assert!(resolve_pager(Some(""), env(&[("PAGER", "more")]))
+            .unwrap()
+            .is_empty());
+        assert!(
+            resolve_pager(None, env(&[("MDCAT_PAGER", ""), ("PAGER", "more")]))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(resolve_pager(Some("less 'unterminated"), env(&[])).is_err());
+        assert!(resolve_pager(None, |_| Err(anyhow!("not unicode"))).is_err());
+    }
+
+    #[test]
+    fn fits_on_screen_with_rows() {
+        assert!(fits_on_screen(b"", 24));
+        assert!(fits_on_screen(b"one\ntwo\n", 3));
+        assert!(!fits_on_screen(b"one\ntwo\nthree\n", 3));
+        assert!(!fits_on_screen(b"one\ntwo\nthree", 3));
+        assert!(fits_on_screen("line\n".repeat(23).as_bytes(), 24));
+        assert!(!fits_on_screen("line\n".repeat(24).as_bytes(), 24));
+    }
+
+    #[test]
+    fn auto_paginate_by_height() {
+        let rendered = "line\n".repeat(40);
+        // Paginate only if the output has at least as many lines as the terminal
+        assert!(!fits_on_screen(rendered.as_bytes(), 24));
+        assert!(!fits_on_screen(rendered.as_bytes(), 40));
+        assert!(fits_on_screen(rendered.as_bytes(), 41));
+        assert!(fits_on_screen(rendered.as_bytes(), 80));
+    }
+
+    #[test]
+    fn buffered_output_is_terminal_like_stdout() {
+        assert_eq!(
+            Output::buffered().is_terminal(),
+            std::io::stdout().is_terminal()
+        );
+    }
+}
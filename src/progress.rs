@@ -0,0 +1,195 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::io::{Stderr, Write};
+use std::ops::Range;
+use std::time::Duration;
+
+use pulldown_cmark::Event;
+use serde::Serialize;
+
+/// Whether processing a file succeeded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    /// mdcat processed the file successfully.
+    Ok,
+    /// mdcat failed to process the file.
+    Error,
+}
+
+/// Progress information about a single processed file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileProgress<'a> {
+    /// The name of the file, as given on the command line.
+    pub file: &'a str,
+    /// Whether processing succeeded.
+    pub status: FileStatus,
+    /// The number of bytes written to the output for this file, including the file separator.
+    pub bytes: u64,
+    /// The time it took to process the file, in milliseconds.
+    pub duration_ms: u64,
+    /// The error message, if processing failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl<'a> FileProgress<'a> {
+    /// Create progress information for `file`.
+    ///
+    /// `result` tells whether processing succeeded.
+    pub fn new<T>(
+        file: &'a str,
+        result: &anyhow::Result<T>,
+        bytes: u64,
+        duration: Duration,
+    ) -> Self {
+        let (status, error) = match result {
+            Ok(_) => (FileStatus::Ok, None),
+            Err(error) => (FileStatus::Error, Some(format!("{error:#}"))),
+        };
+        Self {
+            file,
+            status,
+            bytes,
+            duration_ms: duration.as_millis().try_into().unwrap_or(u64::MAX),
+            error,
+        }
+    }
+
+    /// Format this progress information as a single line of JSON, without a trailing newline.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("Progress information must serialize to JSON")
+    }
+}
+
+/// Only show a progress bar for inputs of at least this many bytes.
+pub const PROGRESS_BAR_MIN_BYTES: usize = 1 << 20;
+
+/// The width of the progress bar, in columns.
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+/// Compute how many percent of `total` bytes the parser consumed at `offset`.
+///
+/// Clamp the result to 100, and consider empty inputs complete.
+pub fn percentage(offset: usize, total: usize) -> u8 {
+    (offset.min(total) * 100)
+        .checked_div(total)
+        .map_or(100, |percentage| percentage as u8)
+}
+
+/// Show a progress bar for parsing `events` from an input of `total` bytes.
+///
+/// Take the progress from the byte offsets of `events`, and draw the progress bar on standard
+/// error.  Clear the progress bar when all events are consumed.
+pub fn with_progress_bar<'a, I>(events: I, total: usize) -> ProgressBar<I>
+where
+    I: Iterator<Item = (Event<'a>, Range<usize>)>,
+{
+    ProgressBar {
+        events,
+        total,
+        shown: None,
+        stderr: std::io::stderr(),
+    }
+}
+
+/// An iterator over events which draws a progress bar on standard error.
+///
+/// See [`with_progress_bar`].
+pub struct ProgressBar<I> {
+    events: I,
+    total: usize,
+    shown: Option<u8>,
+    stderr: Stderr,
+}
+
+impl<I> ProgressBar<I> {
+    fn draw(&mut self, percentage: u8) {
+        if self.shown == Some(percentage) {
+            return;
+        }
+        self.shown = Some(percentage);
+        let filled = PROGRESS_BAR_WIDTH * usize::from(percentage) / 100;
+        // Ignore errors, because the progress bar is purely informational
+        let _ = write!(
+            self.stderr,
+            "\r[{}{}] {percentage:>3}%",
+            "#".repeat(filled),
+            " ".repeat(PROGRESS_BAR_WIDTH - filled)
+        )
+        .and_then(|_| self.stderr.flush());
+    }
+
+    fn clear(&mut self) {
+        if self.shown.take().is_some() {
+            let _ = write!(self.stderr, "\r\x1b[2K").and_then(|_| self.stderr.flush());
+        }
+    }
+}
+
+impl<'a, I> Iterator for ProgressBar<I>
+where
+    I: Iterator<Item = (Event<'a>, Range<usize>)>,
+{
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.events.next() {
+            Some((event, range)) => {
+                self.draw(percentage(range.end, self.total));
+                Some(event)
+            }
+            None => {
+                self.clear();
+                None
+            }
+        }
+    }
+}
+
+impl<I> Drop for ProgressBar<I> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn percentage_of_offsets() {
+        assert_eq!(percentage(0, 200), 0);
+        assert_eq!(percentage(1, 200), 0);
+        assert_eq!(percentage(100, 200), 50);
+        assert_eq!(percentage(199, 200), 99);
+        assert_eq!(percentage(200, 200), 100);
+        assert_eq!(percentage(300, 200), 100);
+        assert_eq!(percentage(0, 0), 100);
+    }
+
+    #[test]
+    fn json_lines() {
+        let ok = FileProgress::new("a.md", &Ok(()), 42, Duration::from_millis(12));
+        assert_eq!(
+            ok.to_json_line(),
+            r#"{"file":"a.md","status":"ok","bytes":42,"duration_ms":12}"#
+        );
+        let error = FileProgress::new::<()>(
+            "b.md",
+            &Err(anyhow!("No such file")),
+            0,
+            Duration::from_millis(1),
+        );
+        assert_eq!(
+            error.to_json_line(),
+            r#"{"file":"b.md","status":"error","bytes":0,"duration_ms":1,"error":"No such file"}"#
+        );
+    }
+}
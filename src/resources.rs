@@ -4,14 +4,21 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{cell::RefCell, time::Duration};
 
-use curl::easy::{Easy2, Handler, WriteError};
+use curl::easy::{Easy2, Handler, List, WriteError};
 use mime::Mime;
 use pulldown_cmark_mdcat::{
     resources::{filter_schemes, MimeData},
     ResourceUrlHandler,
 };
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::{event, instrument, Level};
 use url::Url;
 
@@ -20,6 +27,7 @@ use url::Url;
 pub struct CollectBuffer {
     read_limit: u64,
     buffer: Vec<u8>,
+    limit_exceeded: bool,
 }
 
 impl Handler for CollectBuffer {
@@ -27,6 +35,7 @@ impl Handler for CollectBuffer {
         if self.read_limit < (self.buffer.len() + data.len()).try_into().unwrap() {
             // Do not handle data and tell curl that we didn't handle it;
             // this will make curl fail with a write error
+            self.limit_exceeded = true;
             Ok(0)
         } else {
             self.buffer.extend_from_slice(data);
@@ -35,9 +44,103 @@ impl Handler for CollectBuffer {
     }
 }
 
+/// Options for [`CurlResourceHandler`].
+#[derive(Debug, Clone)]
+pub struct CurlOptions {
+    /// Limit the time to connect, as well as the time for the entire transfer.
+    pub timeout: Duration,
+    /// The proxy to use for all requests.
+    ///
+    /// If `None` curl takes proxies from the standard environment variables, see `curl(1)`.
+    pub proxy: Option<String>,
+    /// Additional headers for all requests, each in the form `Name: Value`.
+    pub headers: Vec<String>,
+    /// Fixed addresses for hosts and ports, each in the form `HOST:PORT:ADDR`, like curl's
+    /// `--resolve`.
+    pub resolve: Vec<String>,
+    /// The user agent for all requests, instead of the default user agent of mdcat.
+    pub user_agent: Option<String>,
+    /// Retry a request this many times if it times out or the server responds with a 5xx status.
+    pub retries: u32,
+}
+
+impl Default for CurlOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            proxy: None,
+            headers: Vec::new(),
+            resolve: Vec::new(),
+            user_agent: None,
+            retries: 0,
+        }
+    }
+}
+
+/// The maximum number of concurrent requests when prefetching resources.
+const PREFETCH_THREADS: usize = 4;
+
+/// How long to wait before the first retry of a failed request; each further retry waits twice
+/// as long as the previous one.
+const RETRY_INITIAL_DELAY: Duration = Duration::from_millis(250);
+
+/// Everything needed to create a new curl handle.
+#[derive(Debug, Clone)]
+struct CurlConfig {
+    read_limit: u64,
+    useragent: String,
+    options: CurlOptions,
+}
+
+impl CurlConfig {
+    fn create_easy(&self) -> std::io::Result<Easy2<CollectBuffer>> {
+        let mut easy = Easy2::new(CollectBuffer {
+            buffer: Vec::new(),
+            read_limit: self.read_limit,
+            limit_exceeded: false,
+        });
+        easy.timeout(self.options.timeout)?;
+        easy.connect_timeout(self.options.timeout)?;
+        if let Some(proxy) = &self.options.proxy {
+            // An explicit proxy takes precedence over the environment
+            easy.proxy(proxy)?;
+        }
+        if !self.options.headers.is_empty() {
+            let mut headers = List::new();
+            for header in &self.options.headers {
+                headers.append(header)?;
+            }
+            easy.http_headers(headers)?;
+        }
+        if !self.options.resolve.is_empty() {
+            let mut resolve = List::new();
+            for entry in &self.options.resolve {
+                resolve.append(entry)?;
+            }
+            easy.resolve(resolve)?;
+        }
+        easy.follow_location(true)?;
+        easy.fail_on_error(true)?;
+        easy.tcp_nodelay(true)?;
+        easy.useragent(&self.useragent)?;
+        Ok(easy)
+    }
+}
+
+/// The result of prefetching a resource.
+///
+/// IO errors are not `Clone`, so keep only their kind and message.
+type PrefetchResult = Result<MimeData, (ErrorKind, String)>;
+
 /// A [`curl`]-based resource handler for [`pulldown-cmark-mdcat`].
 pub struct CurlResourceHandler {
     easy: RefCell<Easy2<CollectBuffer>>,
+    /// The configuration to create additional handles for prefetching, if known.
+    config: Option<CurlConfig>,
+    /// Resources read ahead of time.
+    prefetched: RefCell<HashMap<Url, PrefetchResult>>,
+    /// A separate handle for HEAD requests to check resources, created on first use.
+    head: RefCell<Option<Easy2<CollectBuffer>>>,
 }
 
 impl CurlResourceHandler {
@@ -45,43 +148,147 @@ impl CurlResourceHandler {
     ///
     /// `read_limit` is the maximum amount of data to be read from a resource.
     /// `useragent` is the value of the user agent header.
-    pub fn create(read_limit: u64, useragent: &str) -> std::io::Result<Self> {
-        let mut easy = Easy2::new(CollectBuffer {
-            buffer: Vec::new(),
+    /// `options` configures timeouts, proxies and headers.
+    pub fn create(
+        read_limit: u64,
+        useragent: &str,
+        options: &CurlOptions,
+    ) -> std::io::Result<Self> {
+        let config = CurlConfig {
             read_limit,
-        });
-        // Use somewhat aggressive timeouts to avoid blocking rendering for long; we have graceful
-        // fallbacks since we have to support terminals without image capabilities anyways.
-
-        easy.timeout(Duration::from_secs(1))?;
-        easy.connect_timeout(Duration::from_secs(1))?;
-        easy.follow_location(true)?;
-        easy.fail_on_error(true)?;
-        easy.tcp_nodelay(true)?;
-        easy.useragent(useragent)?;
-        Ok(Self::new(easy))
+            useragent: useragent.to_string(),
+            options: options.clone(),
+        };
+        let mut handler = Self::new(config.create_easy()?);
+        handler.config = Some(config);
+        Ok(handler)
     }
 
     /// Create a new resource handler.
+    ///
+    /// The handler does not know how to create further handles, and thus does not support
+    /// prefetching.
     pub fn new(easy: Easy2<CollectBuffer>) -> Self {
         Self {
             easy: RefCell::new(easy),
+            config: None,
+            prefetched: RefCell::new(HashMap::new()),
+            head: RefCell::new(None),
         }
     }
-}
 
-impl ResourceUrlHandler for CurlResourceHandler {
-    #[instrument(level = "debug", skip(self), fields(url = %url))]
-    fn read_resource(
-        &self,
+    /// The number of times to retry a failed request.
+    fn retries(&self) -> u32 {
+        self.config
+            .as_ref()
+            .map_or(0, |config| config.options.retries)
+    }
+
+    /// Check `url` with a HEAD request.
+    ///
+    /// Fall back to a GET request for the first byte only if the server does not support HEAD
+    /// requests.
+    fn check_with(
+        config: &CurlConfig,
+        head: &mut Option<Easy2<CollectBuffer>>,
         url: &Url,
-    ) -> std::io::Result<pulldown_cmark_mdcat::resources::MimeData> {
+    ) -> std::io::Result<()> {
+        let easy = match head {
+            Some(easy) => easy,
+            None => {
+                let mut easy = config.create_easy()?;
+                easy.nobody(true)?;
+                head.insert(easy)
+            }
+        };
+        match Self::read_with(easy, url, config.options.retries) {
+            Err(error)
+                if easy
+                    .response_code()
+                    .is_ok_and(|code| code == 405 || code == 501) =>
+            {
+                event!(Level::DEBUG, %url, %error, "HEAD not supported, requesting first byte");
+                let mut easy = config.create_easy()?;
+                easy.range("0-0")?;
+                Self::read_with(&mut easy, url, config.options.retries).map(|_| ())
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Whether `error` from reading a resource with `easy` is transient.
+    ///
+    /// Consider timeouts and 5xx responses transient, but no other errors, in particular no 4xx
+    /// responses.
+    fn is_transient(easy: &mut Easy2<CollectBuffer>, error: &std::io::Error) -> bool {
+        if error.kind() == ErrorKind::TimedOut {
+            return true;
+        }
+        let http_error = error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<curl::Error>())
+            .is_some_and(curl::Error::is_http_returned_error);
+        http_error
+            && easy
+                .response_code()
+                .is_ok_and(|code| (500..600).contains(&code))
+    }
+
+    /// Read `url` with the given curl handle.
+    ///
+    /// Retry up to `retries` times with exponential backoff if reading fails with a transient
+    /// error.
+    fn read_with(
+        easy: &mut Easy2<CollectBuffer>,
+        url: &Url,
+        retries: u32,
+    ) -> std::io::Result<MimeData> {
+        let mut delay = RETRY_INITIAL_DELAY;
+        for attempt in 1..=retries {
+            match Self::read_once(easy, url) {
+                Err(error) if Self::is_transient(easy, &error) => {
+                    event!(
+                        Level::DEBUG,
+                        %url,
+                        %error,
+                        "Retrying in {delay:?}, attempt {attempt} of {retries}"
+                    );
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+        Self::read_once(easy, url)
+    }
+
+    /// Read `url` with the given curl handle, once.
+    fn read_once(easy: &mut Easy2<CollectBuffer>, url: &Url) -> std::io::Result<MimeData> {
         // See https://curl.se/docs/url-syntax.html for all schemas curl supports
         // We omit the more exotic ones :)
         filter_schemes(&["http", "https", "ftp", "ftps", "smb"], url).and_then(|url| {
-            let mut easy = self.easy.borrow_mut();
             easy.url(url.as_str())?;
-            easy.perform()?;
+            // Discard any partial data of a previous failed request
+            easy.get_mut().buffer.clear();
+            easy.get_mut().limit_exceeded = false;
+            easy.perform().map_err(|error| {
+                if error.is_operation_timedout() {
+                    std::io::Error::new(ErrorKind::TimedOut, error)
+                } else if easy.get_ref().limit_exceeded {
+                    let read_limit = easy.get_ref().read_limit;
+                    event!(
+                        Level::WARN,
+                        %url,
+                        "Skipping resource which exceeds {read_limit} bytes"
+                    );
+                    std::io::Error::new(
+                        ErrorKind::FileTooLarge,
+                        format!("Contents of {url} exceeded {read_limit} bytes"),
+                    )
+                } else {
+                    error.into()
+                }
+            })?;
 
             let mime_type = easy.content_type()?.and_then(|content_type| {
                 event!(
@@ -98,3 +305,313 @@ impl ResourceUrlHandler for CurlResourceHandler {
         })
     }
 }
+
+impl ResourceUrlHandler for CurlResourceHandler {
+    #[instrument(level = "debug", skip(self), fields(url = %url))]
+    fn read_resource(
+        &self,
+        url: &Url,
+    ) -> std::io::Result<pulldown_cmark_mdcat::resources::MimeData> {
+        if let Some(result) = self.prefetched.borrow().get(url) {
+            event!(Level::DEBUG, %url, "Using prefetched resource");
+            return result
+                .clone()
+                .map_err(|(kind, message)| std::io::Error::new(kind, message));
+        }
+        Self::read_with(&mut self.easy.borrow_mut(), url, self.retries())
+    }
+
+    /// Check `url` with a HEAD request instead of reading it.
+    ///
+    /// Use a prefetched result if any, and read the entire resource if the handler does not know
+    /// how to create further handles.
+    #[instrument(level = "debug", skip(self), fields(url = %url))]
+    fn check_resource(&self, url: &Url) -> std::io::Result<()> {
+        match (self.prefetched.borrow().get(url), &self.config) {
+            (Some(result), _) => result
+                .as_ref()
+                .map(|_| ())
+                .map_err(|(kind, message)| std::io::Error::new(*kind, message.clone())),
+            (None, Some(config)) => filter_schemes(&["http", "https", "ftp", "ftps", "smb"], url)
+                .and_then(|url| Self::check_with(config, &mut self.head.borrow_mut(), url)),
+            (None, None) => self.read_resource(url).map(|_| ()),
+        }
+    }
+
+    /// Read all `urls` concurrently, with a bounded number of threads.
+    ///
+    /// Each thread uses its own curl handle with the same configuration as this handler.
+    #[instrument(level = "debug", skip_all)]
+    fn prefetch(&self, urls: &[Url]) {
+        let Some(config) = &self.config else {
+            return;
+        };
+        let urls = urls
+            .iter()
+            .filter(|url| filter_schemes(&["http", "https", "ftp", "ftps", "smb"], url).is_ok())
+            .filter(|url| !self.prefetched.borrow().contains_key(url))
+            .collect::<Vec<_>>();
+        let next = AtomicUsize::new(0);
+        let results = std::thread::scope(|scope| {
+            let workers = (0..PREFETCH_THREADS.min(urls.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        let mut easy = match config.create_easy() {
+                            Ok(easy) => easy,
+                            Err(error) => {
+                                event!(Level::WARN, %error, "Failed to create curl handle");
+                                return results;
+                            }
+                        };
+                        while let Some(url) = urls.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            let result = Self::read_with(&mut easy, url, config.options.retries)
+                                .map_err(|error| (error.kind(), error.to_string()));
+                            results.push(((*url).clone(), result));
+                        }
+                        results
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_default())
+                .collect::<Vec<_>>()
+        });
+        event!(Level::DEBUG, "Prefetched {} resources", results.len());
+        self.prefetched.borrow_mut().extend(results);
+    }
+}
+
+/// A resource handler which remembers all resources which timed out.
+///
+/// Rendering falls back gracefully if it fails to read a resource, e.g. an image; this handler
+/// keeps track of timeouts nonetheless to let mdcat fail if requested.
+pub struct TimeoutTrackingResourceHandler<H> {
+    inner: H,
+    timed_out: RefCell<Vec<Url>>,
+}
+
+impl<H: ResourceUrlHandler> TimeoutTrackingResourceHandler<H> {
+    /// Track timeouts of the `inner` handler.
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            timed_out: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Take all URLs which timed out since the last call.
+    pub fn take_timed_out(&self) -> Vec<Url> {
+        self.timed_out.take()
+    }
+}
+
+impl<H: ResourceUrlHandler> ResourceUrlHandler for TimeoutTrackingResourceHandler<H> {
+    fn read_resource(
+        &self,
+        url: &Url,
+    ) -> std::io::Result<pulldown_cmark_mdcat::resources::MimeData> {
+        self.inner.read_resource(url).inspect_err(|error| {
+            if error.kind() == ErrorKind::TimedOut {
+                event!(Level::WARN, %url, "Timed out reading resource");
+                self.timed_out.borrow_mut().push(url.clone());
+            }
+        })
+    }
+
+    fn check_resource(&self, url: &Url) -> std::io::Result<()> {
+        self.inner.check_resource(url).inspect_err(|error| {
+            if error.kind() == ErrorKind::TimedOut {
+                event!(Level::WARN, %url, "Timed out checking resource");
+                self.timed_out.borrow_mut().push(url.clone());
+            }
+        })
+    }
+
+    fn prefetch(&self, urls: &[Url]) {
+        self.inner.prefetch(urls);
+    }
+}
+
+/// The default maximum age of cached resources: One day.
+pub const DEFAULT_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Get the default directory to cache remote resources in.
+///
+/// Use `$XDG_CACHE_HOME/mdcat` or `~/.cache/mdcat` on Unix, `~/Library/Caches/mdcat` on macOS,
+/// and `%LOCALAPPDATA%\mdcat` on Windows.  Return `None` if the relevant environment variables
+/// are not set.
+pub fn default_cache_directory() -> Option<PathBuf> {
+    let non_empty = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    let base = if cfg!(windows) {
+        non_empty("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        non_empty("HOME").map(|home| Path::new(&home).join("Library").join("Caches"))
+    } else {
+        non_empty("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| non_empty("HOME").map(|home| Path::new(&home).join(".cache")))
+    };
+    base.map(|base| base.join("mdcat"))
+}
+
+/// Metadata of a cached resource.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheMetadata {
+    url: String,
+    mime_type: Option<String>,
+}
+
+/// A resource handler which caches images of an inner handler on disk.
+///
+/// Key cached images by a SHA-256 digest of their URL and the request headers, and read images
+/// from the inner handler again if the cached copy is older than a maximum age.  Do not cache any
+/// other resources.  Failure to read from or write to the cache is not an error; the handler then
+/// just reads resources from the inner handler.
+pub struct CachingResourceHandler<H> {
+    inner: H,
+    directory: PathBuf,
+    max_age: Duration,
+    request_headers: Vec<String>,
+}
+
+impl<H: ResourceUrlHandler> CachingResourceHandler<H> {
+    /// Cache images of `inner` in `directory`, for at most `max_age`.
+    pub fn new(inner: H, directory: PathBuf, max_age: Duration) -> Self {
+        Self {
+            inner,
+            directory,
+            max_age,
+            request_headers: Vec::new(),
+        }
+    }
+
+    /// Key cache entries by these `headers` in addition to the URL.
+    ///
+    /// Use this if the inner handler sends additional headers which may change the response.
+    pub fn with_request_headers(mut self, headers: Vec<String>) -> Self {
+        self.request_headers = headers;
+        self
+    }
+
+    /// The paths of the metadata and the data of the cache entry for `url`.
+    fn entry_paths(&self, url: &Url) -> (PathBuf, PathBuf) {
+        let mut hasher = Sha256::new();
+        hasher.update(url.as_str());
+        for header in &self.request_headers {
+            hasher.update(b"\n");
+            hasher.update(header);
+        }
+        let key = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        (
+            self.directory.join(format!("{key}.json")),
+            self.directory.join(key),
+        )
+    }
+
+    fn read_cached(&self, url: &Url) -> std::io::Result<Option<MimeData>> {
+        let (metadata_path, data_path) = self.entry_paths(url);
+        let age = std::fs::metadata(&data_path)?
+            .modified()?
+            .elapsed()
+            .unwrap_or_default();
+        if self.max_age < age {
+            event!(Level::DEBUG, %url, "Cached resource expired");
+            return Ok(None);
+        }
+        let metadata: CacheMetadata = serde_json::from_reader(File::open(metadata_path)?)?;
+        if metadata.url != url.as_str() {
+            // A different URL with the same hash
+            return Ok(None);
+        }
+        let data = std::fs::read(data_path)?;
+        let mime_type = metadata.mime_type.and_then(|m| m.parse::<Mime>().ok());
+        Ok(Some(MimeData { mime_type, data }))
+    }
+
+    /// Write `contents` to `path` atomically, through a temporary file in the same directory.
+    fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(format!(".tmp-{}", std::process::id()));
+        let temporary = PathBuf::from(temporary);
+        let result = File::create(&temporary)
+            .and_then(|mut file| file.write_all(contents))
+            .and_then(|_| std::fs::rename(&temporary, path));
+        if result.is_err() {
+            // Do not leave partial entries behind
+            let _ = std::fs::remove_file(&temporary);
+        }
+        result
+    }
+
+    /// Cache `resource` for `url`, if it is an image.
+    fn write_cached(&self, url: &Url, resource: &MimeData) -> std::io::Result<()> {
+        if resource.mime_type.as_ref().map(Mime::type_) != Some(mime::IMAGE) {
+            event!(Level::DEBUG, %url, "Not caching resource which is no image");
+            return Ok(());
+        }
+        let (metadata_path, data_path) = self.entry_paths(url);
+        std::fs::create_dir_all(&self.directory)?;
+        let metadata = CacheMetadata {
+            url: url.to_string(),
+            mime_type: resource.mime_type.as_ref().map(ToString::to_string),
+        };
+        Self::write_atomically(&metadata_path, &serde_json::to_vec(&metadata)?)?;
+        // Write data last, because its modification time marks the age of the entry
+        Self::write_atomically(&data_path, &resource.data)
+    }
+}
+
+impl<H: ResourceUrlHandler> ResourceUrlHandler for CachingResourceHandler<H> {
+    #[instrument(level = "debug", skip(self), fields(url = %url))]
+    fn read_resource(
+        &self,
+        url: &Url,
+    ) -> std::io::Result<pulldown_cmark_mdcat::resources::MimeData> {
+        match self.read_cached(url) {
+            Ok(Some(resource)) => {
+                event!(Level::DEBUG, %url, "Read resource from cache");
+                return Ok(resource);
+            }
+            Ok(None) => {}
+            Err(error) => {
+                event!(Level::DEBUG, %url, %error, "Resource not cached");
+            }
+        }
+        let resource = self.inner.read_resource(url)?;
+        if let Err(error) = self.write_cached(url, &resource) {
+            event!(Level::WARN, %url, %error, "Failed to cache resource");
+        }
+        Ok(resource)
+    }
+
+    /// Check `url` with the inner handler, unless it is cached.
+    fn check_resource(&self, url: &Url) -> std::io::Result<()> {
+        match self.read_cached(url) {
+            Ok(Some(_)) => Ok(()),
+            _ => self.inner.check_resource(url),
+        }
+    }
+
+    /// Prefetch all `urls` not yet cached with the inner handler, and cache them.
+    fn prefetch(&self, urls: &[Url]) {
+        let uncached = urls
+            .iter()
+            .filter(|url| !matches!(self.read_cached(url), Ok(Some(_))))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.inner.prefetch(&uncached);
+        for url in &uncached {
+            if let Ok(resource) = self.inner.read_resource(url) {
+                if let Err(error) = self.write_cached(url, &resource) {
+                    event!(Level::WARN, %url, %error, "Failed to cache resource");
+                }
+            }
+        }
+    }
+}
@@ -4,62 +4,723 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use anstyle::{AnsiColor, Color, Style};
+use pulldown_cmark::{Event, Options, Parser};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::io::{Result, Write};
-use pulldown_cmark::{Event, Parser, Options};
+use std::num::NonZeroUsize;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-/// Statistics about a markdown document.
+use crate::MarkdownExtensions;
+
+/// Default reading speed for reading time estimates, in words per minute.
+///
+/// Average reading speed is about 200 to 250 words per minute.
+pub const DEFAULT_WORDS_PER_MINUTE: NonZeroUsize = NonZeroUsize::new(225).unwrap();
+
+/// Common English words to omit from the most frequent words.
+pub const DEFAULT_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "because",
+    "been", "but", "by", "can", "could", "do", "does", "for", "from", "had", "has", "have", "he",
+    "her", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "may", "me",
+    "more", "most", "my", "no", "not", "of", "on", "one", "only", "or", "other", "our", "out",
+    "over", "she", "should", "so", "some", "such", "than", "that", "the", "their", "them", "then",
+    "there", "these", "they", "this", "those", "to", "up", "us", "was", "we", "were", "what",
+    "when", "where", "which", "who", "will", "with", "would", "you", "your",
+];
+
+/// A set of words to omit from the most frequent words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stopwords(HashSet<String>);
+
+impl Stopwords {
+    /// Parse stopwords from `contents`.
+    ///
+    /// Take every whitespace-separated word as a stopword, ignoring case, and skip lines starting
+    /// with `#`.
+    pub fn parse(contents: &str) -> Self {
+        Self(
+            contents
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .flat_map(str::split_whitespace)
+                .map(str::to_lowercase)
+                .collect(),
+        )
+    }
+
+    /// Whether `word` is a stopword.
+    pub fn contains(&self, word: &str) -> bool {
+        self.0.contains(word)
+    }
+}
+
+impl Default for Stopwords {
+    /// The [`DEFAULT_STOPWORDS`].
+    fn default() -> Self {
+        Self(DEFAULT_STOPWORDS.iter().map(ToString::to_string).collect())
+    }
+}
+
+/// How to print document statistics.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatsFormat {
+    /// A human-readable table.
+    #[default]
+    Text,
+    /// A JSON object.
+    Json,
+}
+
+/// Options for document statistics.
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    /// The format to print statistics in.
+    pub format: StatsFormat,
+    /// Whether to print only a single summary line, regardless of `format`.
+    pub summary: bool,
+    /// Whether to only count characters, words and lines of the source, without parsing it.
+    pub count_only: bool,
+    /// Reading speed for the reading time estimate, in words per minute.
+    pub words_per_minute: NonZeroUsize,
+    /// Whether to exclude code blocks from word and character counts.
+    pub exclude_code: bool,
+    /// Whether to count graphemes, i.e. user-perceived characters, of the text.
+    pub graphemes: bool,
+    /// Estimate the number of rendered lines for this many columns.
+    pub columns: Option<u16>,
+    /// Whether to include word counts per section.
+    pub sections: bool,
+    /// Include this many of the most frequent words.
+    pub top_words: Option<usize>,
+    /// Words to omit from the most frequent words.
+    pub stopwords: Stopwords,
+    /// The markdown extensions to parse, to count the document as it is rendered.
+    pub extensions: MarkdownExtensions,
+    /// Only count lines added since this git ref.
+    pub since_git: Option<String>,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            format: StatsFormat::default(),
+            summary: false,
+            count_only: false,
+            words_per_minute: DEFAULT_WORDS_PER_MINUTE,
+            exclude_code: false,
+            graphemes: false,
+            columns: None,
+            sections: false,
+            top_words: None,
+            stopwords: Stopwords::default(),
+            extensions: MarkdownExtensions::default(),
+            since_git: None,
+        }
+    }
+}
+
+/// Calculate statistics for markdown `content` according to `options`.
+pub fn document_stats(content: &str, options: &StatsOptions) -> DocumentStats {
+    if options.count_only {
+        return DocumentStats::count_source(content, options.graphemes);
+    }
+    DocumentStats::builder()
+        .extensions(options.extensions)
+        .exclude_code(options.exclude_code)
+        .count_graphemes(options.graphemes)
+        .build()
+        .analyze(content)
+}
+
+/// Calculate statistics for markdown `content` and format them according to `options`.
+pub fn format_stats(content: &str, options: &StatsOptions) -> String {
+    let stats = document_stats(content, options);
+    let sections = options
+        .sections
+        .then(|| DocumentStats::sections_with_options(content, options.extensions));
+    format_document_stats(&stats, sections.as_deref(), options)
+}
+
+/// Format `stats` and `sections` of a document according to `options`.
+pub fn format_document_stats(
+    stats: &DocumentStats,
+    sections: Option<&[SectionStat]>,
+    options: &StatsOptions,
+) -> String {
+    if options.summary {
+        return stats.format_summary_at(options.words_per_minute);
+    }
+    if options.count_only {
+        return match options.format {
+            StatsFormat::Text => stats.format_counts(),
+            StatsFormat::Json => stats.counts_to_json(),
+        };
+    }
+    let top_words = options
+        .top_words
+        .map(|n| stats.top_words_excluding(n, &options.stopwords));
+    match options.format {
+        StatsFormat::Text => {
+            let mut text = stats.format_text(options.words_per_minute);
+            if let Some(columns) = options.columns {
+                text.push_str(&format!(
+                    "Estimated rendered lines: {} at {columns} columns\n",
+                    stats.estimated_rendered_lines(columns)
+                ));
+            }
+            if let Some(sections) = sections {
+                text.push_str(&format_sections(sections));
+            }
+            if let Some(top_words) = top_words {
+                text.push_str(&format_top_words(&top_words));
+            }
+            text
+        }
+        StatsFormat::Json => stats.to_json_at(
+            options.words_per_minute,
+            options.columns,
+            sections,
+            top_words.as_deref(),
+        ),
+    }
+}
+
+/// Format the `total` statistics of a number of `files` according to `options`.
+///
+/// Omit sections, which make no sense across files.  As JSON, wrap the total statistics in an
+/// object together with the number of files.
+pub fn format_total(total: &DocumentStats, files: usize, options: &StatsOptions) -> String {
+    let stats = format_document_stats(total, None, options);
+    match options.format {
+        StatsFormat::Text => format!("Total ({files} files):\n{stats}"),
+        StatsFormat::Json => format!("{{\"files\":{files},\"total\":{stats}}}"),
+    }
+}
+
+/// Word count of a single section of a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionStat {
+    /// The plain text of the section heading.
+    pub title: String,
+    /// The level of the section heading, or 0 for the preamble before the first heading.
+    pub level: u8,
+    /// Number of words in the section, excluding its heading.
+    pub word_count: usize,
+}
+
+/// The title of the synthetic section for all content before the first heading.
+pub const PREAMBLE_TITLE: &str = "(preamble)";
+
+/// Format `sections` as an outline, indented by heading level.
+fn format_sections(sections: &[SectionStat]) -> String {
+    let mut outline = String::from("Sections:\n");
+    for section in sections {
+        let indent = "  ".repeat(usize::from(section.level.max(1)));
+        outline.push_str(&format!(
+            "{indent}{}: {} word{}\n",
+            section.title,
+            section.word_count,
+            if section.word_count == 1 { "" } else { "s" }
+        ));
+    }
+    outline
+}
+
+/// Format the most frequent `words` as a list.
+fn format_top_words(words: &[(String, usize)]) -> String {
+    let mut list = String::from("Top words:\n");
+    for (word, count) in words {
+        list.push_str(&format!("  {word}: {count}\n"));
+    }
+    list
+}
+
+/// Count words across the text events of a markdown document.
+///
+/// Markup such as emphasis may split a single word into multiple text events, so a word only
+/// ends at whitespace or at the boundary of a block.
+///
+/// Also count how often every word occurs, ignoring case and leading and trailing punctuation,
+/// as well as sentences and syllables.  A sentence ends with a word ending in `.`, `!` or `?`,
+/// or at the end of a block, e.g. a heading or a list item.
 #[derive(Debug, Default)]
+struct WordCounter {
+    count: usize,
+    in_word: bool,
+    current: String,
+    frequencies: HashMap<String, usize>,
+    sentences: usize,
+    in_sentence: bool,
+    syllables: usize,
+}
+
+impl WordCounter {
+    /// Count the words in `text`, and return the number of words which start in `text`.
+    fn push(&mut self, text: &str) -> usize {
+        let before = self.count;
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.end_word();
+            } else {
+                if !self.in_word {
+                    self.in_word = true;
+                    self.count += 1;
+                }
+                self.current.extend(c.to_lowercase());
+            }
+        }
+        self.count - before
+    }
+
+    /// End the current word, and count its occurrence.
+    fn end_word(&mut self) {
+        self.in_word = false;
+        let word = self.current.trim_matches(|c: char| !c.is_alphanumeric());
+        if !word.is_empty() {
+            *self.frequencies.entry(word.to_string()).or_default() += 1;
+            self.syllables += count_syllables(word);
+            self.in_sentence = true;
+        }
+        if self
+            .current
+            .trim_end_matches(['"', '\'', ')', ']'])
+            .ends_with(['.', '!', '?'])
+        {
+            self.end_sentence();
+        }
+        self.current.clear();
+    }
+
+    /// End the current sentence, if any.
+    fn end_sentence(&mut self) {
+        if self.in_sentence {
+            self.in_sentence = false;
+            self.sentences += 1;
+        }
+    }
+
+    /// End the current word at `event`, unless it's inline markup within a word.
+    ///
+    /// Also end the current sentence at the boundary of a block.
+    fn end_word_unless_inline(&mut self, event: &Event) {
+        use pulldown_cmark::{Tag, TagEnd};
+        match event {
+            Event::Text(_)
+            | Event::Code(_)
+            | Event::Start(Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link { .. })
+            | Event::End(
+                TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link,
+            ) => {}
+            Event::SoftBreak | Event::HardBreak => self.end_word(),
+            _ => {
+                self.end_word();
+                self.end_sentence();
+            }
+        }
+    }
+}
+
+/// The width of the border of code blocks, see `write_code_block_border` in
+/// `pulldown_cmark_mdcat`.
+const CODE_BLOCK_BORDER_WIDTH: usize = 20;
+
+/// Measures the lines of a rendered document before wrapping, from markdown events.
+///
+/// Roughly follows how mdcat renders documents, but ignores details such as link references
+/// or the indentation of block quotes.
+#[derive(Default)]
+struct LineMeasure {
+    /// The width of each line, in columns.
+    widths: Vec<usize>,
+    /// The width of the current line, if any.
+    line: Option<usize>,
+    list_depth: usize,
+    in_code_block: bool,
+}
+
+impl LineMeasure {
+    /// End the current line, if any.
+    fn end_line(&mut self) {
+        self.widths.extend(self.line.take());
+    }
+
+    /// Start a new block, with an empty line before unless it's the first block.
+    fn start_block(&mut self) {
+        self.end_line();
+        if !self.widths.is_empty() {
+            self.widths.push(0);
+        }
+    }
+
+    fn push(&mut self, event: &Event) {
+        use pulldown_cmark::{Tag, TagEnd};
+        match event {
+            Event::Text(text) if self.in_code_block => {
+                self.widths.extend(text.lines().map(UnicodeWidthStr::width));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                *self.line.get_or_insert(0) += text.width();
+            }
+            Event::SoftBreak => *self.line.get_or_insert(0) += 1,
+            Event::HardBreak => self.end_line(),
+            Event::Start(Tag::Paragraph | Tag::Table(_)) => self.start_block(),
+            Event::Start(Tag::Heading { level, .. }) => {
+                self.start_block();
+                self.line = Some(*level as usize);
+            }
+            Event::Start(Tag::List(_)) if self.list_depth == 0 => {
+                self.start_block();
+                self.list_depth += 1;
+            }
+            Event::Start(Tag::List(_)) => {
+                self.end_line();
+                self.list_depth += 1;
+            }
+            Event::End(TagEnd::List(_)) => self.list_depth -= 1,
+            Event::Start(Tag::Item) => {
+                self.end_line();
+                // Account for the indentation and the bullet
+                self.line = Some(self.list_depth * 2);
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.start_block();
+                self.widths.push(CODE_BLOCK_BORDER_WIDTH);
+                self.in_code_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                self.widths.push(CODE_BLOCK_BORDER_WIDTH);
+                self.in_code_block = false;
+            }
+            Event::Rule => {
+                self.start_block();
+                self.widths.push(0);
+            }
+            // Account for the separator between cells
+            Event::End(TagEnd::TableCell) => *self.line.get_or_insert(0) += 3,
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::TableHead
+                | TagEnd::TableRow
+                | TagEnd::DefinitionListTitle
+                | TagEnd::DefinitionListDefinition,
+            ) => self.end_line(),
+            _ => {}
+        }
+    }
+}
+
+/// Estimate the number of syllables of a lowercase `word`.
+///
+/// Count groups of consecutive vowels, including `y`, but not a silent `e` at the end of the
+/// word, as in "time", unless the word ends in "le", as in "table".  Every word has at least
+/// one syllable, including numbers and other words without vowels.
+fn count_syllables(word: &str) -> usize {
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut syllables = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            syllables += 1;
+        }
+        previous_vowel = vowel;
+    }
+    let silent_e = word.ends_with('e')
+        && !word.ends_with("le")
+        && !word.ends_with("ee")
+        && word.chars().rev().nth(1).is_some_and(|c| !is_vowel(c));
+    if silent_e {
+        syllables -= 1;
+    }
+    syllables.max(1)
+}
+
+/// A label for a Flesch reading-ease `score`, after the usual bands of the score.
+fn flesch_label(score: f64) -> &'static str {
+    match score {
+        s if 90.0 <= s => "Very easy",
+        s if 80.0 <= s => "Easy",
+        s if 70.0 <= s => "Fairly easy",
+        s if 60.0 <= s => "Standard",
+        s if 50.0 <= s => "Fairly difficult",
+        s if 30.0 <= s => "Difficult",
+        _ => "Very difficult",
+    }
+}
+
+/// Statistics about a markdown document.
+#[derive(Debug, Default, Serialize)]
 pub struct DocumentStats {
-    /// Total number of characters (including whitespace).
+    /// Size of the markdown source in bytes, including whitespace and markup.
+    pub byte_count: usize,
+    /// Number of Unicode characters in the text of the document, excluding markup.
     pub character_count: usize,
-    /// Total number of words.
+    /// Number of graphemes, i.e. user-perceived characters, in the text of the document.
+    ///
+    /// Only counted if requested, see [`StatsBuilder::count_graphemes`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grapheme_count: Option<usize>,
+    /// Number of words in the text of the document, excluding markup.
     pub word_count: usize,
+    /// Number of sentences in the text of the document.
+    pub sentence_count: usize,
+    /// Estimated number of syllables of all words.
+    pub syllable_count: usize,
     /// Number of lines.
     pub line_count: usize,
+    /// Display width of the longest line, in columns.
+    pub max_line_length: usize,
+    /// Average display width of all lines, in columns.
+    pub average_line_length: f64,
     /// Number of headings.
     pub heading_count: usize,
     /// Number of code blocks.
     pub code_block_count: usize,
     /// Number of links.
     pub link_count: usize,
+    /// Number of distinct link destinations.
+    pub distinct_link_count: usize,
+    /// Number of links to http or https URLs; all other links are internal.
+    pub external_link_count: usize,
     /// Number of images.
     pub image_count: usize,
     /// Number of lists.
     pub list_count: usize,
+    /// Number of definition lists.
+    pub definition_list_count: usize,
     /// Number of tables.
     pub table_count: usize,
+    /// Number of block quotes.
+    pub blockquote_count: usize,
+    /// Number of footnote definitions.
+    pub footnote_count: usize,
+    /// Number of inline and display math expressions.
+    ///
+    /// Only counted if math is enabled, see [`StatsBuilder::math`].
+    pub math_count: usize,
+    /// Number of task list items.
+    pub tasks_total: usize,
+    /// Number of checked task list items.
+    pub tasks_done: usize,
+    /// How often every word occurs in the text of the document, in lower case.
+    #[serde(skip)]
+    pub word_frequencies: HashMap<String, usize>,
+    /// The width of every line of the rendered document, before wrapping.
+    #[serde(skip)]
+    line_widths: Vec<usize>,
+    /// The destinations of all links, to count distinct destinations across documents.
+    #[serde(skip)]
+    link_destinations: HashSet<String>,
+}
+
+/// The parser options to compute statistics for markdown `extensions` with.
+///
+/// Always enable footnotes; mdcat does not render them, but statistics count them.
+fn extension_options(extensions: MarkdownExtensions) -> Options {
+    extensions.options().union(Options::ENABLE_FOOTNOTES)
+}
+
+/// Build a [`StatsAnalyzer`].
+///
+/// Enable the default [`MarkdownExtensions`] mdcat renders with, as well as footnotes, and include
+/// code blocks in counts by default, like [`DocumentStats::from_markdown`].
+#[derive(Debug, Copy, Clone)]
+pub struct StatsBuilder {
+    options: Options,
+    exclude_code: bool,
+    count_graphemes: bool,
+}
+
+impl Default for StatsBuilder {
+    fn default() -> Self {
+        Self {
+            options: extension_options(MarkdownExtensions::default()),
+            exclude_code: false,
+            count_graphemes: false,
+        }
+    }
+}
+
+impl StatsBuilder {
+    fn set(mut self, option: Options, enabled: bool) -> Self {
+        self.options.set(option, enabled);
+        self
+    }
+
+    /// Whether to parse pipe tables.
+    pub fn tables(self, enabled: bool) -> Self {
+        self.set(Options::ENABLE_TABLES, enabled)
+    }
+
+    /// Whether to parse `~~strikethrough~~`.
+    pub fn strikethrough(self, enabled: bool) -> Self {
+        self.set(Options::ENABLE_STRIKETHROUGH, enabled)
+    }
+
+    /// Whether to parse task lists.
+    pub fn tasklists(self, enabled: bool) -> Self {
+        self.set(Options::ENABLE_TASKLISTS, enabled)
+    }
+
+    /// Whether to parse footnotes.
+    pub fn footnotes(self, enabled: bool) -> Self {
+        self.set(Options::ENABLE_FOOTNOTES, enabled)
+    }
+
+    /// Whether to parse definition lists.
+    pub fn definition_lists(self, enabled: bool) -> Self {
+        self.set(Options::ENABLE_DEFINITION_LIST, enabled)
+    }
+
+    /// Whether to parse inline math in `$…$` and display math in `$$…$$`.
+    pub fn math(self, enabled: bool) -> Self {
+        self.set(Options::ENABLE_MATH, enabled)
+    }
+
+    /// Parse the markdown `extensions` mdcat renders with.
+    ///
+    /// Parse footnotes regardless, to count footnote definitions, and to not count footnote
+    /// references as links.
+    pub fn extensions(mut self, extensions: MarkdownExtensions) -> Self {
+        self.options = extension_options(extensions);
+        self
+    }
+
+    /// Whether to omit the text of code blocks from word and character counts.
+    ///
+    /// See [`DocumentStats::from_markdown_excluding_code`].
+    pub fn exclude_code(mut self, exclude_code: bool) -> Self {
+        self.exclude_code = exclude_code;
+        self
+    }
+
+    /// Whether to count graphemes of the text, see [`DocumentStats::grapheme_count`].
+    ///
+    /// Off by default, because segmenting text into graphemes is comparatively expensive.
+    pub fn count_graphemes(mut self, count_graphemes: bool) -> Self {
+        self.count_graphemes = count_graphemes;
+        self
+    }
+
+    /// Build the analyzer.
+    pub fn build(self) -> StatsAnalyzer {
+        StatsAnalyzer {
+            options: self.options,
+            exclude_code: self.exclude_code,
+            count_graphemes: self.count_graphemes,
+        }
+    }
+}
+
+/// Calculate statistics of markdown documents with fixed options.
+///
+/// Create with [`DocumentStats::builder`].
+#[derive(Debug, Copy, Clone)]
+pub struct StatsAnalyzer {
+    options: Options,
+    exclude_code: bool,
+    count_graphemes: bool,
+}
+
+impl StatsAnalyzer {
+    /// Calculate statistics for markdown `content`.
+    pub fn analyze(&self, content: &str) -> DocumentStats {
+        DocumentStats::count(
+            content,
+            self.options,
+            self.exclude_code,
+            self.count_graphemes,
+        )
+    }
 }
 
 impl DocumentStats {
     /// Calculate statistics from markdown content.
     pub fn from_markdown(content: &str) -> Self {
-        let mut stats = DocumentStats::default();
-        
-        // Count characters and lines
-        stats.character_count = content.len();
-        stats.line_count = content.lines().count();
-        
-        // Count words (simple whitespace-based counting)
-        stats.word_count = content
-            .split_whitespace()
-            .count();
-        
+        Self::builder().build().analyze(content)
+    }
+
+    /// Configure an analyzer to calculate statistics of many documents with the same options.
+    ///
+    /// ```
+    /// use mdcat::stats::DocumentStats;
+    ///
+    /// let analyzer = DocumentStats::builder().exclude_code(true).build();
+    /// let stats = analyzer.analyze("Some words\n\n```\nlet code = 42;\n```\n");
+    /// assert_eq!(stats.word_count, 2);
+    /// assert_eq!(stats.code_block_count, 1);
+    /// assert_eq!(analyzer.analyze("Three more words").word_count, 3);
+    /// ```
+    pub fn builder() -> StatsBuilder {
+        StatsBuilder::default()
+    }
+
+    /// Calculate statistics from markdown content, excluding code blocks from counts.
+    ///
+    /// Like [`DocumentStats::from_markdown`], but omit the text of code blocks from the word
+    /// and character counts.  Code blocks still count towards `code_block_count`, and `byte_count`
+    /// remains the size of the whole source.
+    pub fn from_markdown_excluding_code(content: &str) -> Self {
+        Self::builder().exclude_code(true).build().analyze(content)
+    }
+
+    /// Calculate statistics from markdown `content` parsed with `options`.
+    fn count(content: &str, options: Options, exclude_code: bool, count_graphemes: bool) -> Self {
+        let mut stats = DocumentStats {
+            // Count bytes and lines of the raw source
+            byte_count: content.len(),
+            grapheme_count: count_graphemes.then_some(0),
+            line_count: content.lines().count(),
+            ..Default::default()
+        };
+        // Measure display width, because bytes and chars misrepresent wide characters
+        let widths = content
+            .lines()
+            .map(UnicodeWidthStr::width)
+            .collect::<Vec<_>>();
+        stats.max_line_length = widths.iter().copied().max().unwrap_or_default();
+        if !widths.is_empty() {
+            stats.average_line_length = widths.iter().sum::<usize>() as f64 / widths.len() as f64;
+        }
+        let mut in_code_block = false;
+        let mut words = WordCounter::default();
+        let mut lines = LineMeasure::default();
+
         // Parse markdown to count structural elements
-        let parser = Parser::new_ext(
-            content,
-            Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
-        );
-        
+        let parser = Parser::new_ext(content, options);
+
         for event in parser {
+            words.end_word_unless_inline(&event);
+            lines.push(&event);
             match event {
+                Event::Text(text) | Event::Code(text) if !(exclude_code && in_code_block) => {
+                    words.push(&text);
+                    stats.character_count += text.chars().count();
+                    if let Some(graphemes) = stats.grapheme_count.as_mut() {
+                        *graphemes += text.graphemes(true).count();
+                    }
+                }
                 Event::Start(pulldown_cmark::Tag::Heading { .. }) => {
                     stats.heading_count += 1;
                 }
                 Event::Start(pulldown_cmark::Tag::CodeBlock(_)) => {
                     stats.code_block_count += 1;
+                    in_code_block = true;
                 }
-                Event::Start(pulldown_cmark::Tag::Link { .. }) => {
+                Event::End(pulldown_cmark::TagEnd::CodeBlock) => {
+                    in_code_block = false;
+                }
+                Event::Start(pulldown_cmark::Tag::Link { dest_url, .. }) => {
                     stats.link_count += 1;
+                    if is_external_link(&dest_url) {
+                        stats.external_link_count += 1;
+                    }
+                    stats.link_destinations.insert(dest_url.into_string());
                 }
                 Event::Start(pulldown_cmark::Tag::Image { .. }) => {
                     stats.image_count += 1;
@@ -67,59 +728,494 @@ impl DocumentStats {
                 Event::Start(pulldown_cmark::Tag::List(_)) => {
                     stats.list_count += 1;
                 }
+                Event::Start(pulldown_cmark::Tag::DefinitionList) => {
+                    stats.definition_list_count += 1;
+                }
                 Event::Start(pulldown_cmark::Tag::Table(_)) => {
                     stats.table_count += 1;
                 }
+                Event::Start(pulldown_cmark::Tag::BlockQuote(_)) => {
+                    stats.blockquote_count += 1;
+                }
+                Event::Start(pulldown_cmark::Tag::FootnoteDefinition(_)) => {
+                    stats.footnote_count += 1;
+                }
+                Event::InlineMath(_) | Event::DisplayMath(_) => {
+                    stats.math_count += 1;
+                }
+                Event::TaskListMarker(checked) => {
+                    stats.tasks_total += 1;
+                    stats.tasks_done += usize::from(checked);
+                }
                 _ => {}
             }
         }
-        
+
+        words.end_word();
+        words.end_sentence();
+        stats.word_count = words.count;
+        stats.sentence_count = words.sentences;
+        stats.syllable_count = words.syllables;
+        stats.word_frequencies = words.frequencies;
+        lines.end_line();
+        stats.line_widths = lines.widths;
+        stats.distinct_link_count = stats.link_destinations.len();
         stats
     }
-    
+
+    /// Count characters, words and lines of markdown `content`, without parsing it.
+    ///
+    /// Count words and characters of the source, including markup, e.g. list bullets, and leave
+    /// all structural counts at zero.  This is much faster than parsing markdown, and good enough
+    /// for documents which contain little markup.  Count graphemes of the source as well if
+    /// `count_graphemes` is true.
+    pub fn count_source(content: &str, count_graphemes: bool) -> Self {
+        let mut words = WordCounter::default();
+        words.push(content);
+        words.end_word();
+        words.end_sentence();
+        DocumentStats {
+            byte_count: content.len(),
+            character_count: content.chars().count(),
+            grapheme_count: count_graphemes.then(|| content.graphemes(true).count()),
+            word_count: words.count,
+            sentence_count: words.sentences,
+            syllable_count: words.syllables,
+            line_count: content.lines().count(),
+            word_frequencies: words.frequencies,
+            ..Default::default()
+        }
+    }
+
+    /// Format only the counts of [`DocumentStats::count_source`] for display.
+    pub fn format_counts(&self) -> String {
+        let graphemes = self
+            .grapheme_count
+            .map(|count| format!("Characters (graphemes): {count}\n"))
+            .unwrap_or_default();
+        format!(
+            "Document Statistics:\n\
+             ───────────────────\n\
+             Bytes (source): {}\n\
+             Characters (source): {}\n\
+             {graphemes}\
+             Words: {}\n\
+             Lines: {}\n",
+            self.byte_count, self.character_count, self.word_count, self.line_count
+        )
+    }
+
+    /// Format only the counts of [`DocumentStats::count_source`] as a JSON object.
+    pub fn counts_to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct JsonCounts {
+            byte_count: usize,
+            character_count: usize,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            grapheme_count: Option<usize>,
+            word_count: usize,
+            line_count: usize,
+        }
+
+        serde_json::to_string(&JsonCounts {
+            byte_count: self.byte_count,
+            character_count: self.character_count,
+            grapheme_count: self.grapheme_count,
+            word_count: self.word_count,
+            line_count: self.line_count,
+        })
+        .expect("Document statistics must serialize to JSON")
+    }
+
+    /// Estimate how many lines the rendered document occupies on a terminal with `columns`.
+    ///
+    /// This is only an estimate: it approximates the lines of paragraphs, headings, list items,
+    /// code blocks and tables, and assumes that the terminal wraps long lines at any character,
+    /// whereas mdcat wraps paragraphs at words.  It ignores images and indentation.
+    pub fn estimated_rendered_lines(&self, columns: u16) -> usize {
+        let columns = usize::from(columns.max(1));
+        self.line_widths
+            .iter()
+            .map(|width| width.div_ceil(columns).max(1))
+            .sum()
+    }
+
+    /// Calculate the Flesch reading-ease score.
+    ///
+    /// Higher scores indicate text which is easier to read; plain English scores about 60 to 70.
+    /// Estimate syllables from groups of vowels, see `count_syllables`, so the score is only an
+    /// approximation, particularly for technical text.
+    ///
+    /// Return `None` if the document has no words.
+    pub fn flesch_reading_ease(&self) -> Option<f64> {
+        (0 < self.word_count).then(|| {
+            let words = self.word_count as f64;
+            let sentences = self.sentence_count.max(1) as f64;
+            let syllables = self.syllable_count as f64;
+            206.835 - 1.015 * (words / sentences) - 84.6 * (syllables / words)
+        })
+    }
+
+    /// Get the `n` most frequent words, except for [`DEFAULT_STOPWORDS`].
+    ///
+    /// Return words in lower case with their number of occurrences, most frequent words first,
+    /// and words of equal frequency in alphabetical order.
+    pub fn top_words(&self, n: usize) -> Vec<(String, usize)> {
+        self.top_words_excluding(n, &Stopwords::default())
+    }
+
+    /// Get the `n` most frequent words, except for the given `stopwords`.
+    ///
+    /// See [`DocumentStats::top_words`].
+    pub fn top_words_excluding(&self, n: usize, stopwords: &Stopwords) -> Vec<(String, usize)> {
+        let mut words = self
+            .word_frequencies
+            .iter()
+            .filter(|(word, _)| !stopwords.contains(word))
+            .map(|(word, count)| (word.clone(), *count))
+            .collect::<Vec<_>>();
+        words.sort_by(|(word_a, count_a), (word_b, count_b)| {
+            count_b.cmp(count_a).then_with(|| word_a.cmp(word_b))
+        });
+        words.truncate(n);
+        words
+    }
+
+    /// Add all counts of `other` to these statistics.
+    ///
+    /// Derived statistics such as the reading time or the readability score then apply to the
+    /// combined text of both documents.
+    pub fn merge(&mut self, other: &DocumentStats) {
+        self.byte_count += other.byte_count;
+        self.character_count += other.character_count;
+        self.grapheme_count = match (self.grapheme_count, other.grapheme_count) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        self.word_count += other.word_count;
+        self.sentence_count += other.sentence_count;
+        self.syllable_count += other.syllable_count;
+        let total_width = self.average_line_length * self.line_count as f64
+            + other.average_line_length * other.line_count as f64;
+        self.line_count += other.line_count;
+        if 0 < self.line_count {
+            self.average_line_length = total_width / self.line_count as f64;
+        }
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+        self.heading_count += other.heading_count;
+        self.code_block_count += other.code_block_count;
+        self.link_count += other.link_count;
+        self.external_link_count += other.external_link_count;
+        self.link_destinations
+            .extend(other.link_destinations.iter().cloned());
+        self.distinct_link_count = self.link_destinations.len();
+        self.image_count += other.image_count;
+        self.list_count += other.list_count;
+        self.definition_list_count += other.definition_list_count;
+        self.table_count += other.table_count;
+        self.blockquote_count += other.blockquote_count;
+        self.footnote_count += other.footnote_count;
+        self.math_count += other.math_count;
+        self.tasks_total += other.tasks_total;
+        self.tasks_done += other.tasks_done;
+        for (word, count) in &other.word_frequencies {
+            *self.word_frequencies.entry(word.clone()).or_default() += count;
+        }
+        self.line_widths.extend_from_slice(&other.line_widths);
+    }
+
+    /// Calculate word counts per section of markdown `content`.
+    ///
+    /// Attribute all words between a heading and the next heading of any level to the former
+    /// heading.  Words before the first heading go to a synthetic [`PREAMBLE_TITLE`] section at
+    /// level 0; this section is omitted if the document starts with a heading, and holds the
+    /// entire document if it has no headings at all.
+    pub fn sections(content: &str) -> Vec<SectionStat> {
+        Self::sections_with_options(content, MarkdownExtensions::default())
+    }
+
+    /// Calculate word counts per section of markdown `content` parsed with `extensions`.
+    ///
+    /// See [`DocumentStats::sections`].
+    pub fn sections_with_options(
+        content: &str,
+        extensions: MarkdownExtensions,
+    ) -> Vec<SectionStat> {
+        let mut sections = vec![SectionStat {
+            title: PREAMBLE_TITLE.to_string(),
+            level: 0,
+            word_count: 0,
+        }];
+        let mut in_heading = false;
+        let mut words = WordCounter::default();
+        for event in Parser::new_ext(content, extension_options(extensions)) {
+            words.end_word_unless_inline(&event);
+            match event {
+                Event::Start(pulldown_cmark::Tag::Heading { level, .. }) => {
+                    in_heading = true;
+                    sections.push(SectionStat {
+                        title: String::new(),
+                        level: level as u8,
+                        word_count: 0,
+                    });
+                }
+                Event::End(pulldown_cmark::TagEnd::Heading(_)) => {
+                    in_heading = false;
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    // There's always at least the preamble section
+                    let section = sections.last_mut().unwrap();
+                    if in_heading {
+                        section.title.push_str(&text);
+                    } else {
+                        section.word_count += words.push(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if sections[0].word_count == 0 && 1 < sections.len() {
+            sections.remove(0);
+        }
+        sections
+    }
+
     /// Calculate estimated reading time in minutes.
-    /// Based on average reading speed of 200-250 words per minute.
+    ///
+    /// Assume a reading speed of [`DEFAULT_WORDS_PER_MINUTE`].
     pub fn reading_time_minutes(&self) -> usize {
-        const WORDS_PER_MINUTE: usize = 225;
-        (self.word_count + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE // Ceiling division
+        self.reading_time_minutes_at(DEFAULT_WORDS_PER_MINUTE)
+    }
+
+    /// Calculate estimated reading time in minutes at `wpm` words per minute.
+    pub fn reading_time_minutes_at(&self, wpm: NonZeroUsize) -> usize {
+        self.word_count.div_ceil(wpm.get())
+    }
+
+    /// Format the estimated reading time in hours and minutes, e.g. `8h 0m` or `6m`.
+    ///
+    /// Assume a reading speed of [`DEFAULT_WORDS_PER_MINUTE`].
+    pub fn reading_time_formatted(&self) -> String {
+        self.reading_time_formatted_at(DEFAULT_WORDS_PER_MINUTE)
+    }
+
+    /// Format the estimated reading time at `wpm` words per minute in hours and minutes.
+    ///
+    /// Omit hours for documents which take less than an hour to read.
+    pub fn reading_time_formatted_at(&self, wpm: NonZeroUsize) -> String {
+        let minutes = self.reading_time_minutes_at(wpm);
+        match minutes / 60 {
+            0 => format!("{minutes}m"),
+            hours => format!("{hours}h {}m", minutes % 60),
+        }
+    }
+
+    /// Format statistics as a JSON object.
+    ///
+    /// The object contains all fields of these statistics, plus the estimated
+    /// `reading_time_minutes`.
+    pub fn to_json(&self) -> String {
+        self.to_json_at(DEFAULT_WORDS_PER_MINUTE, None, None, None)
     }
-    
+
+    fn to_json_at(
+        &self,
+        wpm: NonZeroUsize,
+        columns: Option<u16>,
+        sections: Option<&[SectionStat]>,
+        top_words: Option<&[(String, usize)]>,
+    ) -> String {
+        #[derive(Serialize)]
+        struct JsonStats<'a> {
+            #[serde(flatten)]
+            stats: &'a DocumentStats,
+            reading_time_minutes: usize,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            flesch_reading_ease: Option<f64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            estimated_rendered_lines: Option<usize>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            sections: Option<&'a [SectionStat]>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            top_words: Option<Vec<JsonWord<'a>>>,
+        }
+
+        #[derive(Serialize)]
+        struct JsonWord<'a> {
+            word: &'a str,
+            count: usize,
+        }
+
+        serde_json::to_string(&JsonStats {
+            stats: self,
+            reading_time_minutes: self.reading_time_minutes_at(wpm),
+            flesch_reading_ease: self.flesch_reading_ease(),
+            estimated_rendered_lines: columns.map(|columns| self.estimated_rendered_lines(columns)),
+            sections,
+            top_words: top_words.map(|words| {
+                words
+                    .iter()
+                    .map(|(word, count)| JsonWord {
+                        word,
+                        count: *count,
+                    })
+                    .collect()
+            }),
+        })
+        .expect("Document statistics must serialize to JSON")
+    }
+
     /// Format statistics for display.
     pub fn format(&self) -> String {
-        let reading_time = self.reading_time_minutes();
+        self.format_text(DEFAULT_WORDS_PER_MINUTE)
+    }
+
+    /// Format statistics as a single line.
+    ///
+    /// See [`DocumentStats::format_summary_at`].
+    pub fn format_summary(&self) -> String {
+        self.format_summary_at(DEFAULT_WORDS_PER_MINUTE)
+    }
+
+    /// Format statistics as a single line, with the reading time at `wpm` words per minute.
+    ///
+    /// Format the number of words, the reading time in minutes, and the number of headings as
+    /// space-separated `key=value` pairs, e.g. `words=1234 read=6m headings=12`, without a
+    /// trailing newline.
+    pub fn format_summary_at(&self, wpm: NonZeroUsize) -> String {
+        format!(
+            "words={} read={}m headings={}",
+            self.word_count,
+            self.reading_time_minutes_at(wpm),
+            self.heading_count
+        )
+    }
+
+    /// The percentage of done tasks, rounded to the nearest integer.
+    ///
+    /// Return `None` if the document has no tasks.
+    pub fn tasks_percentage(&self) -> Option<usize> {
+        (0 < self.tasks_total)
+            .then(|| (self.tasks_done * 100 + self.tasks_total / 2) / self.tasks_total)
+    }
+
+    fn format_text(&self, wpm: NonZeroUsize) -> String {
+        let tasks = self
+            .tasks_percentage()
+            .map(|percentage| {
+                format!(
+                    "Tasks: {}/{} ({percentage}%)\n",
+                    self.tasks_done, self.tasks_total
+                )
+            })
+            .unwrap_or_default();
+        let graphemes = self
+            .grapheme_count
+            .map(|count| format!("Characters (graphemes): {count}\n"))
+            .unwrap_or_default();
+        let math = if 0 < self.math_count {
+            format!("Math: {}\n", self.math_count)
+        } else {
+            String::new()
+        };
+        let readability = self
+            .flesch_reading_ease()
+            .map(|score| {
+                format!(
+                    "Readability (Flesch): {score:.1} ({})\n",
+                    flesch_label(score)
+                )
+            })
+            .unwrap_or_default();
         format!(
             "Document Statistics:\n\
              ───────────────────\n\
-             Characters: {}\n\
+             Bytes (source): {}\n\
+             Characters (text): {}\n\
+             {}\
              Words: {}\n\
              Lines: {}\n\
+             Longest line: {} columns\n\
+             Average line length: {:.1} columns\n\
              Headings: {}\n\
              Code blocks: {}\n\
-             Links: {}\n\
+             Links: {} ({} distinct, {} external, {} internal)\n\
              Images: {}\n\
              Lists: {}\n\
+             Definition lists: {}\n\
              Tables: {}\n\
-             Estimated reading time: {} minute{}\n",
+             Block quotes: {}\n\
+             Footnotes: {}\n\
+             {}\
+             {}\
+             {}\
+             Estimated reading time: {}\n",
+            self.byte_count,
             self.character_count,
+            graphemes,
             self.word_count,
             self.line_count,
+            self.max_line_length,
+            self.average_line_length,
             self.heading_count,
             self.code_block_count,
             self.link_count,
+            self.distinct_link_count,
+            self.external_link_count,
+            self.link_count - self.external_link_count,
             self.image_count,
             self.list_count,
+            self.definition_list_count,
             self.table_count,
-            reading_time,
-            if reading_time == 1 { "" } else { "s" }
+            self.blockquote_count,
+            self.footnote_count,
+            math,
+            tasks,
+            readability,
+            self.reading_time_formatted_at(wpm),
         )
     }
 }
 
+/// Whether the link destination `url` is an http or https URL.
+fn is_external_link(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+    })
+}
+
+/// The default separator between line numbers and lines.
+pub const DEFAULT_LINE_NUMBER_SEPARATOR: &str = " │ ";
+
+/// How to format line numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineNumberOptions {
+    /// The separator between the line number and the line.
+    pub separator: String,
+    /// The colour of line numbers and the separator, if any.
+    pub colour: Option<AnsiColor>,
+    /// The number of the first line; at least 1.
+    pub start: usize,
+}
+
+impl Default for LineNumberOptions {
+    fn default() -> Self {
+        Self {
+            separator: DEFAULT_LINE_NUMBER_SEPARATOR.to_string(),
+            colour: None,
+            start: 1,
+        }
+    }
+}
+
 /// Line number formatter for markdown output.
 pub struct LineNumberFormatter {
     current_line: usize,
     show_line_numbers: bool,
+    total_lines: usize,
     line_number_width: usize,
+    separator: String,
+    style: Style,
 }
 
 impl LineNumberFormatter {
@@ -130,36 +1226,72 @@ impl LineNumberFormatter {
         } else {
             0
         };
-        
+
         Self {
             current_line: 0,
             show_line_numbers,
+            total_lines,
             line_number_width,
+            separator: DEFAULT_LINE_NUMBER_SEPARATOR.to_string(),
+            style: Style::new(),
         }
     }
-    
+
+    /// Format line numbers according to `options`.
+    ///
+    /// Number lines from `options.start` on, and pad line numbers to the width of the last line
+    /// number.
+    pub fn with_options(self, options: LineNumberOptions) -> Self {
+        let first_line = options.start.max(1);
+        let line_number_width = if self.show_line_numbers {
+            (first_line - 1 + self.total_lines).to_string().len()
+        } else {
+            0
+        };
+        Self {
+            current_line: first_line - 1,
+            line_number_width,
+            separator: options.separator,
+            style: Style::new().fg_color(options.colour.map(Color::Ansi)),
+            ..self
+        }
+    }
+
     /// Write a line number prefix if line numbers are enabled.
     pub fn write_line_number<W: Write>(&mut self, writer: &mut W) -> Result<()> {
         if self.show_line_numbers {
             self.current_line += 1;
             write!(
                 writer,
-                "{:>width$} │ ",
+                "{}{:>width$}{}{:#}",
+                self.style,
                 self.current_line,
+                self.separator,
+                self.style,
                 width = self.line_number_width
             )?;
         }
         Ok(())
     }
-    
+
     /// Write a newline and prepare for the next line.
     pub fn write_newline<W: Write>(&mut self, writer: &mut W) -> Result<()> {
         writeln!(writer)?;
         Ok(())
     }
-    
+
+    /// Write `text` to `writer`, prefixing every line with its line number.
+    pub fn write_lines<W: Write>(&mut self, writer: &mut W, text: &str) -> Result<()> {
+        for line in text.lines() {
+            self.write_line_number(writer)?;
+            write!(writer, "{line}")?;
+            self.write_newline(writer)?;
+        }
+        Ok(())
+    }
+
     /// Get the current line number.
     pub fn current_line(&self) -> usize {
         self.current_line
     }
-} 
\ No newline at end of file
+}
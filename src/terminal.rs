@@ -0,0 +1,86 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use pulldown_cmark_mdcat::terminal::capabilities::ImageCapability;
+use pulldown_cmark_mdcat::terminal::TerminalSize;
+use pulldown_cmark_mdcat::TerminalProgram;
+use serde::Serialize;
+
+/// Whether `colorterm`, i.e. the value of `$COLORTERM`, announces 24-bit colours.
+pub fn is_truecolor(colorterm: Option<&str>) -> bool {
+    matches!(colorterm, Some("truecolor" | "24bit"))
+}
+
+/// Information about the detected terminal, to debug rendering issues.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TerminalInfo {
+    /// The name of the terminal program.
+    pub terminal: String,
+    /// Whether the terminal supports ANSI styles.
+    pub styles: bool,
+    /// Whether the terminal supports inline images.
+    pub images: bool,
+    /// The protocol for inline images, if any.
+    pub image_protocol: Option<&'static str>,
+    /// Whether the terminal supports OSC 8 hyperlinks.
+    pub hyperlinks: bool,
+    /// Whether the terminal announces 24-bit colours in `$COLORTERM`.
+    pub truecolor: bool,
+    /// The number of columns of the terminal, if known.
+    pub columns: Option<u16>,
+}
+
+impl TerminalInfo {
+    /// Collect information about `terminal`, with the given `size`, if known.
+    ///
+    /// Take support for 24-bit colours from `$COLORTERM`.
+    pub fn detect(terminal: TerminalProgram, size: Option<TerminalSize>) -> Self {
+        let capabilities = terminal.capabilities();
+        let image_protocol = capabilities.image.map(|image| match image {
+            ImageCapability::Terminology(_) => "terminology",
+            ImageCapability::ITerm2(_) => "iterm2",
+            ImageCapability::Kitty(_) => "kitty",
+        });
+        Self {
+            terminal: terminal.to_string(),
+            styles: capabilities.style.is_some(),
+            images: image_protocol.is_some(),
+            image_protocol,
+            hyperlinks: capabilities.links.is_some(),
+            truecolor: is_truecolor(std::env::var("COLORTERM").ok().as_deref()),
+            columns: size.map(|size| size.columns),
+        }
+    }
+
+    /// Format this information as a JSON object.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Terminal information must serialize to JSON")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn truecolor() {
+        assert!(is_truecolor(Some("truecolor")));
+        assert!(is_truecolor(Some("24bit")));
+        assert!(!is_truecolor(Some("256")));
+        assert!(!is_truecolor(None));
+    }
+
+    #[test]
+    fn kitty_info() {
+        let info = TerminalInfo::detect(TerminalProgram::Kitty, None);
+        assert_eq!(info.terminal, "kitty");
+        assert!(info.styles);
+        assert!(info.hyperlinks);
+        assert_eq!(info.image_protocol, Some("kitty"));
+        assert!(info.to_json().contains("\"columns\": null"));
+    }
+}
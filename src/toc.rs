@@ -0,0 +1,204 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashSet;
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// A heading in a table of contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// The level of the heading, from 1 to 6.
+    pub level: u8,
+    /// The plain text of the heading, without any inline markup.
+    pub title: String,
+}
+
+/// Collect all headings of markdown `content`.
+///
+/// Extract the plain text of each heading, i.e. the text of inline code, links, emphasis, etc.
+pub fn headings(content: &str) -> Vec<TocEntry> {
+    let parser = Parser::new_ext(
+        content,
+        Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
+    );
+    let mut entries = Vec::new();
+    let mut current: Option<TocEntry> = None;
+    for event in parser {
+        match (event, current.as_mut()) {
+            (Event::Start(Tag::Heading { level, .. }), _) => {
+                current = Some(TocEntry {
+                    level: level as u8,
+                    title: String::new(),
+                });
+            }
+            (Event::End(TagEnd::Heading(_)), Some(_)) => {
+                entries.extend(current.take());
+            }
+            (Event::Text(text) | Event::Code(text), Some(entry)) => {
+                entry.title.push_str(&text);
+            }
+            (Event::SoftBreak | Event::HardBreak, Some(entry)) => {
+                entry.title.push(' ');
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// Generate the GitHub-style anchor slug for a heading with `title`.
+///
+/// Lowercase `title`, strip all punctuation except hyphens and underscores, and replace spaces
+/// with hyphens.
+pub fn slug(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Generate unique anchor slugs for all `entries`, in order.
+///
+/// Like [`slug`], but append `-1`, `-2`, etc. to the slug of a heading if an earlier heading
+/// already has the same slug.
+pub fn anchors(entries: &[TocEntry]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    entries
+        .iter()
+        .map(|entry| {
+            let base = slug(&entry.title);
+            let mut anchor = base.clone();
+            let mut suffix = 0;
+            while !seen.insert(anchor.clone()) {
+                suffix += 1;
+                anchor = format!("{base}-{suffix}");
+            }
+            anchor
+        })
+        .collect()
+}
+
+/// Escape all ASCII punctuation in `text` to make it literal markdown text.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Build a table of contents for `entries` as a nested markdown list.
+///
+/// Omit all entries with a level deeper than `max_depth`.  Nest the list relative to the
+/// top-most heading level among the remaining entries, but never nest an entry more than one
+/// level deeper than the previous entry, to keep the list well-formed if headings skip levels.
+///
+/// Return an empty string if no entries remain.
+pub fn markdown(entries: &[TocEntry], max_depth: u8) -> String {
+    let entries = entries
+        .iter()
+        .filter(|entry| entry.level <= max_depth)
+        .collect::<Vec<_>>();
+    let top_level = entries.iter().map(|entry| entry.level).min().unwrap_or(1);
+    let mut toc = String::new();
+    let mut previous_depth = None;
+    for entry in entries {
+        let depth = usize::from(entry.level - top_level);
+        let depth = previous_depth.map_or(0, |previous: usize| depth.min(previous + 1));
+        previous_depth = Some(depth);
+        let indent = "  ".repeat(depth);
+        toc.push_str(&format!("{indent}- {}\n", escape_markdown(&entry.title)));
+    }
+    toc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar_asserts::assert_eq;
+
+    #[test]
+    fn headings_with_inline_markup() {
+        let entries = headings(
+            "# Using `mdcat`\n\nText\n\n## See [the docs](http://example.com) *now*\n\nHeading\n-------\n",
+        );
+        assert_eq!(
+            entries,
+            vec![
+                TocEntry {
+                    level: 1,
+                    title: "Using mdcat".to_string()
+                },
+                TocEntry {
+                    level: 2,
+                    title: "See the docs now".to_string()
+                },
+                TocEntry {
+                    level: 2,
+                    title: "Heading".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slug("Hello World"), "hello-world");
+        assert_eq!(
+            slug("Using `mdcat`: a (short) guide!"),
+            "using-mdcat-a-short-guide"
+        );
+        assert_eq!(
+            slug("snake_case and kebab-case"),
+            "snake_case-and-kebab-case"
+        );
+        assert_eq!(slug("1. Über Straße"), "1-über-straße");
+        assert_eq!(slug("Foo & Bar"), "foo--bar");
+    }
+
+    #[test]
+    fn anchors_of_duplicate_headings() {
+        let entries = headings("# Title\n\n## Title\n\n## Other\n\n### title\n\n# Title 1\n");
+        assert_eq!(
+            anchors(&entries),
+            ["title", "title-1", "other", "title-2", "title-1-1"]
+        );
+    }
+
+    #[test]
+    fn markdown_nests_and_limits_depth() {
+        let entries = headings("## Intro\n\n### Details\n\n#### Deep\n\n## 1. Usage_now\n");
+        assert_eq!(
+            markdown(&entries, 6),
+            "- Intro\n  - Details\n    - Deep\n- 1\\. Usage\\_now\n"
+        );
+        assert_eq!(
+            markdown(&entries, 3),
+            "- Intro\n  - Details\n- 1\\. Usage\\_now\n"
+        );
+        assert_eq!(markdown(&entries, 1), "");
+    }
+
+    #[test]
+    fn markdown_with_skipped_levels() {
+        let entries = headings("### Before\n\n# Title\n\n#### Deep\n\n## Section\n");
+        assert_eq!(
+            markdown(&entries, 6),
+            "- Before\n- Title\n  - Deep\n  - Section\n"
+        );
+    }
+}
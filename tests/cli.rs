@@ -8,10 +8,17 @@
 
 #![deny(warnings, clippy::all)]
 
+mod server;
+
 mod cli {
     use std::ffi::OsStr;
     use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
     use std::process::{Command, Output, Stdio};
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+
+    use crate::server::{self, Response};
 
     fn cargo_mdcat() -> Command {
         Command::new(env!("CARGO_BIN_EXE_mdcat"))
@@ -25,6 +32,96 @@ mod cli {
         cargo_mdcat().args(args).output().unwrap()
     }
 
+    /// Serve a single HTTP request with `body` on a local port.
+    ///
+    /// Return the URL of the server, and a handle to the thread serving the request.
+    fn serve_once(body: &'static str) -> (String, JoinHandle<Vec<Vec<String>>>) {
+        let (address, handle) = server::serve(vec![Response::ok("text/markdown", body)]);
+        (format!("http://{address}/docs/README.md"), handle)
+    }
+
+    /// Accept a single connection on a local port, and never respond.
+    ///
+    /// Return the URL of an image on the server, and a handle to the thread serving the request.
+    fn serve_slowly() -> (String, JoinHandle<()>) {
+        let (address, handle) = server::serve_slowly();
+        (format!("http://{address}/image.png"), handle)
+    }
+
+    /// Run `command` with `input` on stdin, and return its output.
+    fn run_with_stdin(command: &mut Command, input: &str) -> Output {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(input.as_bytes()).unwrap();
+        drop(stdin);
+        child.wait_with_output().unwrap()
+    }
+
+    /// Render `input` from stdin with `args`.
+    fn render_stdin<I, S>(args: I, input: &str) -> Output
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        run_with_stdin(cargo_mdcat().args(args), input)
+    }
+
+    /// Render `markdown` from stdin with `args` in kitty, to make mdcat fetch images.
+    fn render_in_kitty(markdown: &str, args: &[&str]) -> Output {
+        run_with_stdin(
+            without_proxy(&mut cargo_mdcat())
+                .env("TERM", "xterm-kitty")
+                .args(args),
+            markdown,
+        )
+    }
+
+    /// A temporary directory, removed with all its contents when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        /// Create a fresh temporary directory for the test `name`.
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("mdcat-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+
+        fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+            self.0.join(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Remove all proxy configuration from the environment of `command`.
+    fn without_proxy(command: &mut Command) -> &mut Command {
+        for var in [
+            "http_proxy",
+            "https_proxy",
+            "HTTPS_PROXY",
+            "all_proxy",
+            "ALL_PROXY",
+        ] {
+            command.env_remove(var);
+        }
+        command
+    }
+
     #[test]
     fn show_help() {
         let output = run_cargo_mdcat(["--help"]);
@@ -80,6 +177,1407 @@ mod cli {
         assert!(output.stdout.is_empty());
     }
 
+    #[test]
+    fn file_separator_between_files() {
+        let output = run_cargo_mdcat([
+            "--no-colour",
+            "--file-separator",
+            "## Next: {file}",
+            "sample/common-mark.md",
+            "sample/showcase.md",
+        ]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success(), "Stdout: {stdout}");
+        // The separator comes between both files, but not before the first one
+        let first = stdout.find("CommonMark sample document").unwrap();
+        let separator = stdout
+            .find("\u{2504}\u{2504}Next: sample/showcase.md")
+            .unwrap();
+        assert!(first < separator, "Stdout: {stdout}");
+        assert!(!stdout.contains("Next: sample/common-mark.md"));
+    }
+
+    #[test]
+    fn default_file_separator() {
+        let output =
+            run_cargo_mdcat(["--no-colour", "sample/common-mark.md", "sample/showcase.md"]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(stdout.contains("\u{2550}\u{2550}\u{2550}"));
+        assert!(stdout.contains("sample/showcase.md"));
+        assert!(!stdout.contains("sample/common-mark.md"));
+    }
+
+    #[test]
+    fn plain_output_without_escapes() {
+        let output = cargo_mdcat()
+            .env("TERM", "xterm-kitty")
+            .args(["--plain", "sample/showcase.md"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(!output.stdout.is_empty());
+        assert!(!output.stdout.contains(&b'\x1b'));
+    }
+
+    #[test]
+    fn no_color() {
+        let render = |no_color: &str, args: &[&str]| {
+            let output = cargo_mdcat()
+                .env("TERM", "xterm-kitty")
+                .env("NO_COLOR", no_color)
+                .args(args)
+                .arg("sample/showcase.md")
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            assert!(!output.stdout.is_empty());
+            output.stdout
+        };
+        assert!(!render("1", &[]).contains(&b'\x1b'));
+        assert!(render("", &[]).contains(&b'\x1b'));
+        assert!(render("1", &["--color=always"]).contains(&b'\x1b'));
+    }
+
+    #[test]
+    fn write_output_to_file() {
+        let directory = TempDir::new("output");
+        let path = directory.join("output.txt");
+        let output = cargo_mdcat()
+            .arg("--output")
+            .arg(&path)
+            .args(["sample/common-mark.md", "sample/showcase.md"])
+            .output()
+            .unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        // Both files end up in the output file, in order
+        let separator = contents.find("sample/showcase.md").unwrap();
+        assert!(0 < separator, "Contents: {contents}");
+        assert!(!contents.contains('\x1b'), "Contents: {contents}");
+    }
+
+    #[test]
+    fn write_output_to_directory() {
+        let directory = TempDir::new("output-dir");
+        let output = cargo_mdcat()
+            .args(["--to", "html", "--output-dir"])
+            .arg(directory.join("out"))
+            .args(["sample/common-mark.md", "sample/showcase.md"])
+            .output()
+            .unwrap();
+        let common_mark = std::fs::read_to_string(directory.join("out/common-mark.html"));
+        let showcase = std::fs::read_to_string(directory.join("out/showcase.html"));
+        let mut files = std::fs::read_dir(directory.join("out"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        assert_eq!(files, ["common-mark.html", "showcase.html"]);
+        assert!(common_mark.unwrap().starts_with("<h1>"));
+        assert!(showcase.unwrap().starts_with("<h1>"));
+    }
+
+    #[test]
+    fn read_options_from_config() {
+        let directory = TempDir::new("config");
+        let path = directory.join("config.toml");
+        std::fs::write(&path, "no_colour = true\n").unwrap();
+        let output = cargo_mdcat()
+            .env("TERM", "xterm-kitty")
+            .arg("--config")
+            .arg(&path)
+            .arg("sample/showcase.md")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(!output.stdout.contains(&b'\x1b'));
+    }
+
+    #[test]
+    fn warn_if_pager_fails_to_start() {
+        let run = |args: &[&str]| {
+            let output = cargo_mdcat()
+                .args(["--paginate", "--pager", "mdcat-no-such-pager"])
+                .args(args)
+                .arg("sample/common-mark.md")
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            assert!(!output.stdout.is_empty());
+            String::from_utf8(output.stderr).unwrap()
+        };
+        let stderr = run(&[]);
+        assert!(
+            stderr.starts_with("Warning: Failed to spawn pager mdcat-no-such-pager"),
+            "Stderr: {stderr}"
+        );
+        assert_eq!(run(&["--quiet"]), "");
+    }
+
+    #[test]
+    fn explicit_arguments_override_mdcat_opts() {
+        let output = cargo_mdcat()
+            .env("MDCAT_OPTS", "--file-separator '<<{file}>>' --no-colour")
+            .args(["--file-separator", "[{file}]"])
+            .args(["sample/common-mark.md", "sample/showcase.md"])
+            .output()
+            .unwrap();
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(stdout.contains("[sample/showcase.md]"), "{stdout}");
+        assert!(!stdout.contains("<<"), "{stdout}");
+    }
+
+    #[test]
+    fn reject_invalid_config() {
+        let directory = TempDir::new("invalid");
+        let path = directory.join("invalid.toml");
+        std::fs::write(&path, "columns = -1\n").unwrap();
+        let output = cargo_mdcat()
+            .arg(format!("--config={}", path.display()))
+            .arg("sample/showcase.md")
+            .output()
+            .unwrap();
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(stderr.contains(&path.display().to_string()), "{stderr}");
+        assert!(stderr.contains("columns"), "{stderr}");
+    }
+
+    #[test]
+    fn dump_events() {
+        let output = render_stdin(["--dump-events"], "# Hello\n\n- [x] ~~done~~\n");
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(
+            stdout.lines().next().unwrap().starts_with("Start(Heading"),
+            "{stdout}"
+        );
+        // Parse with the same extensions as for rendering
+        assert!(stdout.contains("TaskListMarker(true)"), "{stdout}");
+        assert!(stdout.contains("Start(Strikethrough)"), "{stdout}");
+    }
+
+    #[test]
+    fn list_themes() {
+        let output = run_cargo_mdcat(["--list-themes"]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            stdout.lines().collect::<Vec<_>>(),
+            ["dark", "light", "dracula"]
+        );
+    }
+
+    #[test]
+    fn list_languages() {
+        let output = run_cargo_mdcat(["--list-languages"]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(!stdout.is_empty());
+        let lines = stdout.lines().collect::<Vec<_>>();
+        assert!(lines.contains(&"rust: rs"), "Stdout: {stdout}");
+    }
+
+    #[test]
+    fn reject_unknown_theme() {
+        let output = run_cargo_mdcat(["--theme", "solarized", "sample/showcase.md"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains("[possible values: dark, light, dracula]"),
+            "{stderr}"
+        );
+    }
+
+    #[test]
+    fn colour_always_to_file() {
+        let directory = TempDir::new("colour");
+        let path = directory.join("colour.txt");
+        let output = cargo_mdcat()
+            .args(["--color", "always", "--output"])
+            .arg(&path)
+            .arg("sample/showcase.md")
+            .output()
+            .unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(output.status.success());
+        assert!(contents.contains("\x1b["), "Contents: {contents}");
+    }
+
+    #[test]
+    fn colour_never() {
+        let output = cargo_mdcat()
+            .env("TERM", "xterm-kitty")
+            .args(["--color", "never", "sample/showcase.md"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(!output.stdout.contains(&b'\x1b'));
+    }
+
+    #[test]
+    fn line_numbers_with_custom_separator_and_colour() {
+        let args = [
+            "--line-numbers",
+            "--line-number-sep",
+            " | ",
+            "--line-number-color",
+            "red",
+            "sample/showcase.md",
+        ];
+        let output = cargo_mdcat()
+            .env("TERM", "xterm-kitty")
+            .args(["--color", "always"])
+            .args(args)
+            .output()
+            .unwrap();
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(stdout.starts_with("\x1b[31m "), "Stdout: {stdout}");
+        assert!(stdout.contains(" 1 | \x1b[0m"), "Stdout: {stdout}");
+
+        let output = cargo_mdcat()
+            .env("TERM", "xterm-kitty")
+            .args(["--color", "never"])
+            .args(args)
+            .output()
+            .unwrap();
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(!stdout.contains('\x1b'), "Stdout: {stdout}");
+        assert!(stdout.trim_start().starts_with("1 | "), "Stdout: {stdout}");
+    }
+
+    #[test]
+    fn total_stats_of_multiple_files() {
+        let output = run_cargo_mdcat([
+            "--stats",
+            "--stats-format=json",
+            "--file-separator=",
+            "sample/showcase.md",
+            "sample/showcase.md",
+        ]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        let values = stdout
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values.len(), 3, "Stdout: {stdout}");
+        assert_eq!(values[2]["files"], 2);
+        assert_eq!(
+            values[2]["total"]["word_count"],
+            values[0]["word_count"].as_u64().unwrap() * 2
+        );
+
+        // No total for a single file
+        let output = run_cargo_mdcat(["--stats", "sample/showcase.md"]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(!stdout.contains("Total"), "Stdout: {stdout}");
+    }
+
+    #[test]
+    fn check_links() {
+        let directory = TempDir::new("links");
+        std::fs::write(directory.join("existing.md"), "# Existing\n").unwrap();
+        let document = directory.join("document.md");
+        std::fs::write(
+            &document,
+            "# Links\n\nSee [this](existing.md) and\n[that](missing.md).\n",
+        )
+        .unwrap();
+        let check = |args: &[&str]| {
+            cargo_mdcat()
+                .args(["--local", "--check-links"])
+                .args(args)
+                .arg(&document)
+                .output()
+                .unwrap()
+        };
+        let output = check(&[]);
+        let failed = check(&["--fail"]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(stdout.contains(":4: missing.md: "), "Stdout: {stdout}");
+        assert!(!stdout.contains("existing.md"), "Stdout: {stdout}");
+
+        let stdout = std::str::from_utf8(&failed.stdout).unwrap();
+        let stderr = std::str::from_utf8(&failed.stderr).unwrap();
+        assert!(!failed.status.success());
+        assert!(stdout.contains(":4: missing.md: "), "Stdout: {stdout}");
+        assert!(stderr.contains("1 broken link"), "Stderr: {stderr}");
+    }
+
+    #[test]
+    fn check_remote_links_with_head_requests() {
+        let (address, server) = server::serve(vec![Response {
+            status: "404 Not Found",
+            content_type: "text/plain",
+            body: "",
+        }]);
+        let url = format!("http://{address}/missing");
+        let output = render_in_kitty(&format!("See [this]({url}).\n"), &["--check-links"]);
+        let request = &server.join().unwrap()[0][0];
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(request.starts_with("HEAD /missing "), "Request: {request}");
+        assert!(stdout.contains(&format!(":1: {url}: ")), "Stdout: {stdout}");
+    }
+
+    #[test]
+    fn print_resources() {
+        let directory = TempDir::new("resources");
+        let document = directory.join("document.md");
+        std::fs::write(
+            &document,
+            "# Images\n\n![Logo](logo.png)\n\n![Badge](https://example.com/badge.svg)\n",
+        )
+        .unwrap();
+        let print = |args: &[&str]| {
+            let output = cargo_mdcat()
+                .arg("--print-resources")
+                .args(args)
+                .arg(&document)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let remote = print(&[]);
+        let local = print(&["--local"]);
+        let name = document.display();
+        let logo = directory.join("logo.png");
+        let logo = logo.display();
+        assert_eq!(
+            remote,
+            format!(
+                "{name}:3: image local {logo}\n\
+                 {name}:5: image remote https://example.com/badge.svg\n"
+            )
+        );
+        assert_eq!(
+            local,
+            format!(
+                "{name}:3: image local {logo}\n\
+                 {name}:5: image remote https://example.com/badge.svg (would be blocked)\n"
+            )
+        );
+    }
+
+    #[test]
+    fn check_headings() {
+        let check = |args: &[&str]| {
+            render_stdin(
+                ["--check-headings"].iter().chain(args),
+                "# Title\n\nIntro\n\n### Details\n\n## Usage\n",
+            )
+        };
+        let output = check(&[]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert_eq!(stdout, "-:5: warning: heading level 3 skips from level 1\n");
+
+        let output = check(&["--fail"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr.contains("1 heading warning"), "Stderr: {stderr}");
+    }
+
+    #[test]
+    fn strict_markdown() {
+        let check = |args: &[&str]| render_stdin(args, "# Title\n\nSome items:\n- one\n- two\n");
+        for flag in ["--strict-markdown", "--strict"] {
+            let output = check(&[flag]);
+            let stdout = std::str::from_utf8(&output.stdout).unwrap();
+            assert!(output.status.success());
+            assert_eq!(stdout, "-:4: warning: no blank line before list\n");
+        }
+
+        let output = check(&["--strict-markdown", "--fail"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr.contains("1 markdown warning"), "Stderr: {stderr}");
+    }
+
+    #[test]
+    fn expand_tabs_with_tab_width() {
+        let output = render_stdin(
+            ["--no-colour", "--tab-width", "4"],
+            "Some\ttext\n\n```\n\tindented\n```\n",
+        );
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert!(stdout.contains("\n    indented\n"), "Stdout: {stdout:?}");
+        assert!(!stdout.contains("\tindented"), "Stdout: {stdout:?}");
+    }
+
+    #[test]
+    fn wrap_code() {
+        let render = |mode: &str| {
+            let output = render_stdin(
+                ["--no-colour", "--columns", "20", "--wrap-code", mode],
+                &format!("```\n{}\n```\n", "0123456789".repeat(3)),
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let border = "\u{2500}".repeat(20);
+        assert_eq!(
+            render("off"),
+            format!("{border}\n012345678901234567890123456789\n{border}\n")
+        );
+        assert_eq!(
+            render("none"),
+            format!("{border}\n0123456789012345678\u{2026}\n{border}\n")
+        );
+        assert_eq!(
+            render("char"),
+            format!("{border}\n01234567890123456789\n  0123456789\n{border}\n")
+        );
+    }
+
+    #[test]
+    fn dry_run() {
+        let output = run_cargo_mdcat(["--local", "--dry-run", "sample/showcase.md"]);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+
+        let output = render_stdin(
+            ["--dry-run", "--fail"],
+            "# Missing\n\n![image](missing-image.png)\n",
+        );
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(stderr.contains("missing-image.png"), "Stderr: {stderr}");
+    }
+
+    #[test]
+    fn base_dir() {
+        let dry_run = |args: &[&str]| {
+            render_stdin(
+                ["--dry-run", "--local"].iter().chain(args),
+                "# Logo\n\n![logo](rust-logo.svg)\n",
+            )
+        };
+        let output = dry_run(&["--base-dir", "sample"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(output.status.success(), "Stderr: {stderr}");
+        // Without a base directory mdcat looks for the image in the working directory
+        let output = dry_run(&[]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(stderr.contains("rust-logo.svg"), "Stderr: {stderr}");
+
+        let output = run_cargo_mdcat(["--base-dir", "sample/showcase.md", "sample/showcase.md"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(stderr.contains("is not a directory"), "Stderr: {stderr}");
+    }
+
+    #[test]
+    fn render_timeout() {
+        let directory = TempDir::new("timeout");
+        let path = directory.join("slow.md");
+        let render = |args: &[&str]| {
+            // Every image takes a while to fetch, so rendering all images takes 4 seconds
+            let (address, server) =
+                server::serve_with_delay(Response::image("200 OK"), Duration::from_millis(200));
+            let images = (0..20)
+                .map(|n| format!("![Image {n}](http://{address}/{n}.png)\n\n"))
+                .collect::<String>();
+            std::fs::write(&path, images).unwrap();
+            let output = run_with_stdin(
+                without_proxy(&mut cargo_mdcat())
+                    .env("TERM", "xterm-kitty")
+                    .args(["--render-timeout", "0.5"])
+                    .args(args)
+                    .arg(&path)
+                    .arg("-"),
+                "Next document\n",
+            );
+            (output, server.join().unwrap())
+        };
+
+        for args in [&[][..], &["--line-numbers"]] {
+            let (output, served) = render(args);
+            let stdout = std::str::from_utf8(&output.stdout).unwrap();
+            let stderr = std::str::from_utf8(&output.stderr).unwrap();
+            assert!(output.status.success(), "Stderr: {stderr}");
+            assert!(
+                stderr.contains("Rendering took longer than"),
+                "Stderr: {stderr}"
+            );
+            // Stop rendering at the deadline instead of fetching all images
+            assert!(served < 10, "Served {served} images with {args:?}");
+            // Discard all output of the document, but still continue with the next one
+            assert!(!stdout.contains("Image"), "Stdout: {stdout}");
+            assert!(stdout.contains("Next document"), "Stdout: {stdout}");
+        }
+
+        let (output, _) = render(&["--fail"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(stderr.contains("Error:"), "Stderr: {stderr}");
+        assert!(output.stdout.is_empty());
+    }
+
+    #[test]
+    fn progress_as_json_lines() {
+        let output = run_cargo_mdcat([
+            "--progress",
+            "json",
+            "sample/showcase.md",
+            "sample/does-not-exist.md",
+        ]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(!output.stdout.is_empty());
+        let lines = stderr
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2, "Stderr: {stderr}");
+        assert_eq!(lines[0]["file"], "sample/showcase.md");
+        assert_eq!(lines[0]["status"], "ok");
+        assert!(0 < lines[0]["bytes"].as_u64().unwrap());
+        assert!(lines[0]["duration_ms"].is_u64());
+        assert!(lines[0].get("error").is_none());
+        assert_eq!(lines[1]["file"], "sample/does-not-exist.md");
+        assert_eq!(lines[1]["status"], "error");
+        assert!(lines[1]["error"].is_string());
+    }
+
+    #[test]
+    fn no_tables_renders_pipe_tables_as_text() {
+        let table = "| a | b |\n|---|---|\n| c | d |\n";
+        let render = |args: &[&str]| {
+            let output = render_stdin(["--no-colour"].iter().chain(args), table);
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let stdout = render(&["--no-tables"]);
+        assert!(stdout.contains("| a | b |"), "Stdout: {stdout:?}");
+        assert!(!stdout.contains('─'), "Stdout: {stdout:?}");
+        let stdout = render(&[]);
+        assert!(!stdout.contains("| a | b |"), "Stdout: {stdout:?}");
+        assert!(stdout.contains('─'), "Stdout: {stdout:?}");
+    }
+
+    #[test]
+    fn smart_punctuation() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--no-colour"].iter().chain(args),
+                "Say \"quoted\"... `\"code\"`\n\n```\n\"block\"\n```\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let stdout = render(&["--smart-punctuation"]);
+        assert!(
+            stdout.contains("Say \u{201c}quoted\u{201d}\u{2026} \"code\""),
+            "Stdout: {stdout:?}"
+        );
+        assert!(stdout.contains("\n\"block\"\n"), "Stdout: {stdout:?}");
+        let stdout = render(&[]);
+        assert!(
+            stdout.contains("Say \"quoted\"... \"code\""),
+            "Stdout: {stdout:?}"
+        );
+    }
+
+    #[test]
+    fn list_anchors() {
+        let output = render_stdin(
+            ["--list-anchors"],
+            "# Title\n\nText\n\n## Title\n\n## Using `mdcat`!\n",
+        );
+        assert!(output.status.success());
+        assert_eq!(
+            std::str::from_utf8(&output.stdout).unwrap(),
+            "#title\tTitle\n#title-1\tTitle\n#using-mdcat\tUsing mdcat!\n"
+        );
+    }
+
+    // Uses standard Unix tools as highlighters
+    #[cfg(unix)]
+    #[test]
+    fn external_highlighter() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--ansi"].iter().chain(args),
+                "```rust\nfn main() {}\n```\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let builtin = render(&[]);
+        assert!(!builtin.contains("fn main() {}"), "Stdout: {builtin:?}");
+        // cat leaves the code as it is, so no highlighting happens at all
+        let stdout = render(&["--highlighter", "cat"]);
+        assert!(stdout.contains("fn main() {}\n"), "Stdout: {stdout:?}");
+        // Fall back to built-in highlighting if the highlighter fails
+        assert_eq!(render(&["--highlighter", "false"]), builtin);
+    }
+
+    #[test]
+    fn stats_summary() {
+        let output = render_stdin(
+            ["--stats-summary", "--reading-speed", "5"],
+            "# Title\n\nOne two three four five six.\n",
+        );
+        assert!(output.status.success());
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(stdout.contains("words="), "Stdout: {stdout:?}");
+        assert_eq!(
+            stdout.find('\n'),
+            Some(stdout.len() - 1),
+            "Stdout: {stdout:?}"
+        );
+        assert_eq!(stdout, "words=7 read=2m headings=1\n");
+    }
+
+    #[test]
+    fn stats_since_git() {
+        let directory = TempDir::new("git");
+        let repository = directory.join("repository");
+        std::fs::create_dir_all(&repository).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=mdcat",
+                    "-c",
+                    "user.email=mdcat@example.com",
+                ])
+                .arg("-C")
+                .arg(&repository)
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "--quiet"]);
+        std::fs::write(repository.join("tracked.md"), "Old words.\n").unwrap();
+        git(&["add", "tracked.md"]);
+        git(&["commit", "--quiet", "-m", "Initial commit"]);
+        std::fs::write(
+            repository.join("tracked.md"),
+            "Old words.\n\nNew words here.\n",
+        )
+        .unwrap();
+        std::fs::write(repository.join("untracked.md"), "All new words.\n").unwrap();
+        std::fs::write(directory.join("outside.md"), "Not in git.\n").unwrap();
+
+        let words = |path: std::path::PathBuf| {
+            let output = cargo_mdcat()
+                .args(["--stats-summary", "--since-git", "HEAD"])
+                .arg(path)
+                .output()
+                .unwrap();
+            let stderr = std::str::from_utf8(&output.stderr).unwrap();
+            assert!(output.status.success(), "Stderr: {stderr}");
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let tracked = words(repository.join("tracked.md"));
+        let untracked = words(repository.join("untracked.md"));
+        let outside = words(directory.join("outside.md"));
+        assert!(tracked.starts_with("words=3 "), "{tracked}");
+        assert!(untracked.starts_with("words=3 "), "{untracked}");
+        assert!(outside.starts_with("words=3 "), "{outside}");
+    }
+
+    #[test]
+    fn count_only() {
+        let output = run_cargo_mdcat(["--count-only", "sample/common-mark.md"]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(
+            output.status.success(),
+            "Stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(stdout.contains("Words: "), "Stdout: {stdout}");
+        assert!(stdout.contains("Lines: "), "Stdout: {stdout}");
+        assert!(!stdout.contains("Headings:"), "Stdout: {stdout}");
+
+        let output = run_cargo_mdcat(["--count-only", "--stats-sections", "sample/common-mark.md"]);
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn includes() {
+        let directory = TempDir::new("includes");
+        std::fs::write(directory.join("main.md"), "Main\n\n{{include: part.md}}\n").unwrap();
+        std::fs::write(directory.join("part.md"), "Included part\n").unwrap();
+        std::fs::write(directory.join("broken.md"), "{{include: missing.md}}\n").unwrap();
+        let run = |args: &[&str], file: &str| {
+            cargo_mdcat()
+                .args(["--no-colour", "--enable-includes"])
+                .args(args)
+                .arg(directory.join(file))
+                .output()
+                .unwrap()
+        };
+        let included = run(&[], "main.md");
+        let missing = run(&[], "broken.md");
+        let failed = run(&["--fail"], "broken.md");
+
+        assert!(included.status.success());
+        assert!(std::str::from_utf8(&included.stdout)
+            .unwrap()
+            .contains("Included part"));
+        assert!(missing.status.success());
+        assert!(std::str::from_utf8(&missing.stdout)
+            .unwrap()
+            .contains("{{include: missing.md}}"));
+        assert_eq!(failed.status.code(), Some(4));
+        assert!(std::str::from_utf8(&failed.stderr)
+            .unwrap()
+            .contains("Failed to include missing.md"));
+    }
+
+    #[test]
+    fn front_matter_as_table() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--no-colour"].iter().chain(args),
+                "---\ntitle: Hi\n---\n# Body\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let stdout = render(&["--frontmatter-as-table"]);
+        assert!(
+            stdout
+                .lines()
+                .any(|line| line.split_whitespace().eq(["title", "Hi"])),
+            "Stdout: {stdout}"
+        );
+        assert!(stdout.contains("Body"), "Stdout: {stdout}");
+        assert!(!render(&[]).contains("title"));
+    }
+
+    #[test]
+    fn no_hyperlinks() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--ansi"].iter().chain(args),
+                "See [mdcat](https://example.com/mdcat).\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        // OSC 8 hyperlinks start with ESC ] 8
+        assert!(render(&[]).contains("\x1b]8;;https://example.com/mdcat"));
+        let stdout = render(&["--no-hyperlinks"]);
+        assert!(!stdout.contains("\x1b]8;"), "Stdout: {stdout:?}");
+        assert!(
+            stdout.contains("https://example.com/mdcat"),
+            "Stdout: {stdout:?}"
+        );
+    }
+
+    #[test]
+    fn emoji() {
+        let output = render_stdin(
+            ["--no-colour", "--emoji"],
+            "Launch :rocket: with `:rocket:` at :unknown:\n",
+        );
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "Launch 🚀 with :rocket: at :unknown:\n");
+    }
+
+    #[test]
+    fn max_input_size() {
+        let render = |max_size: &str| {
+            let mut child = cargo_mdcat()
+                .args(["--no-colour", "--max-input-size", max_size])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut stdin = child.stdin.take().unwrap();
+            // Write from a thread, because mdcat stops reading at the limit
+            let writer = std::thread::spawn(move || {
+                let _ = stdin.write_all("Some text.\n".repeat(200).as_bytes());
+            });
+            let output = child.wait_with_output().unwrap();
+            writer.join().unwrap();
+            output
+        };
+        let output = render("1K");
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        assert!(
+            stderr.contains("Input exceeds the maximum input size of 1024 bytes"),
+            "Stderr: {stderr}"
+        );
+
+        let output = render("4K");
+        assert!(output.status.success());
+        assert!(std::str::from_utf8(&output.stdout)
+            .unwrap()
+            .starts_with("Some text."));
+    }
+
+    #[test]
+    fn task_progress() {
+        let output = render_stdin(
+            ["--no-colour", "--task-progress"],
+            "- [x] one\n- [ ] two\n- [x] three\n- [ ] four\n",
+        );
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.ends_with("\n[████░░░░] 50%\n"), "Stdout: {stdout:?}");
+    }
+
+    #[test]
+    fn detect_terminal_json() {
+        let output = run_cargo_mdcat(["--detect-terminal", "--format", "json"]);
+        assert!(output.status.success());
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(info["terminal"].is_string(), "{info}");
+        assert!(info["images"].is_boolean(), "{info}");
+        assert!(info["hyperlinks"].is_boolean(), "{info}");
+        assert!(info["truecolor"].is_boolean(), "{info}");
+
+        let output = run_cargo_mdcat(["--format", "json"]);
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn math() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(["--ansi"].iter().chain(args), "Square $x^2$.\n");
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(render(&[]), "Square $x^2$.\n");
+        let stdout = render(&["--math"]);
+        assert!(!stdout.contains('$'), "Stdout: {stdout:?}");
+        // Italic cyan
+        assert!(
+            stdout.contains("\x1b[3m\x1b[36m x^2\x1b[0m"),
+            "Stdout: {stdout:?}"
+        );
+    }
+
+    #[test]
+    fn stats_output() {
+        let output = run_cargo_mdcat([
+            "--no-colour",
+            "--stats-summary",
+            "--stats-output",
+            "-",
+            "sample/common-mark.md",
+            "sample/showcase.md",
+        ]);
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(output.status.success(), "Stderr: {stderr}");
+        assert!(stdout.contains("CommonMark sample document"));
+        assert!(!stdout.contains("words="), "Stdout: {stdout}");
+        // One line for each file, and the total
+        assert_eq!(
+            stderr
+                .lines()
+                .filter(|line| line.contains("words="))
+                .count(),
+            3,
+            "Stderr: {stderr}"
+        );
+
+        let directory = TempDir::new("stats");
+        let path = directory.join("stats.txt");
+        let output = cargo_mdcat()
+            .args(["--no-colour", "--stats", "--stats-output"])
+            .arg(&path)
+            .arg("sample/common-mark.md")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(stdout.contains("CommonMark sample document"));
+        assert!(!stdout.contains("Document Statistics:"));
+        let stats = std::fs::read_to_string(&path).unwrap();
+        assert!(stats.contains("Document Statistics:"), "Stats: {stats}");
+        // Without statistics there is nothing to write to the statistics output
+        let output = run_cargo_mdcat(["--stats-output", "-", "sample/common-mark.md"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(2), "Stderr: {stderr}");
+        assert!(stderr.contains("--stats-output"), "Stderr: {stderr}");
+    }
+
+    #[test]
+    fn render_to_html() {
+        let output = render_stdin(["--to", "html"], "# Title\n\nSee [the logo](logo.png).\n");
+        assert!(output.status.success());
+        assert_eq!(
+            std::str::from_utf8(&output.stdout).unwrap(),
+            "<h1>Title</h1>\n<p>See <a href=\"logo.png\">the logo</a>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn line_numbers_with_crlf_line_endings() {
+        let render = |args: &[&str], input: &str| {
+            let output = render_stdin(["--no-colour"].iter().chain(args), input);
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let stdout = render(&["--line-numbers", "--line-number-sep", " "], "a\r\nb\r\n");
+        assert!(!stdout.contains('\r'), "Stdout: {stdout:?}");
+        assert_eq!(stdout, "1 a b\n");
+        let stdout = render(
+            &["--line-numbers", "--line-number-sep", " "],
+            "a\r\n\r\n<div>\r\nb\r\n</div>\r\n",
+        );
+        assert!(!stdout.contains('\r'), "Stdout: {stdout:?}");
+        assert!(stdout.contains("\n4 b\n"), "Stdout: {stdout:?}");
+        assert_eq!(
+            render(&["--stats"], "a\r\nb\r\n"),
+            render(&["--stats"], "a\nb\n")
+        );
+    }
+
+    #[test]
+    fn list_indent() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(["--no-colour"].iter().chain(args), "- a\n  - b\n\n1. one\n");
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(render(&[]), "• a\n  • b\n\n 1. one\n");
+        // The text of the second level starts at 2 * 4 columns
+        assert_eq!(
+            render(&["--list-indent", "4"]),
+            "•   a\n    •   b\n\n 1. one\n"
+        );
+        // Never indent by less than the list marker
+        assert_eq!(render(&["--list-indent", "1"]), render(&[]));
+        let output = run_cargo_mdcat(["--list-indent", "9", "sample/showcase.md"]);
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn horizontal_rule_char_and_width() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--no-colour", "--columns", "20"].iter().chain(args),
+                "a\n\n---\n\nb\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(render(&[]), format!("a\n\n{}\n\nb\n", "═".repeat(20)));
+        assert_eq!(
+            render(&["--hr-char", "="]),
+            format!("a\n\n{}\n\nb\n", "=".repeat(20))
+        );
+        assert_eq!(
+            render(&["--hr-char", "─", "--hr-width", "half"]),
+            format!("a\n\n{}\n\nb\n", "─".repeat(10))
+        );
+        // Wide glyphs take two columns each
+        assert_eq!(
+            render(&["--hr-char", "＝"]),
+            format!("a\n\n{}\n\nb\n", "＝".repeat(10))
+        );
+    }
+
+    #[test]
+    fn literal_source_with_line_numbers() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--colour", "always", "--literal"].iter().chain(args),
+                "# Title\n\n*Emphasis* and `code`\n\n- [link](https://example.com)\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(
+            render(&[]),
+            "# Title\n\n*Emphasis* and `code`\n\n- [link](https://example.com)\n"
+        );
+        assert_eq!(
+            render(&["--line-numbers", "--line-number-sep", " "]),
+            "1 # Title\n2 \n3 *Emphasis* and `code`\n4 \n5 - [link](https://example.com)\n"
+        );
+        assert_eq!(
+            render(&["--line-numbers", "--line-number-sep", " ", "--lines", "3-"]),
+            "3 *Emphasis* and `code`\n4 \n5 - [link](https://example.com)\n"
+        );
+        assert_eq!(
+            render(&["--to", "terminal"]),
+            "# Title\n\n*Emphasis* and `code`\n\n- [link](https://example.com)\n"
+        );
+        for analysis in [
+            &["--stats"][..],
+            &["--stats-summary"],
+            &["--count-only"],
+            &["--since-git", "HEAD"],
+            &["--to", "html"],
+            &["--toc"],
+            &["--check-links"],
+            &["--section", "Usage"],
+            &["--dry-run"],
+            &["--max-heading-level", "2"],
+            &["--dump-events"],
+        ] {
+            let output = run_cargo_mdcat([&["--literal"], analysis, &["README.md"]].concat());
+            let stderr = std::str::from_utf8(&output.stderr).unwrap();
+            assert_eq!(output.status.code(), Some(2), "{analysis:?}");
+            assert!(stderr.contains("cannot be used with"), "Stderr: {stderr}");
+        }
+    }
+
+    #[test]
+    fn prose_width_wraps_paragraphs_but_not_tables() {
+        let output = render_stdin(
+            ["--no-colour", "--columns", "60", "--prose-width", "30"],
+            &format!(
+                "{}\n\n| First column | Second column | Third column |\n|---|---|---|\n| one | two | three |\n",
+                "word ".repeat(30)
+            ),
+        );
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let (paragraph, table) = stdout.split_once("\n\n").unwrap();
+        assert!(1 < paragraph.lines().count(), "Stdout: {stdout:?}");
+        for line in paragraph.lines() {
+            assert!(line.chars().count() <= 30, "Line too long: {line:?}");
+        }
+        assert!(
+            table.lines().any(|line| 30 < line.chars().count()),
+            "Stdout: {stdout:?}"
+        );
+    }
+
+    #[test]
+    fn definition_lists() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--no-colour"].iter().chain(args),
+                "Apple\n: A red fruit.\n: A company.\n\nBanana\n: A yellow fruit.\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(
+            render(&["--definition-lists"]),
+            "Apple\n    A red fruit.\n    A company.\nBanana\n    A yellow fruit.\n"
+        );
+        assert!(render(&[]).contains(": A red fruit."));
+    }
+
+    #[test]
+    fn exit_codes() {
+        let output = run_cargo_mdcat(["does-not-exist"]);
+        assert_eq!(output.status.code(), Some(4));
+        let output = run_cargo_mdcat(["does-not-exist", "sample/common-mark.md"]);
+        assert_eq!(output.status.code(), Some(4));
+        let output = run_cargo_mdcat(["--fail", "does-not-exist"]);
+        assert_eq!(output.status.code(), Some(4));
+        let output = run_cargo_mdcat(["--no-such-flag"]);
+        assert_eq!(output.status.code(), Some(2));
+        let output = run_cargo_mdcat(["sample/common-mark.md"]);
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    #[test]
+    fn render_section() {
+        let render = |args: &[&str]| {
+            render_stdin(
+                ["--no-colour"].iter().chain(args),
+                "# Tool\n\nIntro\n\n## Installation\n\nInstall it.\n\n\
+                 ### From source\n\nBuild it.\n\n## Usage\n\nUse it.\n\n> ## Quoted\n",
+            )
+        };
+        let output = render(&["--section", " installation "]);
+        assert!(output.status.success());
+        assert_eq!(
+            std::str::from_utf8(&output.stdout).unwrap(),
+            "┄┄Installation\n\nInstall it.\n\n┄┄┄From source\n\nBuild it.\n"
+        );
+
+        let output = render(&["--section", "Missing"]);
+        assert!(!output.status.success());
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(
+            stderr.contains("No section titled Missing"),
+            "Stderr: {stderr}"
+        );
+
+        // Sections only start at top-level headings which mdcat renders
+        for args in [
+            &["--section", "Quoted"][..],
+            &["--max-heading-level", "2", "--section", "From source"],
+        ] {
+            let output = render(args);
+            let stderr = std::str::from_utf8(&output.stderr).unwrap();
+            assert!(!output.status.success(), "Args: {args:?}");
+            assert!(stderr.contains("No section titled"), "Stderr: {stderr}");
+        }
+    }
+
+    #[test]
+    fn broken_pipe() {
+        let render = |args: &[&str]| {
+            let mut child = cargo_mdcat()
+                .arg("--no-colour")
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+            // Close our end of the pipe right away, to make every write of mdcat fail
+            drop(child.stdout.take());
+            let mut stdin = child.stdin.take().unwrap();
+            // Write more than fits into the buffer of a pipe
+            write!(stdin, "{}", "Some text to render.\n\n".repeat(10_000)).unwrap();
+            drop(stdin);
+            child.wait_with_output().unwrap()
+        };
+
+        let output = render(&[]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(output.status.success(), "Stderr: {stderr}");
+        assert!(stderr.is_empty(), "Stderr: {stderr}");
+
+        let output = render(&["--strict-pipe"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(stderr.contains("Broken pipe"), "Stderr: {stderr}");
+    }
+
+    #[test]
+    fn strip_comments() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--no-colour"].iter().chain(args),
+                "Before <!-- hidden --> after\n\n<!-- hidden -->\n\nEnd\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let stdout = render(&["--strip-comments"]);
+        assert!(!stdout.contains("hidden"), "Stdout: {stdout:?}");
+        assert_eq!(stdout, "Before  after\n\nEnd\n");
+        assert!(render(&[]).contains("<!-- hidden -->"));
+    }
+
+    #[test]
+    fn hide_deep_sections_with_max_heading_level() {
+        let output = render_stdin(
+            ["--no-colour", "--max-heading-level", "2"],
+            "Preamble\n\n# One\n\nFirst\n\n## Two\n\nSecond\n\n### Three\n\nThird\n\n## Four\n\nFourth\n",
+        );
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        for visible in [
+            "Preamble", "One", "First", "Two", "Second", "Four", "Fourth",
+        ] {
+            assert!(stdout.contains(visible), "{visible} missing: {stdout}");
+        }
+        assert!(!stdout.contains("Three"), "{stdout}");
+        assert!(!stdout.contains("Third"), "{stdout}");
+    }
+
+    #[test]
+    fn strip_front_matter() {
+        let render = |args: &[&str]| {
+            let output = render_stdin(
+                ["--no-colour"].iter().chain(args),
+                "---\ntitle: Hello\ndraft: true\n---\n# Document\n",
+            );
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let stdout = render(&[]);
+        assert_eq!(stdout, "\u{2504}Document\n");
+        let stdout = render(&["--show-front-matter"]);
+        assert_eq!(
+            stdout,
+            "Front matter (YAML): title, draft\n\n\u{2504}Document\n"
+        );
+    }
+
+    #[test]
+    fn render_images_as_alt_text() {
+        let output = render_in_kitty(
+            "An ![Alt text](sample/rust-logo.svg) image\n",
+            &["--no-images", "--ansi"],
+        );
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert_eq!(stdout, "An Alt text image\n");
+    }
+
+    #[test]
+    fn read_remote_input() {
+        let (url, server) = serve_once("# Remote document\n\nServed over HTTP.\n");
+        let output = without_proxy(&mut cargo_mdcat())
+            .args(["--no-colour", &url])
+            .output()
+            .unwrap();
+        server.join().unwrap();
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(output.status.success(), "Stderr: {stderr}");
+        assert!(
+            stdout.contains("\u{2504}Remote document"),
+            "Stdout: {stdout}"
+        );
+        assert!(stdout.contains("Served over HTTP."), "Stdout: {stdout}");
+    }
+
+    #[test]
+    fn read_remote_input_through_proxy() {
+        // The proxy receives the request for the remote host and serves the document
+        let (proxy, server) = serve_once("# Proxied document\n");
+        let output = without_proxy(&mut cargo_mdcat())
+            .args([
+                "--no-colour",
+                "--proxy",
+                &proxy,
+                "http://mdcat.invalid/README.md",
+            ])
+            .output()
+            .unwrap();
+        server.join().unwrap();
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(output.status.success(), "Stderr: {stderr}");
+        assert!(
+            stdout.contains("\u{2504}Proxied document"),
+            "Stdout: {stdout}"
+        );
+    }
+
+    #[test]
+    fn remote_input_with_includes() {
+        let (url, server) = serve_once("# Remote document\n");
+        let output = without_proxy(&mut cargo_mdcat())
+            .args(["--no-colour", "--enable-includes", &url])
+            .output()
+            .unwrap();
+        server.join().unwrap();
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(output.status.success(), "Stderr: {stderr}");
+        let output = run_cargo_mdcat(["--enable-includes", "--sandbox", "README.md"]);
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn no_cache_overrides_config() {
+        let config = TempDir::new("cache-config");
+        std::fs::write(config.join("config.toml"), "cache = true\n").unwrap();
+        let render = |args: &[&str]| {
+            let home = TempDir::new("cache-home");
+            let (address, server) = server::serve(vec![Response::image("200 OK")]);
+            let output = run_with_stdin(
+                without_proxy(&mut cargo_mdcat())
+                    .env("TERM", "xterm-kitty")
+                    .env("HOME", home.path())
+                    .env("XDG_CACHE_HOME", home.path())
+                    .env("LOCALAPPDATA", home.path())
+                    .arg("--config")
+                    .arg(config.join("config.toml"))
+                    .args(args),
+                &format!("![image](http://{address}/image.png)\n"),
+            );
+            server.join().unwrap();
+            let stderr = std::str::from_utf8(&output.stderr).unwrap();
+            assert!(output.status.success(), "Stderr: {stderr}");
+            std::fs::read_dir(home.path()).unwrap().count()
+        };
+        assert_ne!(render(&[]), 0);
+        assert_eq!(render(&["--no-cache"]), 0);
+    }
+
+    #[test]
+    fn do_not_cache_remote_input() {
+        let directory = TempDir::new("cache");
+        let cache_dir = directory.join("cache");
+        let (url, server) = serve_once("# Remote document\n");
+        let output = without_proxy(&mut cargo_mdcat())
+            .args(["--no-colour", "--cache-dir"])
+            .arg(&cache_dir)
+            .arg(&url)
+            .output()
+            .unwrap();
+        server.join().unwrap();
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(output.status.success(), "Stderr: {stderr}");
+        // Only images go to the cache
+        let entries = std::fs::read_dir(&cache_dir).map_or(0, |entries| entries.count());
+        assert_eq!(entries, 0);
+    }
+
+    #[test]
+    fn refuse_remote_input_with_local() {
+        let output = run_cargo_mdcat(["--local", "http://127.0.0.1:1/README.md"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains("remote resources are disabled"),
+            "Stderr: {stderr}"
+        );
+    }
+
+    #[test]
+    fn skip_image_on_resource_timeout() {
+        let (url, server) = serve_slowly();
+        let output = render_in_kitty(
+            &format!("![Slow image]({url})"),
+            &["--resource-timeout", "1"],
+        );
+        server.join().unwrap();
+        let stdout = std::str::from_utf8(&output.stdout).unwrap();
+        assert!(output.status.success());
+        // The image falls back to its description
+        assert!(stdout.contains("Slow image"), "Stdout: {stdout}");
+    }
+
+    #[test]
+    fn fail_on_resource_timeout_with_fail() {
+        let (url, server) = serve_slowly();
+        let output = render_in_kitty(
+            &format!("![Slow image]({url})"),
+            &["--resource-timeout", "1", "--fail"],
+        );
+        server.join().unwrap();
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains(&format!("Timed out reading resource {url}")),
+            "Stderr: {stderr}"
+        );
+    }
+
+    #[test]
+    fn reject_zero_reading_speed() {
+        let output = run_cargo_mdcat(["--stats", "--reading-speed", "0", "sample/common-mark.md"]);
+        let stderr = std::str::from_utf8(&output.stderr).unwrap();
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains("reading speed must be greater than zero"),
+            "Stderr: {stderr}",
+        );
+    }
+
     #[test]
     fn ignore_broken_pipe() {
         let mut child = cargo_mdcat()
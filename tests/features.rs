@@ -4,19 +4,36 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use mdcat::stats::DocumentStats;
+use std::num::NonZeroUsize;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use mdcat::args::Columns;
+use mdcat::output::Output;
+use mdcat::resources::{CachingResourceHandler, CurlOptions, CurlResourceHandler};
+use mdcat::stats::{format_document_stats, DocumentStats, StatsOptions};
+use mdcat::{effective_columns, expand_globs, ExpandedFilenames, LineRange, DEFAULT_COLUMNS};
+use pulldown_cmark_mdcat::resources::NoopResourceHandler;
+use pulldown_cmark_mdcat::ResourceUrlHandler;
+use url::Url;
+
+mod server;
+
+use server::Response;
 
 #[test]
 fn test_document_stats() {
     let content = "# Test Document\n\nThis is a **test** document with [a link](http://example.com).\n\n```rust\nfn main() {\n    println!(\"Hello, world!\");\n}\n```\n\n## Another heading\n\n- List item 1\n- List item 2\n\n1. Ordered item 1\n2. Ordered item 2";
-    
+
     let stats = DocumentStats::from_markdown(content);
-    
+
     // Test basic counts
     assert!(stats.character_count > 0);
+    assert!(stats.byte_count > stats.character_count);
+    assert_eq!(stats.grapheme_count, None);
     assert!(stats.word_count > 0);
     assert!(stats.line_count > 0);
-    
+
     // Test structural elements
     assert_eq!(stats.heading_count, 2); // # Test Document and ## Another heading
     assert_eq!(stats.code_block_count, 1); // The rust code block
@@ -24,15 +41,19 @@ fn test_document_stats() {
     assert_eq!(stats.image_count, 0); // No images
     assert_eq!(stats.list_count, 2); // One unordered list, one ordered list
     assert_eq!(stats.table_count, 0); // No tables
-    
+    assert_eq!(stats.blockquote_count, 0); // No block quotes
+    assert_eq!(stats.footnote_count, 0); // No footnotes
+
     // Test reading time calculation
     let reading_time = stats.reading_time_minutes();
     assert!(reading_time > 0);
-    
+
     // Test formatting
     let formatted = stats.format();
     assert!(formatted.contains("Document Statistics:"));
-    assert!(formatted.contains("Characters:"));
+    assert!(formatted.contains("Bytes (source):"));
+    assert!(formatted.contains("Characters (text):"));
+    assert!(!formatted.contains("Characters (graphemes):"));
     assert!(formatted.contains("Words:"));
     assert!(formatted.contains("Lines:"));
     assert!(formatted.contains("Headings:"));
@@ -41,30 +62,209 @@ fn test_document_stats() {
     assert!(formatted.contains("Images:"));
     assert!(formatted.contains("Lists:"));
     assert!(formatted.contains("Tables:"));
+    assert!(formatted.contains("Block quotes:"));
+    assert!(formatted.contains("Footnotes:"));
     assert!(formatted.contains("Estimated reading time:"));
 }
 
+#[test]
+fn test_document_stats_definition_lists() {
+    let content = "Apple\n: A red fruit.\n\nText\n\nBanana\n: A yellow fruit.\n: A plant.\n";
+
+    let stats = DocumentStats::builder()
+        .definition_lists(true)
+        .build()
+        .analyze(content);
+    assert_eq!(stats.definition_list_count, 2);
+    assert_eq!(stats.list_count, 0);
+    assert!(stats.format().contains("Definition lists: 2"));
+
+    // Like rendering, statistics do not parse definition lists by default
+    let stats = DocumentStats::from_markdown(content);
+    assert_eq!(stats.definition_list_count, 0);
+}
+
+#[test]
+fn test_document_stats_blockquotes_and_footnotes() {
+    let content = "# Notes\n\n> A quote\n> over two lines.\n\nSome claim.[^1]\n\n> Another quote\n\n[^1]: The footnote definition.\n";
+
+    let stats = DocumentStats::from_markdown(content);
+
+    assert_eq!(stats.blockquote_count, 2);
+    assert_eq!(stats.footnote_count, 1);
+    let formatted = stats.format();
+    assert!(formatted.contains("Block quotes: 2"));
+    assert!(formatted.contains("Footnotes: 1"));
+}
+
+#[test]
+fn test_document_stats_count_footnotes_with_render_extensions() {
+    let content = "Some claim.[^1]\n\n[^1]: The footnote definition.\n";
+
+    let stats = mdcat::stats::document_stats(content, &StatsOptions::default());
+
+    assert_eq!(stats.footnote_count, 1);
+    assert_eq!(stats.link_count, 0);
+}
+
+#[test]
+fn test_word_count_ignores_markup() {
+    assert_eq!(DocumentStats::from_markdown("- **hi** there").word_count, 2);
+    assert_eq!(
+        DocumentStats::from_markdown("| a | b |\n|---|---|\n| c | d |\n").word_count,
+        4
+    );
+    // Inline markup within a word does not split the word
+    assert_eq!(
+        DocumentStats::from_markdown("un*believ*able `code`").word_count,
+        2
+    );
+    // Headings, paragraphs and list items separate words
+    assert_eq!(
+        DocumentStats::from_markdown("# Title\nText\n- one\n- two").word_count,
+        4
+    );
+    // The byte count covers the raw source, the character count only the text
+    let stats = DocumentStats::from_markdown("- **hi** there");
+    assert_eq!(stats.byte_count, 14);
+    assert_eq!(stats.character_count, 8);
+}
+
+#[test]
+fn test_task_completion() {
+    let content = "# Todo\n\n- [x] Write code\n- [ ] Write tests\n- [X] Review\n  - [ ] Nested\n- Not a task\n\n1. [ ] Release\n2. [ ] Announce\n3. [x] Celebrate\n";
+    let stats = DocumentStats::from_markdown(content);
+    assert_eq!(stats.tasks_total, 7);
+    assert_eq!(stats.tasks_done, 3);
+    assert_eq!(stats.tasks_percentage(), Some(43));
+    assert!(stats.format().contains("Tasks: 3/7 (43%)\n"));
+}
+
+#[test]
+fn test_no_tasks() {
+    let stats = DocumentStats::from_markdown("- one\n- two\n");
+    assert_eq!(stats.tasks_total, 0);
+    assert_eq!(stats.tasks_percentage(), None);
+    assert!(!stats.format().contains("Tasks:"));
+}
+
+#[test]
+fn test_reading_time_at_custom_speed() {
+    let content = "word ".repeat(450);
+    let stats = DocumentStats::from_markdown(&content);
+
+    assert_eq!(stats.word_count, 450);
+    assert_eq!(stats.reading_time_minutes(), 2);
+    assert_eq!(
+        stats.reading_time_minutes_at(NonZeroUsize::new(225).unwrap()),
+        2
+    );
+    assert_eq!(
+        stats.reading_time_minutes_at(NonZeroUsize::new(150).unwrap()),
+        3
+    );
+}
+
+#[test]
+fn test_reading_time_formatted_with_hours() {
+    // Eight hours at the default reading speed
+    let stats = DocumentStats::from_markdown(&"word ".repeat(225 * 480));
+    assert_eq!(stats.reading_time_minutes(), 480);
+    assert_eq!(stats.reading_time_formatted(), "8h 0m");
+    assert_eq!(
+        stats.reading_time_formatted_at(NonZeroUsize::new(200).unwrap()),
+        "9h 0m"
+    );
+    assert!(stats.format().contains("Estimated reading time: 8h 0m\n"));
+
+    let stats = DocumentStats::from_markdown(&"word ".repeat(1350));
+    assert_eq!(stats.reading_time_formatted(), "6m");
+}
+
+#[test]
+fn test_document_stats_excluding_code() {
+    let prose = "# Setup\n\nRun the following program to get started.\n";
+    let code = format!("\n```rust\n{}```\n", "let answer = 42;\n".repeat(50));
+    let with_code = format!("{prose}{code}");
+
+    let prose_stats = DocumentStats::from_markdown_excluding_code(prose);
+    let included = DocumentStats::from_markdown(&with_code);
+    let excluded = DocumentStats::from_markdown_excluding_code(&with_code);
+
+    // Code fences are no words, so excluding code leaves only the prose
+    assert_eq!(excluded.word_count, prose_stats.word_count);
+    assert!(excluded.word_count < included.word_count);
+    assert_eq!(included.word_count - excluded.word_count, 200);
+    // The size of the source doesn't depend on what counts as text
+    assert_eq!(included.byte_count, excluded.byte_count);
+    assert_eq!(
+        included.character_count - excluded.character_count,
+        "let answer = 42;\n".len() * 50
+    );
+    // Code blocks still count as such
+    assert_eq!(excluded.code_block_count, 1);
+    assert_eq!(included.code_block_count, 1);
+}
+
+#[test]
+fn test_document_sections() {
+    use mdcat::stats::SectionStat;
+
+    let content = "Some intro text.\n\n# Intro to `mdcat`\n\nOne two three.\n\n## Details\n\nFour five.\n\n### Deep\n\n# Usage\n\nSix [seven](http://example.com) eight nine.\n";
+    let sections = DocumentStats::sections(content);
+    let expected = [
+        ("(preamble)", 0, 3),
+        ("Intro to mdcat", 1, 3),
+        ("Details", 2, 2),
+        ("Deep", 3, 0),
+        ("Usage", 1, 4),
+    ]
+    .map(|(title, level, word_count)| SectionStat {
+        title: title.to_string(),
+        level,
+        word_count,
+    });
+    similar_asserts::assert_eq!(sections, expected);
+}
+
+#[test]
+fn test_document_sections_without_headings() {
+    let sections = DocumentStats::sections("Just some text\n\nwithout any headings.\n");
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].title, "(preamble)");
+    assert_eq!(sections[0].level, 0);
+    assert_eq!(sections[0].word_count, 6);
+}
+
+#[test]
+fn test_document_sections_start_with_heading() {
+    let sections = DocumentStats::sections("# Title\n\nBody text.\n");
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].title, "Title");
+    assert_eq!(sections[0].word_count, 2);
+}
+
 #[test]
 fn test_line_number_formatter() {
     use mdcat::stats::LineNumberFormatter;
     use std::io::Write;
-    
+
     let mut formatter = LineNumberFormatter::new(true, 100);
     let mut output = Vec::new();
-    
+
     // Test line number writing
     formatter.write_line_number(&mut output).unwrap();
     write!(&mut output, "test line").unwrap();
     formatter.write_newline(&mut output).unwrap();
-    
+
     formatter.write_line_number(&mut output).unwrap();
     write!(&mut output, "another line").unwrap();
     formatter.write_newline(&mut output).unwrap();
-    
+
     let result = String::from_utf8_lossy(&output);
     assert!(result.contains("1 │ test line"));
     assert!(result.contains("2 │ another line"));
-    
+
     // Test current line tracking
     assert_eq!(formatter.current_line(), 2);
 }
@@ -73,16 +273,704 @@ fn test_line_number_formatter() {
 fn test_line_number_formatter_disabled() {
     use mdcat::stats::LineNumberFormatter;
     use std::io::Write;
-    
+
     let mut formatter = LineNumberFormatter::new(false, 100);
     let mut output = Vec::new();
-    
+
     // Test that no line numbers are added when disabled
     formatter.write_line_number(&mut output).unwrap();
     write!(&mut output, "test line").unwrap();
     formatter.write_newline(&mut output).unwrap();
-    
+
     let result = String::from_utf8_lossy(&output);
     assert!(!result.contains("│"));
     assert!(result.contains("test line"));
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_line_number_formatter_with_custom_separator() {
+    use mdcat::stats::{LineNumberFormatter, LineNumberOptions};
+
+    let options = LineNumberOptions {
+        separator: ": ".to_string(),
+        ..LineNumberOptions::default()
+    };
+    let mut formatter = LineNumberFormatter::new(true, 10).with_options(options);
+    let mut output = Vec::new();
+    formatter
+        .write_lines(&mut output, "first\nsecond\n")
+        .unwrap();
+
+    let result = String::from_utf8(output).unwrap();
+    assert_eq!(result, " 1: first\n 2: second\n");
+}
+
+#[test]
+fn test_line_number_formatter_with_colour() {
+    use anstyle::AnsiColor;
+    use mdcat::stats::{LineNumberFormatter, LineNumberOptions};
+
+    let options = LineNumberOptions {
+        colour: Some(AnsiColor::Blue),
+        ..LineNumberOptions::default()
+    };
+    let mut formatter = LineNumberFormatter::new(true, 10).with_options(options);
+    let mut output = Vec::new();
+    formatter.write_lines(&mut output, "first\n").unwrap();
+
+    let result = String::from_utf8(output).unwrap();
+    assert_eq!(result, "\u{1b}[34m 1 │ \u{1b}[0mfirst\n");
+}
+
+#[test]
+fn test_line_number_formatter_with_start() {
+    use mdcat::stats::{LineNumberFormatter, LineNumberOptions};
+
+    let options = LineNumberOptions {
+        start: 98,
+        ..LineNumberOptions::default()
+    };
+    let mut formatter = LineNumberFormatter::new(true, 3).with_options(options);
+    let mut output = Vec::new();
+    formatter.write_lines(&mut output, "a\nb\nc\n").unwrap();
+
+    let result = String::from_utf8(output).unwrap();
+    assert_eq!(result, " 98 │ a\n 99 │ b\n100 │ c\n");
+    assert_eq!(formatter.current_line(), 100);
+}
+
+#[test]
+fn test_line_numbers_preserve_structure() {
+    use mdcat::write_markdown;
+    use pulldown_cmark_mdcat::resources::NoopResourceHandler;
+    use pulldown_cmark_mdcat::{Settings, TerminalProgram, TerminalSize, Theme};
+    use syntect::parsing::SyntaxSet;
+
+    let content = "# Heading\n\n- First item\n- Second item\n\n```\nfn main() {}\n```\n";
+    let syntax_set = SyntaxSet::default();
+    let settings = Settings::new(
+        TerminalProgram::Dumb.capabilities(),
+        TerminalSize::default(),
+        &syntax_set,
+        Theme::default(),
+    );
+    let base_url = url::Url::from_directory_path(std::env::current_dir().unwrap()).unwrap();
+
+    let mut plain = Vec::new();
+    write_markdown(
+        content,
+        &base_url,
+        &settings,
+        &NoopResourceHandler,
+        &mut plain,
+        false,
+    )
+    .unwrap();
+    let plain = String::from_utf8(plain).unwrap();
+
+    let mut numbered = Vec::new();
+    write_markdown(
+        content,
+        &base_url,
+        &settings,
+        &NoopResourceHandler,
+        &mut numbered,
+        true,
+    )
+    .unwrap();
+    let numbered = String::from_utf8(numbered).unwrap();
+
+    // Every rendered line is numbered, and the rendered content is unchanged
+    let width = plain.lines().count().to_string().len();
+    let expected = plain
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:>width$} │ {line}\n", i + 1))
+        .collect::<String>();
+    similar_asserts::assert_eq!(numbered, expected);
+
+    // The heading, the list and the code block survived
+    assert!(numbered.contains("│ \u{2504}Heading"));
+    assert!(numbered.contains("│ \u{2022} First item"));
+    assert!(numbered.contains("│ \u{2022} Second item"));
+    assert!(numbered.contains("│ fn main() {}"));
+}
+
+#[test]
+fn test_document_stats_json() {
+    let content = "# Title\n\nSome words in [a link](http://example.com).\n";
+    let stats = DocumentStats::from_markdown(content);
+
+    let json = stats.to_json();
+    assert!(json.contains("\"word_count\""));
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["word_count"], stats.word_count);
+    assert_eq!(value["heading_count"], 1);
+    assert_eq!(value["link_count"], 1);
+    assert_eq!(value["reading_time_minutes"], stats.reading_time_minutes());
+}
+
+#[test]
+fn expand_glob_patterns() {
+    let expanded = expand_globs(&["sample/*.md", "-", "sample/show*.md", "README.md"]);
+    assert_eq!(
+        expanded,
+        ExpandedFilenames {
+            filenames: vec![
+                "sample/common-mark.md".to_string(),
+                "sample/showcase.md".to_string(),
+                "-".to_string(),
+                "README.md".to_string(),
+            ],
+            unmatched: vec![],
+        }
+    );
+}
+
+#[test]
+fn expand_glob_patterns_without_matches() {
+    let expanded = expand_globs(&["sample/*.rst", "does-not-exist.md", "[invalid"]);
+    assert_eq!(
+        expanded,
+        ExpandedFilenames {
+            // Plain filenames and invalid patterns remain as they are
+            filenames: vec!["does-not-exist.md".to_string(), "[invalid".to_string()],
+            unmatched: vec!["sample/*.rst".to_string()],
+        }
+    );
+}
+
+#[test]
+fn expand_glob_patterns_keeps_urls() {
+    let expanded = expand_globs(&["https://example.com/*.md?raw=true"]);
+    assert_eq!(
+        expanded.filenames,
+        vec!["https://example.com/*.md?raw=true".to_string()]
+    );
+}
+
+#[test]
+fn prefetch_images_into_cache() {
+    let (address, server) = server::serve(vec![Response::image("200 OK"); 2]);
+    let urls =
+        ["one.png", "two.png"].map(|name| Url::parse(&format!("http://{address}/{name}")).unwrap());
+
+    let cache_dir = std::env::temp_dir().join(format!("mdcat-prefetch-{}", std::process::id()));
+    let curl = CurlResourceHandler::create(1024, "mdcat-test", &CurlOptions::default()).unwrap();
+    let handler = CachingResourceHandler::new(curl, cache_dir.clone(), Duration::from_secs(60));
+    handler.prefetch(&urls);
+    server.join().unwrap();
+
+    // Without any inner handler, only cached resources are available
+    let cached = CachingResourceHandler::new(
+        NoopResourceHandler,
+        cache_dir.clone(),
+        Duration::from_secs(60),
+    );
+    let resources = urls.map(|url| cached.read_resource(&url));
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+    for resource in resources {
+        let resource = resource.unwrap();
+        assert_eq!(resource.data, b"PNG!");
+        assert_eq!(resource.mime_type_essence(), Some("image/png"));
+    }
+}
+
+#[test]
+fn slice_line_ranges() {
+    let input = (1..=40).map(|n| format!("Line {n}\n")).collect::<String>();
+    let range = |start, end| LineRange { start, end };
+
+    let lines = range(10, Some(20)).slice(&input);
+    assert_eq!(lines.lines().count(), 11);
+    assert!(lines.starts_with("Line 10\n"));
+    assert!(lines.ends_with("Line 20\n"));
+
+    let lines = range(30, None).slice(&input);
+    assert_eq!(lines.lines().count(), 11);
+    assert!(lines.starts_with("Line 30\n"));
+    assert!(lines.ends_with("Line 40\n"));
+
+    // Clamp to the end of the input
+    assert_eq!(range(39, Some(100)).slice(&input), "Line 39\nLine 40\n");
+    assert_eq!(range(50, Some(60)).slice(&input), "");
+    assert_eq!(range(50, None).slice(&input), "");
+}
+
+#[test]
+fn test_top_words() {
+    let stats = DocumentStats::from_markdown("the the cat");
+    assert_eq!(stats.top_words(1), [("cat".to_string(), 1)]);
+}
+
+#[test]
+fn test_top_words_ignore_case_and_markup() {
+    use mdcat::stats::Stopwords;
+
+    let stats = DocumentStats::from_markdown(
+        "# Dogs and cats\n\nA *dog*, a Dog, and some **ca**ts.\n\nBirds!\n",
+    );
+    assert_eq!(
+        stats.top_words(3),
+        [
+            ("cats".to_string(), 2),
+            ("dog".to_string(), 2),
+            ("birds".to_string(), 1)
+        ]
+    );
+    let stopwords = Stopwords::parse("# Custom stopwords\ncats dogs\nbirds");
+    assert_eq!(
+        stats.top_words_excluding(2, &stopwords),
+        [("a".to_string(), 2), ("and".to_string(), 2)]
+    );
+}
+
+#[test]
+fn test_flesch_reading_ease() {
+    let simple = DocumentStats::from_markdown(
+        "The cat sat on the mat. It was a warm day. The dog ran to the park and back.\n",
+    );
+    let technical = DocumentStats::from_markdown(
+        "Asynchronous initialization of heterogeneous computational infrastructure necessitates \
+         comprehensive synchronization methodologies, particularly regarding distributed \
+         transactional consistency guarantees and probabilistic verification procedures.\n",
+    );
+    assert_eq!(simple.sentence_count, 3);
+    assert_eq!(technical.sentence_count, 1);
+    let simple_score = simple.flesch_reading_ease().unwrap();
+    let technical_score = technical.flesch_reading_ease().unwrap();
+    assert!(
+        technical_score < simple_score,
+        "{technical_score} >= {simple_score}"
+    );
+    assert!(simple.format().contains("Readability (Flesch): "));
+    assert_eq!(DocumentStats::from_markdown("").flesch_reading_ease(), None);
+}
+
+#[test]
+fn test_sentences_end_at_blocks() {
+    let stats = DocumentStats::from_markdown(
+        "# A heading\n\nFirst sentence. Second\nsentence!\n\n- An item\n- Another \"item.\"\n",
+    );
+    assert_eq!(stats.sentence_count, 5);
+}
+
+#[test]
+fn test_merge_document_stats() {
+    let first_markdown = "# One\n\nSome *words* with [a link](http://example.com).\n\n- [x] Done\n";
+    let first = DocumentStats::from_markdown(first_markdown);
+    let second = DocumentStats::from_markdown(
+        "# Two\n\n## Three\n\n> Quoted words.\n\n```\ncode\n```\n\n![image](a.png)\n\n- [ ] Open\n\n| A |\n|---|\n| B |\n\nWords[^1]\n\n[^1]: Note\n",
+    );
+    let mut total = DocumentStats::from_markdown(first_markdown);
+    total.merge(&second);
+
+    assert_eq!(
+        total.character_count,
+        first.character_count + second.character_count
+    );
+    assert_eq!(total.word_count, first.word_count + second.word_count);
+    assert_eq!(
+        total.sentence_count,
+        first.sentence_count + second.sentence_count
+    );
+    assert_eq!(
+        total.syllable_count,
+        first.syllable_count + second.syllable_count
+    );
+    assert_eq!(total.line_count, first.line_count + second.line_count);
+    assert_eq!(total.heading_count, 3);
+    assert_eq!(total.code_block_count, 1);
+    assert_eq!(total.link_count, 1);
+    assert_eq!(total.image_count, 1);
+    assert_eq!(total.list_count, 2);
+    assert_eq!(total.table_count, 1);
+    assert_eq!(total.blockquote_count, 1);
+    assert_eq!(total.footnote_count, 1);
+    assert_eq!(total.tasks_total, 2);
+    assert_eq!(total.tasks_done, 1);
+    assert_eq!(total.word_frequencies["words"], 3);
+    // Reading time follows the summed word count
+    assert_eq!(
+        total.reading_time_minutes_at(NonZeroUsize::new(5).unwrap()),
+        (first.word_count + second.word_count).div_ceil(5)
+    );
+}
+
+#[test]
+fn dry_run_fails_on_missing_images() {
+    use mdcat::args::ResourceAccess;
+    use mdcat::output::Output;
+    use mdcat::{create_resource_handler, process_file, ProcessOptions, ResourceOptions};
+    use pulldown_cmark_mdcat::{Settings, TerminalProgram, TerminalSize, Theme};
+    use syntect::parsing::SyntaxSet;
+
+    let syntax_set = SyntaxSet::default();
+    let settings = Settings::new(
+        TerminalProgram::Dumb.capabilities(),
+        TerminalSize::default(),
+        &syntax_set,
+        Theme::default(),
+    );
+    let resource_handler =
+        create_resource_handler(ResourceAccess::LocalOnly, &ResourceOptions::default()).unwrap();
+    let options = ProcessOptions {
+        dry_run: true,
+        ..ProcessOptions::default()
+    };
+    let directory = std::env::temp_dir().join(format!("mdcat-dry-run-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::copy(
+        "sample/rust-logo-128x128.png",
+        directory.join("rust-logo.png"),
+    )
+    .unwrap();
+    let valid = directory.join("valid.md");
+    std::fs::write(
+        &valid,
+        "# Valid\n\n![Logo](rust-logo.png) and ![remote](https://example.com/logo.png)\n",
+    )
+    .unwrap();
+    let invalid = directory.join("invalid.md");
+    std::fs::write(&invalid, "# Invalid\n\n![Logo](missing.png)\n").unwrap();
+
+    let mut output = Output::new(false, None, false).unwrap();
+    let process = |path: &std::path::Path, output: &mut Output| {
+        process_file(
+            path.to_str().unwrap(),
+            &settings,
+            &resource_handler,
+            output,
+            &options,
+        )
+    };
+    let valid_result = process(&valid, &mut output);
+    let invalid_result = process(&invalid, &mut output);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(valid_result.is_ok(), "{valid_result:?}");
+    let error = invalid_result.unwrap_err().to_string();
+    assert!(error.contains("missing.png"), "{error}");
+}
+
+#[test]
+fn effective_columns_without_terminal() {
+    let path = std::env::temp_dir().join(format!("mdcat-columns-{}.txt", std::process::id()));
+    let output = Output::to_file(&path).unwrap();
+    assert!(!output.is_terminal());
+    assert_eq!(effective_columns(Columns::Auto, &output), DEFAULT_COLUMNS);
+    assert_eq!(DEFAULT_COLUMNS, 80);
+    assert_eq!(effective_columns(Columns::Fixed(120), &output), 120);
+    drop(output);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_line_lengths_with_wide_characters() {
+    // Nine bytes and three chars, but six columns
+    let stats = DocumentStats::from_markdown("日本語\nab\n👍👍\n");
+    assert_eq!(stats.max_line_length, 6);
+    assert_eq!(stats.average_line_length, 4.0);
+    let formatted = stats.format();
+    assert!(
+        formatted.contains("Longest line: 6 columns\n"),
+        "{formatted}"
+    );
+    assert!(
+        formatted.contains("Average line length: 4.0 columns\n"),
+        "{formatted}"
+    );
+
+    let empty = DocumentStats::from_markdown("");
+    assert_eq!(empty.max_line_length, 0);
+    assert_eq!(empty.average_line_length, 0.0);
+}
+
+#[test]
+fn test_format_summary() {
+    let stats = DocumentStats::from_markdown("# Title\n\n## Section\n\nSome words here.\n");
+    assert_eq!(stats.format_summary(), "words=5 read=1m headings=2");
+    assert_eq!(
+        stats.format_summary_at(NonZeroUsize::new(2).unwrap()),
+        "words=5 read=3m headings=2"
+    );
+}
+
+/// Serve one request for each of `statuses` in turn, with the body `PNG!`.
+fn serve_statuses(statuses: &[&'static str]) -> (Url, JoinHandle<Vec<Vec<String>>>) {
+    let (address, server) = server::serve(statuses.iter().copied().map(Response::image).collect());
+    let url = Url::parse(&format!("http://{address}/image.png")).unwrap();
+    (url, server)
+}
+
+#[test]
+fn retry_server_errors() {
+    let options = CurlOptions {
+        retries: 2,
+        ..CurlOptions::default()
+    };
+
+    let (url, server) = serve_statuses(&["503 Service Unavailable", "200 OK"]);
+    let curl = CurlResourceHandler::create(1024, "mdcat-test", &options).unwrap();
+    let resource = curl.read_resource(&url).unwrap();
+    server.join().unwrap();
+    assert_eq!(resource.data, b"PNG!");
+
+    // Client errors are permanent, so don't retry these; the next request gets the next response
+    let (url, server) = serve_statuses(&["404 Not Found", "200 OK"]);
+    let curl = CurlResourceHandler::create(1024, "mdcat-test", &options).unwrap();
+    assert!(curl.read_resource(&url).is_err());
+    assert_eq!(curl.read_resource(&url).unwrap().data, b"PNG!");
+    server.join().unwrap();
+}
+
+#[test]
+fn custom_user_agent() {
+    use mdcat::args::ResourceAccess;
+    use mdcat::{create_resource_handler, ResourceOptions};
+
+    let (url, server) = serve_statuses(&["200 OK"]);
+    let options = ResourceOptions {
+        read_limit: 1024,
+        curl: CurlOptions {
+            user_agent: Some("internal-proxy-client/1.0".to_string()),
+            ..CurlOptions::default()
+        },
+        ..ResourceOptions::default()
+    };
+    let handler = create_resource_handler(ResourceAccess::Remote, &options).unwrap();
+    assert_eq!(handler.read_resource(&url).unwrap().data, b"PNG!");
+    let headers = &server.join().unwrap()[0];
+    assert!(
+        headers.contains(&"User-Agent: internal-proxy-client/1.0".to_string()),
+        "Headers: {headers:?}"
+    );
+}
+
+#[test]
+fn sandbox_denies_local_resources() {
+    use mdcat::args::ResourceAccess;
+    use mdcat::{create_resource_handler, ResourceOptions};
+    use std::io::ErrorKind;
+
+    let image = Url::from_file_path(std::fs::canonicalize("sample/rust-logo-128x128.png").unwrap())
+        .unwrap();
+    let data = Url::parse("data:image/png;base64,UE5HIQ==").unwrap();
+    let options = ResourceOptions::default();
+
+    let local = create_resource_handler(ResourceAccess::LocalOnly, &options).unwrap();
+    assert!(local.read_resource(&image).is_ok());
+
+    let sandbox = create_resource_handler(ResourceAccess::Sandbox, &options).unwrap();
+    let error = sandbox.read_resource(&image).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Unsupported);
+    assert_eq!(sandbox.read_resource(&data).unwrap().data, b"PNG!");
+}
+
+#[test]
+fn test_document_stats_multibyte_characters() {
+    // A family emoji of three people joined with zero-width joiners, and an e with a combining
+    // acute accent
+    let content = "**\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467} cafe\u{301}**\n";
+    let stats = DocumentStats::builder()
+        .count_graphemes(true)
+        .build()
+        .analyze(content);
+    assert_eq!(stats.byte_count, 30);
+    assert_eq!(stats.character_count, 11);
+    assert_eq!(stats.grapheme_count, Some(6));
+
+    let formatted = stats.format();
+    assert!(formatted.contains("Bytes (source): 30\n"), "{formatted}");
+    assert!(formatted.contains("Characters (text): 11\n"), "{formatted}");
+    assert!(
+        formatted.contains("Characters (graphemes): 6\n"),
+        "{formatted}"
+    );
+
+    assert_eq!(DocumentStats::from_markdown(content).grapheme_count, None);
+}
+
+#[test]
+fn test_estimated_rendered_lines() {
+    let paragraph = "word ".repeat(40);
+    let stats = DocumentStats::from_markdown(paragraph.trim_end());
+    assert_eq!(stats.estimated_rendered_lines(80), 3);
+    assert_eq!(stats.estimated_rendered_lines(200), 1);
+
+    // A heading, an empty line, two list items, an empty line, and a code block with two lines
+    // between borders
+    let stats = DocumentStats::from_markdown("# Title\n\n- one\n- two\n\n```\nfoo\nbar\n```\n");
+    assert_eq!(stats.estimated_rendered_lines(80), 9);
+
+    let options = StatsOptions {
+        columns: Some(80),
+        ..StatsOptions::default()
+    };
+    let formatted = format_document_stats(&stats, None, &options);
+    assert!(
+        formatted.contains("Estimated rendered lines: 9 at 80 columns\n"),
+        "{formatted}"
+    );
+    assert!(!stats.format().contains("Estimated rendered lines"));
+}
+
+#[test]
+fn test_merged_stats() {
+    use mdcat::{process_file, process_merged, ProcessOptions};
+    use pulldown_cmark_mdcat::{Settings, TerminalProgram, TerminalSize, Theme};
+    use syntect::parsing::SyntaxSet;
+
+    let syntax_set = SyntaxSet::default();
+    let settings = Settings::new(
+        TerminalProgram::Dumb.capabilities(),
+        TerminalSize::default(),
+        &syntax_set,
+        Theme::default(),
+    );
+    let options = ProcessOptions {
+        stats: Some(StatsOptions::default()),
+        ..ProcessOptions::default()
+    };
+    let mut output = Output::new(false, None, false).unwrap();
+    let first = process_file(
+        "sample/common-mark.md",
+        &settings,
+        &NoopResourceHandler,
+        &mut output,
+        &options,
+    )
+    .unwrap()
+    .unwrap();
+    let second = process_file(
+        "sample/showcase.md",
+        &settings,
+        &NoopResourceHandler,
+        &mut output,
+        &options,
+    )
+    .unwrap()
+    .unwrap();
+    let merged = process_merged(
+        &["sample/common-mark.md", "sample/showcase.md"],
+        &settings,
+        &NoopResourceHandler,
+        &mut output,
+        &options,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(merged.word_count, first.word_count + second.word_count);
+    assert_eq!(
+        merged.heading_count,
+        first.heading_count + second.heading_count
+    );
+    assert_eq!(
+        merged.byte_count,
+        first.byte_count + second.byte_count + "\n\n".len()
+    );
+}
+
+#[test]
+fn test_syntax_theme_per_language() {
+    use mdcat::render_markdown;
+    use pulldown_cmark_mdcat::resources::NoopResourceHandler;
+    use pulldown_cmark_mdcat::{
+        Environment, Settings, SyntaxTheme, SyntaxThemes, TerminalProgram, TerminalSize, Theme,
+    };
+    use syntect::parsing::SyntaxSet;
+
+    let content = "```sql\nSELECT name FROM users;\n```\n\n```rust\nfn main() {}\n```\n";
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let render = |syntax_themes: SyntaxThemes| {
+        let settings = Settings::new(
+            TerminalProgram::Ansi.capabilities(),
+            TerminalSize::default(),
+            &syntax_set,
+            Theme::default(),
+        )
+        .with_syntax_themes(syntax_themes);
+        let env = Environment::for_local_directory(&std::env::current_dir().unwrap()).unwrap();
+        render_markdown(content, &settings, &env, &NoopResourceHandler).unwrap()
+    };
+    let colourful = |rendered: &str, code: &str| {
+        let line = rendered.lines().find(|line| line.contains(code)).unwrap();
+        // Foreground colours, e.g. \x1b[34m
+        line.contains("\x1b[3") || line.contains("\x1b[9")
+    };
+
+    let themes = SyntaxThemes {
+        default: SyntaxTheme::Solarized,
+        languages: [("sql".to_string(), SyntaxTheme::Monochrome)].into(),
+    };
+    assert_eq!(themes.for_language(["SQL"]), SyntaxTheme::Monochrome);
+    assert_eq!(themes.for_language(["rust"]), SyntaxTheme::Solarized);
+    let rendered = render(themes);
+    assert!(!colourful(&rendered, "SELECT"), "{rendered:?}");
+    assert!(colourful(&rendered, "main"), "{rendered:?}");
+
+    let rendered = render(SyntaxThemes::default());
+    assert!(colourful(&rendered, "SELECT"), "{rendered:?}");
+}
+
+#[test]
+fn resolve_host_to_local_server() {
+    let (mut url, server) = serve_statuses(&["200 OK"]);
+    let port = url.port().unwrap();
+    url.set_host(Some("images.example.invalid")).unwrap();
+    let options = CurlOptions {
+        resolve: vec![format!("images.example.invalid:{port}:127.0.0.1")],
+        ..CurlOptions::default()
+    };
+    let curl = CurlResourceHandler::create(1024, "mdcat-test", &options).unwrap();
+    assert_eq!(curl.read_resource(&url).unwrap().data, b"PNG!");
+    server.join().unwrap();
+}
+
+#[test]
+fn test_math_count() {
+    use pulldown_cmark::{Event, Options, Parser};
+
+    let markdown = "Square $x^2$ and sum:\n\n$$\\sum_i i$$\n\nCosts $5.\n";
+    assert!(Parser::new_ext(markdown, Options::ENABLE_MATH)
+        .any(|event| event == Event::InlineMath("x^2".into())));
+
+    let stats = DocumentStats::builder()
+        .math(true)
+        .build()
+        .analyze(markdown);
+    assert_eq!(stats.math_count, 2);
+    assert!(stats.format().contains("Math: 2\n"), "{}", stats.format());
+    // Math is off by default, so dollar signs are just text
+    assert_eq!(DocumentStats::from_markdown(markdown).math_count, 0);
+}
+
+#[test]
+fn test_distinct_and_external_link_count() {
+    let markdown =
+        "See [docs](https://example.com/docs) and [again](https://example.com/docs).\n\n\
+                    Also [the guide](guide.md) and [HTTP](HTTP://example.com).\n";
+    let stats = DocumentStats::from_markdown(markdown);
+    assert_eq!(stats.link_count, 4);
+    assert_eq!(stats.distinct_link_count, 3);
+    assert_eq!(stats.external_link_count, 3);
+    assert!(
+        stats
+            .format()
+            .contains("Links: 4 (3 distinct, 3 external, 1 internal)\n"),
+        "{}",
+        stats.format()
+    );
+
+    let same = DocumentStats::from_markdown("[a](https://example.com) [b](https://example.com)");
+    assert_eq!(same.link_count, 2);
+    assert_eq!(same.distinct_link_count, 1);
+
+    // Distinct destinations stay distinct across merged documents
+    let mut total = DocumentStats::from_markdown(markdown);
+    total.merge(&same);
+    assert_eq!(total.link_count, 6);
+    assert_eq!(total.distinct_link_count, 4);
+    assert_eq!(total.external_link_count, 5);
+}
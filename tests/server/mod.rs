@@ -0,0 +1,160 @@
+// Copyright 2025 Tachera Sasi <tachera@ekilie.com>
+
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A minimal HTTP server for tests.
+
+// Every test crate uses only some of these helpers.
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long to wait for a client, so that a broken test fails instead of hanging.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A canned HTTP response.
+#[derive(Debug, Clone, Copy)]
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: &'static str,
+}
+
+impl Response {
+    /// A successful response with `body` of the given `content_type`.
+    pub const fn ok(content_type: &'static str, body: &'static str) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    /// A response with `status` and a tiny PNG image as body.
+    pub const fn image(status: &'static str) -> Self {
+        Self {
+            status,
+            content_type: "image/png",
+            body: "PNG!",
+        }
+    }
+}
+
+fn listen() -> TcpListener {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    listener
+}
+
+/// Accept a connection on `listener`, or panic if no client connects in time.
+fn accept(listener: &TcpListener) -> TcpStream {
+    accept_within(listener, TIMEOUT).expect("No client connected")
+}
+
+/// Accept a connection on `listener`, or return `None` if no client connects within `timeout`.
+fn accept_within(listener: &TcpListener, timeout: Duration) -> Option<TcpStream> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false).unwrap();
+                stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+                return Some(stream);
+            }
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                if deadline <= Instant::now() {
+                    return None;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(error) => panic!("Failed to accept connection: {error}"),
+        }
+    }
+}
+
+/// Write `response` to `stream`.
+fn respond(stream: &mut TcpStream, response: Response) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )
+}
+
+/// Read the request line and all headers of a request from `stream`.
+fn read_request_head(stream: &TcpStream) -> Vec<String> {
+    let mut reader = BufReader::new(stream);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    // The empty line which ends the headers
+    while reader.read_line(&mut line).unwrap() > 2 {
+        lines.push(line.trim_end().to_string());
+        line.clear();
+    }
+    lines
+}
+
+/// Serve one request for each of `responses` in turn on a local port.
+///
+/// Return the address of the server, and a handle to the thread serving the requests.  The
+/// thread returns the request line and headers of every request it served.
+pub fn serve(responses: Vec<Response>) -> (SocketAddr, JoinHandle<Vec<Vec<String>>>) {
+    let listener = listen();
+    let address = listener.local_addr().unwrap();
+    let handle = std::thread::spawn(move || {
+        responses
+            .into_iter()
+            .map(|response| {
+                let mut stream = accept(&listener);
+                let request = read_request_head(&stream);
+                respond(&mut stream, response).unwrap();
+                request
+            })
+            .collect()
+    });
+    (address, handle)
+}
+
+/// Accept a single connection on a local port, and never respond.
+///
+/// Return the address of the server, and a handle to the thread which holds the connection
+/// until the client gives up and closes it.
+pub fn serve_slowly() -> (SocketAddr, JoinHandle<()>) {
+    let listener = listen();
+    let address = listener.local_addr().unwrap();
+    let handle = std::thread::spawn(move || {
+        let mut stream = accept(&listener);
+        // Fails with a timeout if the client never hangs up
+        let _ = stream.read_to_end(&mut Vec::new());
+    });
+    (address, handle)
+}
+
+/// Serve `response` to every request after `delay`, until no client connects for a second.
+///
+/// Return the address of the server, and a handle to the thread serving the requests.  The
+/// thread returns the number of requests it served.
+pub fn serve_with_delay(response: Response, delay: Duration) -> (SocketAddr, JoinHandle<usize>) {
+    let listener = listen();
+    let address = listener.local_addr().unwrap();
+    let handle = std::thread::spawn(move || {
+        let mut served = 0;
+        while let Some(mut stream) = accept_within(&listener, Duration::from_secs(1)) {
+            read_request_head(&stream);
+            std::thread::sleep(delay);
+            // The client may have given up in the meantime
+            let _ = respond(&mut stream, response);
+            served += 1;
+        }
+        served
+    });
+    (address, handle)
+}